@@ -0,0 +1,481 @@
+// alternates ranks replacement candidates for a part by comparing normalised parameters,
+// category, package and the pin tables of the datasheets across all vendors in the database
+
+use crate::db::{slug, Database, Part};
+use crate::package::Package;
+use crate::param::{Param, Value};
+use crate::pinout::{Pin, Pinout};
+use crate::taxonomy;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Match,
+    Better,
+    Worse,
+    Differs,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Comparison {
+    pub param: String,
+    pub base: String,
+    pub candidate: String,
+    pub verdict: Verdict,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Candidate<'a> {
+    pub part: &'a Part,
+    pub score: f64,
+    pub comparisons: Vec<Comparison>,
+}
+
+// parameters where less is better, matched against whole words of the unified name
+const LOWER_IS_BETTER: &[&str] = &[
+    "offset",
+    "noise",
+    "drift",
+    "bias",
+    "iq",
+    "quiescent",
+    "supply_current",
+    "current_consumption",
+    "power_consumption",
+    "dropout",
+    "rds_on",
+    "ron",
+    "leakage",
+    "delay",
+    "rise_time",
+    "fall_time",
+    "thd",
+    "price",
+];
+
+const HIGHER_IS_BETTER: &[&str] = &[
+    "gbw",
+    "bandwidth",
+    "slew",
+    "cmrr",
+    "psrr",
+    "resolution",
+    "sample_rate",
+    "output_current",
+    "frequency",
+    "speed",
+    "flash",
+    "ram",
+    "memory",
+    "channels",
+    "snr",
+    "enob",
+];
+
+// values closer than this are the same for all practical purposes
+const TOLERANCE: f64 = 0.05;
+
+// a candidate has to share at least this many parameters with the part to be ranked
+const MIN_SHARED: usize = 2;
+
+pub fn find<'a>(db: &'a Database, base: &Part) -> Vec<Candidate<'a>> {
+    let base_params = by_name(base);
+    let base_packages = base.packages();
+
+    let mut out: Vec<Candidate> = db
+        .parts
+        .iter()
+        .filter(|p| p.mpn != base.mpn || p.vendor != base.vendor)
+        .filter(|p| same_category(base, p))
+        .filter_map(|p| {
+            let params = by_name(p);
+            let mut comparisons: Vec<Comparison> = base_params
+                .iter()
+                .filter_map(|(s, (name, bp))| {
+                    params.get(s).map(|(_, cp)| Comparison {
+                        param: name.to_string(),
+                        base: bp.raw.clone(),
+                        candidate: cp.raw.clone(),
+                        verdict: compare(s, &bp.value, &cp.value),
+                    })
+                })
+                .collect();
+
+            if comparisons.len() < MIN_SHARED.min(base_params.len()).max(1) {
+                return None;
+            }
+
+            let pkgs = p.packages();
+            if !base_packages.is_empty() && !pkgs.is_empty() {
+                // footprint compatible in at least one package, or no replacement
+                if !base_packages
+                    .iter()
+                    .any(|a| pkgs.iter().any(|b| a.compatible(b)))
+                {
+                    return None;
+                }
+                comparisons.push(Comparison {
+                    param: String::from("package"),
+                    base: describe(&base_packages),
+                    candidate: describe(&pkgs),
+                    verdict: Verdict::Match,
+                });
+            }
+
+            if !base.pins.is_empty() && !p.pins.is_empty() {
+                // drop-in if a pin table of each has the same pins with the same functions
+                let same = base
+                    .pins
+                    .iter()
+                    .any(|a| p.pins.iter().any(|b| same_pinout(a, b)));
+                comparisons.push(Comparison {
+                    param: String::from("pinout"),
+                    base: describe_pins(&base.pins),
+                    candidate: describe_pins(&p.pins),
+                    verdict: if same {
+                        Verdict::Match
+                    } else {
+                        Verdict::Differs
+                    },
+                });
+            }
+
+            Some(Candidate {
+                score: score(&comparisons, p.lifecycle_risk().is_some()),
+                part: p,
+                comparisons,
+            })
+        })
+        .collect();
+
+    out.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap()
+            .then_with(|| b.comparisons.len().cmp(&a.comparisons.len()))
+    });
+    out
+}
+
+// parts are comparable if their unified categories agree, the vendor categories are only
+// used when one of them couldn't be mapped
+fn same_category(a: &Part, b: &Part) -> bool {
+    match (&a.unified_category, &b.unified_category) {
+        (Some(x), Some(y)) => x == y,
+        _ => match (&a.category, &b.category) {
+            (Some(x), Some(y)) => x.eq_ignore_ascii_case(y),
+            _ => true,
+        },
+    }
+}
+
+pub fn count(c: &Candidate, v: Verdict) -> usize {
+    c.comparisons.iter().filter(|x| x.verdict == v).count()
+}
+
+// fraction of compared parameters that are at least as good, a part with lifecycle risk
+// is no replacement for one that is going away
+fn score(comparisons: &[Comparison], risky: bool) -> f64 {
+    let good = comparisons
+        .iter()
+        .map(|c| match c.verdict {
+            Verdict::Match | Verdict::Better => 1.0,
+            Verdict::Differs => 0.25,
+            Verdict::Worse => 0.0,
+        })
+        .sum::<f64>();
+    let s = good / comparisons.len() as f64;
+    if risky {
+        s / 2.0
+    } else {
+        s
+    }
+}
+
+fn compare(name: &str, base: &Value, cand: &Value) -> Verdict {
+    if let (Value::Enum { values: a }, Value::Enum { values: b }) = (base, cand) {
+        let a: BTreeSet<String> = a.iter().map(|x| x.to_lowercase()).collect();
+        let b: BTreeSet<String> = b.iter().map(|x| x.to_lowercase()).collect();
+        return if a == b {
+            Verdict::Match
+        } else if a.is_subset(&b) {
+            Verdict::Better
+        } else {
+            Verdict::Differs
+        };
+    }
+
+    let ((bmin, bmax), (cmin, cmax)) = match (base.bounds(), cand.bounds()) {
+        (Some(b), Some(c)) if base.unit() == cand.unit() => (b, c),
+        _ => return Verdict::Differs,
+    };
+
+    if close(bmin, cmin) && close(bmax, cmax) {
+        return Verdict::Match;
+    }
+
+    // operating ranges are better when they cover the original one
+    if matches!(base, Value::Range { .. }) || matches!(cand, Value::Range { .. }) {
+        return if cmin <= bmin && cmax >= bmax {
+            Verdict::Better
+        } else if cmin >= bmin && cmax <= bmax {
+            Verdict::Worse
+        } else {
+            Verdict::Differs
+        };
+    }
+
+    let higher = cmax > bmax;
+    if LOWER_IS_BETTER.iter().any(|k| has_word(name, k)) {
+        if higher {
+            Verdict::Worse
+        } else {
+            Verdict::Better
+        }
+    } else if HIGHER_IS_BETTER.iter().any(|k| has_word(name, k)) {
+        if higher {
+            Verdict::Better
+        } else {
+            Verdict::Worse
+        }
+    } else {
+        Verdict::Differs
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    a == b || (a - b).abs() <= TOLERANCE * a.abs().max(b.abs())
+}
+
+// "ram" is in "ram_kb" but not in "program_memory"
+fn has_word(name: &str, word: &str) -> bool {
+    format!("_{}_", name).contains(&format!("_{}_", word))
+}
+
+// numeric and enum parameters keyed by their unified name, text without structure can't be
+// compared, the first of two names that unify wins
+fn by_name(p: &Part) -> BTreeMap<String, (&String, &Param)> {
+    let mut out = BTreeMap::new();
+    for (name, v) in &p.params {
+        let s = slug(name);
+        if matches!(v.value, Value::Empty) || s.contains("package") || s.contains("status") {
+            continue;
+        }
+        out.entry(taxonomy::parameter(name)).or_insert((name, v));
+    }
+    out
+}
+
+fn describe(packages: &[Package]) -> String {
+    packages
+        .iter()
+        .map(|p| p.name.clone())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// the same pin count and the same function on every number, the types are compared where both
+// tables have them and the names otherwise
+fn same_pinout(a: &Pinout, b: &Pinout) -> bool {
+    let (a, b) = (by_number(a), by_number(b));
+    a.len() == b.len()
+        && a.iter().all(|(n, x)| {
+            b.get(n).is_some_and(|y| match (x.kind, y.kind) {
+                (Some(k), Some(l)) => k == l,
+                _ => x.name.eq_ignore_ascii_case(&y.name),
+            })
+        })
+}
+
+// a number twice is a mistake of the table, the first row wins
+fn by_number(t: &Pinout) -> BTreeMap<String, &Pin> {
+    let mut m = BTreeMap::new();
+    for p in &t.pins {
+        m.entry(p.number.to_uppercase()).or_insert(p);
+    }
+    m
+}
+
+// "8 pins (D, DGK)" per table
+fn describe_pins(pinouts: &[Pinout]) -> String {
+    pinouts
+        .iter()
+        .map(|t| match &t.package {
+            Some(p) => format!("{} pins ({})", by_number(t).len(), p),
+            None => format!("{} pins", by_number(t).len()),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pinout::PinType;
+
+    fn number(raw: &str, unit: &str) -> Value {
+        Param::parse_with_unit(raw, Some(unit)).value
+    }
+
+    fn part(vendor: &str, mpn: &str, params: &[(&str, &str, &str)]) -> Part {
+        Part {
+            vendor: vendor.to_string(),
+            mpn: mpn.to_string(),
+            category: None,
+            category_path: Vec::new(),
+            unified_category: Some(String::from("Operational amplifiers")),
+            also: Vec::new(),
+            conflicts: Vec::new(),
+            url: None,
+            params: params
+                .iter()
+                .map(|(name, raw, unit)| {
+                    (name.to_string(), Param::parse_with_unit(raw, Some(unit)))
+                })
+                .collect(),
+            orderables: Vec::new(),
+            models: Vec::new(),
+            packs: Vec::new(),
+            cad: Vec::new(),
+            datasheet_meta: None,
+            pins: Vec::new(),
+            characteristics: Vec::new(),
+        }
+    }
+
+    fn pinout(pins: &[(&str, &str, Option<PinType>)]) -> Pinout {
+        Pinout {
+            vendor: String::from("ti"),
+            part: String::from("X"),
+            package: Some(String::from("D")),
+            source: String::from("x.pdf"),
+            page: 3,
+            pins: pins
+                .iter()
+                .map(|(number, name, kind)| Pin {
+                    number: number.to_string(),
+                    name: name.to_string(),
+                    kind: *kind,
+                    description: String::new(),
+                    uncertain: None,
+                })
+                .collect(),
+            missing: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn lower_and_higher_is_better() {
+        let (a, b) = (number("100", "µV"), number("50", "µV"));
+        assert_eq!(compare("offset_voltage_max", &a, &b), Verdict::Better);
+        assert_eq!(compare("offset_voltage_max", &b, &a), Verdict::Worse);
+        assert_eq!(compare("gbw_typ", &a, &b), Verdict::Worse);
+        // within the tolerance
+        let c = number("102", "µV");
+        assert_eq!(compare("offset_voltage_max", &a, &c), Verdict::Match);
+        // neither list knows it
+        assert_eq!(compare("output_voltage", &a, &b), Verdict::Differs);
+    }
+
+    #[test]
+    fn whole_words() {
+        let (a, b) = (number("64", "kB"), number("128", "kB"));
+        assert_eq!(compare("ram", &a, &b), Verdict::Better);
+        // "ram" in "program" and "param", "ron" in "electronic"
+        assert_eq!(compare("program_time", &a, &b), Verdict::Differs);
+        assert_eq!(compare("param_count", &a, &b), Verdict::Differs);
+        assert_eq!(compare("electronic_trim", &a, &b), Verdict::Differs);
+    }
+
+    #[test]
+    fn ranges_and_units() {
+        let base = number("-40 to 85", "°C");
+        assert_eq!(
+            compare("operating_temperature", &base, &number("-40 to 125", "°C")),
+            Verdict::Better
+        );
+        assert_eq!(
+            compare("operating_temperature", &base, &number("0 to 70", "°C")),
+            Verdict::Worse
+        );
+        assert_eq!(
+            compare("gbw_typ", &number("10", "MHz"), &number("10", "V")),
+            Verdict::Differs
+        );
+        let enums = |raw: &str| Param::parse(raw).value;
+        assert_eq!(
+            compare("rail_to_rail", &enums("In"), &enums("In, Out")),
+            Verdict::Better
+        );
+    }
+
+    #[test]
+    fn pinouts() {
+        let a = pinout(&[
+            ("1", "OUT", Some(PinType::Output)),
+            ("2", "IN-", Some(PinType::Input)),
+            ("3", "V+", Some(PinType::Power)),
+        ]);
+        // other names, the same functions
+        let b = pinout(&[
+            ("1", "OUT1", Some(PinType::Output)),
+            ("2", "IN1-", Some(PinType::Input)),
+            ("3", "VCC+", Some(PinType::Power)),
+        ]);
+        assert!(same_pinout(&a, &b));
+        let swapped = pinout(&[
+            ("1", "V+", Some(PinType::Power)),
+            ("2", "IN-", Some(PinType::Input)),
+            ("3", "OUT", Some(PinType::Output)),
+        ]);
+        assert!(!same_pinout(&a, &swapped));
+        // without types the names decide
+        let untyped = pinout(&[("1", "out", None), ("2", "IN-", None), ("3", "V+", None)]);
+        assert!(same_pinout(&a, &untyped));
+        assert!(!same_pinout(&a, &pinout(&[("1", "OUT", None)])));
+        assert_eq!(describe_pins(&[a]), "3 pins (D)");
+    }
+
+    #[test]
+    fn ti_and_st_compare() {
+        let ti = part(
+            "ti",
+            "OPA2197",
+            &[
+                ("GBW (Typ) (MHz)", "10", "MHz"),
+                ("Slew rate (Typ) (V/µs)", "20", "V/µs"),
+                ("Vos (offset voltage at 25°C) (Max) (mV)", "0.1", "mV"),
+            ],
+        );
+        let st = part(
+            "st",
+            "TSV912",
+            &[
+                ("GBP typ (MHz)", "8", "MHz"),
+                ("Slew Rate typ (V/µs)", "4.5", "V/µs"),
+                ("Input Offset Voltage (25°C) max (mV)", "4.5", "mV"),
+            ],
+        );
+        let mut db = Database::default();
+        db.parts = vec![ti.clone(), st];
+        let found = find(&db, &ti);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].part.mpn, "TSV912");
+        let params: Vec<&str> = found[0]
+            .comparisons
+            .iter()
+            .map(|c| c.param.as_str())
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                "GBW (Typ) (MHz)",
+                "Vos (offset voltage at 25°C) (Max) (mV)",
+                "Slew rate (Typ) (V/µs)"
+            ]
+        );
+        assert_eq!(count(&found[0], Verdict::Worse), 3);
+    }
+}
@@ -0,0 +1,1334 @@
+extern crate serde;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use indicatif::ProgressBar;
+use llh::alternates::{self, Verdict};
+use llh::bom;
+use llh::category::Category;
+use llh::characteristics::{self, Spec, Table};
+use llh::db::{Database, Match, Part};
+use llh::document::{self, Document, DocumentKind, Kinds};
+use llh::errata::ChangeKind;
+use llh::fulltext::{self, Index};
+use llh::history;
+use llh::kicad;
+use llh::model::{ModelFile, ModelType};
+use llh::orderable::Orderable;
+use llh::pack::{self, PackFile, PackKind};
+use llh::pdfmeta::{self, PdfMeta};
+use llh::pinout::{self, Pinout};
+use llh::search::{self, Filter, Format};
+use llh::watch;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn main() {
+    let matches = App::new("llh")
+        .version(llh::VERSION)
+        .about("Works with the archive built by the crawlers")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("Parametric search over the local database")
+                .arg(
+                    Arg::with_name("filter")
+                        .multiple(true)
+                        .help("Filters like category=\"Op amps\" gbw>10MHz package~SOIC"),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .short("s")
+                        .long("sort")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Column to sort by, prefix with - to sort descending"),
+                )
+                .arg(
+                    Arg::with_name("columns")
+                        .short("c")
+                        .long("columns")
+                        .takes_value(true)
+                        .use_delimiter(true)
+                        .help("Comma separated columns to show"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "csv", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .short("n")
+                        .long("limit")
+                        .takes_value(true)
+                        .help("Only show the first n results"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("index")
+                .about("Extracts the text of new or changed PDFs into the full-text index")
+                .arg(
+                    Arg::with_name("dir")
+                        .multiple(true)
+                        .default_value("pdf")
+                        .help("Directories to scan for PDFs"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("grep")
+                .about("Searches the text of the indexed PDFs")
+                .arg(
+                    Arg::with_name("query")
+                        .required(true)
+                        .multiple(true)
+                        .help("Words that all have to appear on the same page"),
+                )
+                .arg(
+                    Arg::with_name("prefix")
+                        .short("p")
+                        .long("prefix")
+                        .takes_value(true)
+                        .help("Only search documents below this path, e.g. pdf/ti/lit"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .short("n")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value("50")
+                        .help("Maximum number of matching pages"),
+                )
+                .arg(
+                    Arg::with_name("kind")
+                        .short("k")
+                        .long("kind")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .takes_value(true)
+                        .help("Only search documents of this kind, e.g. errata, can be repeated"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the matches as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("docs")
+                .about("Lists the technical documents of the vendors by kind")
+                .arg(
+                    Arg::with_name("words")
+                        .multiple(true)
+                        .help("Words that all have to appear in the title"),
+                )
+                .arg(
+                    Arg::with_name("kind")
+                        .short("k")
+                        .long("kind")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .takes_value(true)
+                        .help("Only list documents of this kind, e.g. user_guide, can be repeated"),
+                )
+                .arg(
+                    Arg::with_name("part")
+                        .short("p")
+                        .long("part")
+                        .takes_value(true)
+                        .help("Only list documents linked from the page of this part"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "csv", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pins")
+                .about("Lists the pins of a part from the pin table of its datasheet")
+                .arg(Arg::with_name("mpn").required(true).help("Part number"))
+                .arg(
+                    Arg::with_name("package")
+                        .short("p")
+                        .long("package")
+                        .takes_value(true)
+                        .help("Only the table of this package, e.g. DGK"),
+                )
+                .arg(
+                    Arg::with_name("uncertain")
+                        .short("u")
+                        .long("uncertain")
+                        .help("Only the rows that need checking against the datasheet"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "csv", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("specs")
+                .about("Lists the electrical characteristics and absolute maximum ratings of a part")
+                .arg(Arg::with_name("mpn").required(true).help("Part number"))
+                .arg(
+                    Arg::with_name("table")
+                        .short("t")
+                        .long("table")
+                        .takes_value(true)
+                        .possible_values(&["electrical", "absolute_maximum"])
+                        .help("Only the rows of this table"),
+                )
+                .arg(
+                    Arg::with_name("words")
+                        .short("w")
+                        .long("words")
+                        .multiple(true)
+                        .takes_value(true)
+                        .help("Words that all have to appear in the parameter"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "csv", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("kicad")
+                .about("Writes a KiCad symbol library from the pin tables of the datasheets")
+                .arg(
+                    Arg::with_name("mpn")
+                        .multiple(true)
+                        .required_unless("list")
+                        .help("Part numbers"),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .short("l")
+                        .long("list")
+                        .takes_value(true)
+                        .help("A file with a part number per line, # starts a comment"),
+                )
+                .arg(
+                    Arg::with_name("package")
+                        .short("p")
+                        .long("package")
+                        .takes_value(true)
+                        .help("Only the symbols of this package, e.g. DGK"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .default_value("llh.kicad_sym")
+                        .help("The library to write"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bom")
+                .about("Works with bills of materials")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("Resolves a CSV BOM against the archive and flags risks")
+                        .arg(
+                            Arg::with_name("bom")
+                                .required(true)
+                                .help("CSV file with a header row"),
+                        )
+                        .arg(
+                            Arg::with_name("column")
+                                .long("column")
+                                .takes_value(true)
+                                .help("Header of the MPN column, guessed if not given"),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .short("f")
+                                .long("format")
+                                .takes_value(true)
+                                .possible_values(&["table", "csv", "json"])
+                                .default_value("table")
+                                .help("Output format"),
+                        )
+                        .arg(
+                            Arg::with_name("zip")
+                                .long("zip")
+                                .takes_value(true)
+                                .help("Write all referenced datasheets into this zip file"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("alternates")
+                .about("Ranks cross-vendor replacement candidates for a part")
+                .arg(
+                    Arg::with_name("mpn")
+                        .required(true)
+                        .help("Generic or orderable part number"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .short("n")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value("10")
+                        .help("Number of candidates to show"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the candidates with all comparisons as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("models")
+                .about("Lists the simulation models archived for a part")
+                .arg(
+                    Arg::with_name("mpn")
+                        .required(true)
+                        .help("Generic or orderable part number"),
+                )
+                .arg(
+                    Arg::with_name("type")
+                        .short("t")
+                        .long("type")
+                        .takes_value(true)
+                        .possible_values(&["spice", "pspice", "tina", "ibis"])
+                        .help("Only list models of this type"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the models as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("packs")
+                .about("Lists the CMSIS pack and SVD files archived for an MCU")
+                .arg(
+                    Arg::with_name("mpn")
+                        .required(true)
+                        .help("Part number or pack device name"),
+                )
+                .arg(
+                    Arg::with_name("svd")
+                        .long("svd")
+                        .help("Only print the SVD paths, e.g. for svd2rust -i"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the files as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cad")
+                .about("Shows where to get the symbol, footprint and 3D model of each orderable")
+                .arg(
+                    Arg::with_name("mpn")
+                        .required(true)
+                        .help("Generic or orderable part number"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "csv", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about("Reports the parts of a list that gained new or revised errata, or a digest of all their changes")
+                .arg(
+                    Arg::with_name("list")
+                        .required(true)
+                        .help("File with one MPN per line, # starts a comment"),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .takes_value(true)
+                        .help("Date or RFC 3339 time, defaults to the crawl before the last"),
+                )
+                .arg(
+                    Arg::with_name("digest")
+                        .long("digest")
+                        .help("Lifecycle, document and parameter changes since the last digest"),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .short("o")
+                        .long("out")
+                        .takes_value(true)
+                        .requires("digest")
+                        .help("Also write the digest as Markdown and JSON into this directory"),
+                )
+                .arg(
+                    Arg::with_name("spool")
+                        .long("spool")
+                        .takes_value(true)
+                        .requires_all(&["digest", "mail-from", "mail-to"])
+                        .help("Write the digest as a mail into this spool directory"),
+                )
+                .arg(
+                    Arg::with_name("mail-from")
+                        .long("mail-from")
+                        .takes_value(true)
+                        .help("Sender address of the spooled mail"),
+                )
+                .arg(
+                    Arg::with_name("mail-to")
+                        .long("mail-to")
+                        .takes_value(true)
+                        .help("Recipient address of the spooled mail"),
+                )
+                .arg(
+                    Arg::with_name("webhook")
+                        .long("webhook")
+                        .takes_value(true)
+                        .requires("digest")
+                        .help("Write the webhook payload of the digest into this directory"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "csv", "json"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tree")
+                .about("Prints the category tree of each vendor with part counts")
+                .arg(
+                    Arg::with_name("vendor")
+                        .multiple(true)
+                        .help("Only print these vendors"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the trees as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Serves the archive as a web UI and JSON API")
+                .arg(
+                    Arg::with_name("listen")
+                        .short("l")
+                        .long("listen")
+                        .takes_value(true)
+                        .default_value("127.0.0.1:8080")
+                        .help("Address to listen on"),
+                ),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        ("search", Some(m)) => cmd_search(m),
+        ("index", Some(m)) => cmd_index(m),
+        ("grep", Some(m)) => cmd_grep(m),
+        ("docs", Some(m)) => cmd_docs(m),
+        ("pins", Some(m)) => cmd_pins(m),
+        ("kicad", Some(m)) => cmd_kicad(m),
+        ("specs", Some(m)) => cmd_specs(m),
+        ("bom", Some(m)) => match m.subcommand() {
+            ("check", Some(m)) => cmd_bom_check(m),
+            _ => unreachable!(),
+        },
+        ("alternates", Some(m)) => cmd_alternates(m),
+        ("models", Some(m)) => cmd_models(m),
+        ("packs", Some(m)) => cmd_packs(m),
+        ("cad", Some(m)) => cmd_cad(m),
+        ("watch", Some(m)) => cmd_watch(m),
+        ("tree", Some(m)) => cmd_tree(m),
+        ("serve", Some(m)) => cmd_serve(m),
+        _ => unreachable!(),
+    }
+}
+
+fn cmd_search(m: &ArgMatches) {
+    let filters: Vec<Filter> = match m
+        .values_of("filter")
+        .map(|v| v.map(Filter::parse).collect())
+        .unwrap_or_else(|| Ok(Vec::new()))
+    {
+        Err(why) => {
+            eprintln!("{}", why);
+            std::process::exit(1);
+        }
+        Ok(f) => f,
+    };
+
+    // default to the identifying columns plus everything that was filtered on
+    let columns: Vec<String> = match m.values_of("columns") {
+        Some(c) => c.map(String::from).collect(),
+        None => {
+            let mut c: Vec<String> = vec!["vendor".into(), "mpn".into(), "category".into()];
+            for f in &filters {
+                if !c.contains(&f.field) {
+                    c.push(f.field.clone());
+                }
+            }
+            c
+        }
+    };
+
+    let db = Database::load(Path::new("."));
+    let mut parts = search::search(&db, &filters);
+
+    if let Some(key) = m.value_of("sort") {
+        search::sort(&mut parts, key);
+    }
+
+    if let Some(n) = m.value_of("limit") {
+        let n: usize = n.parse().expect("limit has to be a number");
+        parts.truncate(n);
+    }
+
+    let format = Format::parse(m.value_of("format").unwrap()).unwrap();
+    println!("{}", search::render(&parts, &columns, format));
+}
+
+type PanicHook = dyn Fn(&std::panic::PanicHookInfo) + Send + Sync;
+
+thread_local! {
+    // set while a worker of cmd_index extracts a PDF
+    static EXTRACTING: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+fn cmd_index(m: &ArgMatches) {
+    let root = Path::new(".");
+    let mut index = Index::open(root);
+
+    let files: Vec<PathBuf> = m
+        .values_of("dir")
+        .unwrap()
+        .flat_map(|d| fulltext::pdf_files(Path::new(d)))
+        .collect();
+
+    let missing = index.missing(&files);
+    if !missing.is_empty() {
+        println!("Removing {} deleted documents...", missing.len());
+        index.remove(&missing);
+    }
+
+    let stale = index.stale(&files);
+    let changed: Vec<String> = stale.iter().map(|f| index.key(f)).collect();
+    println!(
+        "{} documents indexed, {} new or changed",
+        index.docs().len(),
+        stale.len()
+    );
+
+    let pb = ProgressBar::new(stale.len() as u64);
+    pb.set_style(llh::PB_STYLE.clone());
+    pb.set_message("Extracting text...");
+
+    // pdf-extract and lopdf report broken files by panicking, those panics are kept quiet and
+    // the files listed at the end, any other panic goes to the hook there was before
+    let previous: Arc<PanicHook> = Arc::from(std::panic::take_hook());
+    let hook = Arc::clone(&previous);
+    std::panic::set_hook(Box::new(move |info| {
+        if !EXTRACTING.with(|e| e.get()) {
+            hook(info);
+        }
+    }));
+
+    let queue = Arc::new(Mutex::new(stale.into_iter()));
+    let (tx, rx) = mpsc::channel();
+    let workers = thread::available_parallelism().map_or(4, |n| n.get());
+
+    for _ in 0..workers {
+        let queue = Arc::clone(&queue);
+        let tx = tx.clone();
+        thread::spawn(move || loop {
+            let file = match queue.lock().unwrap().next() {
+                Some(f) => f,
+                None => break,
+            };
+            // a panic anywhere in here fails the file, not the worker
+            EXTRACTING.with(|e| e.set(true));
+            let work = std::panic::catch_unwind(|| {
+                let pages = fulltext::extract_pages(&file)?;
+                // files lopdf can't read still get their page count
+                let meta = pdfmeta::read(&file, &pages).unwrap_or_else(|| PdfMeta {
+                    pages: pages.len() as u32,
+                    history: history::parse(&pages),
+                    ..PdfMeta::default()
+                });
+                Some((pages, meta))
+            })
+            .unwrap_or(None);
+            EXTRACTING.with(|e| e.set(false));
+            if tx.send((file, work)).is_err() {
+                break;
+            }
+        });
+    }
+    drop(tx);
+
+    let mut failed = Vec::new();
+    for (n, (file, work)) in rx.iter().enumerate() {
+        match work {
+            Some((pages, meta)) => index.add(&file, pages, Some(meta)),
+            None => {
+                index.fail(&file);
+                failed.push(file);
+            }
+        }
+        pb.inc(1);
+
+        // save every now and then so an interrupted run doesn't start over
+        if n % 500 == 499 {
+            index.save();
+        }
+    }
+
+    let _ = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| previous(info)));
+    pb.finish_and_clear();
+    if !failed.is_empty() {
+        for file in &failed {
+            eprintln!("could not extract text from {}", file.display());
+        }
+        eprintln!(
+            "could not extract text from {} documents, they are tried again when they change",
+            failed.len()
+        );
+    }
+
+    index.compact();
+    pinout::update(root, &index, &changed, &missing);
+    characteristics::update(root, &index, &changed, &missing);
+}
+
+fn cmd_grep(m: &ArgMatches) {
+    let index = Index::open(Path::new("."));
+    let query = m
+        .values_of("query")
+        .unwrap()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let limit: usize = m
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .expect("limit has to be a number");
+
+    let prefix = m.value_of("prefix");
+    let hits = match m.values_of("kind") {
+        None => index.search(&query, prefix, limit),
+        Some(names) => {
+            let wanted = parse_kinds(names);
+            let kinds = Kinds::load(Path::new("."));
+            index.search_where(
+                &query,
+                |path| {
+                    prefix.is_none_or(|p| path.starts_with(p)) && wanted.contains(&kinds.of(path))
+                },
+                limit,
+            )
+        }
+    };
+
+    if m.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&hits).unwrap());
+        return;
+    }
+
+    for hit in hits {
+        println!("{}:{}: {}", hit.path, hit.page, hit.snippet);
+    }
+}
+
+fn cmd_docs(m: &ArgMatches) {
+    let root = Path::new(".");
+    let words: Vec<String> = m
+        .values_of("words")
+        .map(|v| v.map(|w| w.to_lowercase()).collect())
+        .unwrap_or_default();
+    let kinds = m.values_of("kind").map(parse_kinds);
+    let part = m.value_of("part").map(|p| p.to_uppercase());
+
+    let docs: Vec<Document> = ["ti", "st"]
+        .iter()
+        .flat_map(|v| document::load(root, v))
+        .filter(|d| kinds.as_ref().is_none_or(|k| k.contains(&d.kind)))
+        .filter(|d| {
+            part.as_ref()
+                .is_none_or(|p| d.parts.iter().any(|x| x.eq_ignore_ascii_case(p)))
+        })
+        .filter(|d| {
+            let title = d.title.to_lowercase();
+            words.iter().all(|w| title.contains(w))
+        })
+        .collect();
+    // the indexed PDF knows the revision when the product page doesn't
+    let manifest = fulltext::manifest(root);
+    let docs: Vec<Document> = docs
+        .into_iter()
+        .map(|mut d| {
+            let meta = d
+                .local_path()
+                .and_then(|p| manifest.get(&p))
+                .and_then(|e| e.meta.clone());
+            if let Some(m) = meta {
+                d.revision = d.revision.or(m.revision);
+                d.date = d.date.or(m.date);
+            }
+            d
+        })
+        .collect();
+
+    let format = Format::parse(m.value_of("format").unwrap()).unwrap();
+    if format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&docs).unwrap());
+        return;
+    }
+
+    let columns: Vec<String> = ["kind", "vendor", "title", "revision", "date", "url", "file"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    let rows = docs
+        .iter()
+        .map(|d| {
+            vec![
+                d.kind.to_string(),
+                d.vendor.clone(),
+                d.title.clone(),
+                d.revision.clone().unwrap_or_default(),
+                d.date.clone().unwrap_or_default(),
+                d.url.clone(),
+                d.local_path()
+                    .filter(|p| root.join(p).exists())
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+    println!("{}", search::render_rows(&columns, rows, format));
+}
+
+fn cmd_pins(m: &ArgMatches) {
+    let db = Database::load(Path::new("."));
+    let mpn = m.value_of("mpn").unwrap();
+
+    let part = match db.resolve(mpn) {
+        Some((p, _)) => p,
+        None => {
+            eprintln!("{} is not in the database", mpn);
+            std::process::exit(1);
+        }
+    };
+    let mut tables: Vec<Pinout> = package_tables(part, m.value_of("package"))
+        .into_iter()
+        .cloned()
+        .collect();
+    let format = Format::parse(m.value_of("format").unwrap()).unwrap();
+    if tables.is_empty() && format != Format::Json {
+        eprintln!("no pin table found for {}", part.mpn);
+        return;
+    }
+    if m.is_present("uncertain") {
+        for t in tables.iter_mut() {
+            t.pins.retain(|p| p.uncertain.is_some());
+        }
+        tables.retain(|t| !t.pins.is_empty());
+        if tables.is_empty() && format != Format::Json {
+            eprintln!("every row of the pin table of {} parsed cleanly", part.mpn);
+            return;
+        }
+    }
+
+    if format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&tables).unwrap());
+        return;
+    }
+
+    let columns: Vec<String> = ["package", "pin", "name", "type", "description", "uncertain"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    let rows = tables
+        .iter()
+        .flat_map(|t| {
+            t.pins.iter().map(move |p| {
+                vec![
+                    t.package.clone().unwrap_or_default(),
+                    p.number.clone(),
+                    p.name.clone(),
+                    p.kind.map_or("", |k| k.name()).to_string(),
+                    p.description.clone(),
+                    p.uncertain.clone().unwrap_or_default(),
+                ]
+            })
+        })
+        .collect();
+    println!("{}", search::render_rows(&columns, rows, format));
+    for t in &tables {
+        if !t.missing.is_empty() {
+            eprintln!(
+                "{}: no row for pins {} ({} page {})",
+                t.package.as_deref().unwrap_or(&part.mpn),
+                t.missing
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                t.source,
+                t.page
+            );
+        }
+    }
+}
+
+fn cmd_specs(m: &ArgMatches) {
+    let db = Database::load(Path::new("."));
+    let mpn = m.value_of("mpn").unwrap();
+    let table: Option<Table> = m.value_of("table").map(|t| t.parse().unwrap());
+    let words: Vec<String> = m
+        .values_of("words")
+        .map(|v| v.map(|w| w.to_lowercase()).collect())
+        .unwrap_or_default();
+
+    let part = match db.resolve(mpn) {
+        Some((p, _)) => p,
+        None => {
+            eprintln!("{} is not in the database", mpn);
+            std::process::exit(1);
+        }
+    };
+    let specs: Vec<&Spec> = part
+        .characteristics
+        .iter()
+        .filter(|s| table.is_none_or(|t| s.table == t))
+        .filter(|s| {
+            let parameter = s.parameter.to_lowercase();
+            words.iter().all(|w| parameter.contains(w))
+        })
+        .collect();
+
+    let format = Format::parse(m.value_of("format").unwrap()).unwrap();
+    if format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&specs).unwrap());
+        return;
+    }
+    if specs.is_empty() {
+        eprintln!("no characteristics found for {}", part.mpn);
+        return;
+    }
+
+    let number = |n: Option<f64>| n.map(|n| n.to_string()).unwrap_or_default();
+    let columns: Vec<String> = [
+        "table",
+        "symbol",
+        "parameter",
+        "conditions",
+        "min",
+        "typ",
+        "max",
+        "unit",
+        "page",
+        "guessed",
+    ]
+    .iter()
+    .map(|c| c.to_string())
+    .collect();
+    let rows = specs
+        .iter()
+        .map(|s| {
+            vec![
+                s.table.name().to_string(),
+                s.symbol.clone().unwrap_or_default(),
+                s.parameter.clone(),
+                s.conditions.clone().unwrap_or_default(),
+                number(s.min),
+                number(s.typ),
+                number(s.max),
+                s.unit.clone(),
+                s.page.to_string(),
+                if s.guessed { "yes" } else { "" }.to_string(),
+            ]
+        })
+        .collect();
+    println!("{}", search::render_rows(&columns, rows, format));
+}
+
+// the tables of a part for -p, "D, DGK" is the table of both
+fn package_tables<'a>(part: &'a Part, package: Option<&str>) -> Vec<&'a Pinout> {
+    part.pins
+        .iter()
+        .filter(|t| {
+            package.is_none_or(|p| {
+                t.package
+                    .as_deref()
+                    .is_some_and(|x| x.split(", ").any(|x| x.eq_ignore_ascii_case(p)))
+            })
+        })
+        .collect()
+}
+
+fn cmd_kicad(m: &ArgMatches) {
+    let root = Path::new(".");
+    let db = Database::load(root);
+    let mut mpns: Vec<String> = m
+        .values_of("mpn")
+        .map(|v| v.map(String::from).collect())
+        .unwrap_or_default();
+    if let Some(list) = m.value_of("list") {
+        match watch::read_list(Path::new(list)) {
+            Ok(l) => mpns.extend(l),
+            Err(why) => {
+                eprintln!("couldn't read {}: {}", list, why);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // a part named twice, or by two of its orderables, gets its symbols once
+    let mut seen = std::collections::HashSet::new();
+    let mut symbols = Vec::new();
+    for mpn in &mpns {
+        let part = match db.resolve(mpn) {
+            Some((p, _)) => p,
+            None => {
+                eprintln!("{} is not in the database", mpn);
+                continue;
+            }
+        };
+        if !seen.insert((&part.vendor, &part.mpn)) {
+            continue;
+        }
+        let tables = package_tables(part, m.value_of("package"));
+        if tables.is_empty() {
+            eprintln!("no pin table found for {}", part.mpn);
+            continue;
+        }
+        for t in tables {
+            let uncertain = t.pins.iter().filter(|p| p.uncertain.is_some()).count();
+            if uncertain > 0 {
+                eprintln!(
+                    "{}: {} pins need checking, see llh pins {} -u",
+                    kicad::name(t),
+                    uncertain,
+                    part.mpn
+                );
+            }
+            symbols.extend(kicad::symbol(root, part, t));
+        }
+    }
+    if symbols.is_empty() {
+        eprintln!("no symbols to write");
+        std::process::exit(1);
+    }
+
+    let output = m.value_of("output").unwrap();
+    if let Err(why) = std::fs::write(output, kicad::library(&symbols)) {
+        eprintln!("couldn't write {}: {}", output, why);
+        std::process::exit(1);
+    }
+    println!("wrote {} symbols to {}", symbols.len(), output);
+}
+
+fn parse_kinds<'a>(names: impl Iterator<Item = &'a str>) -> Vec<DocumentKind> {
+    match document::kinds(Some(names)) {
+        Ok(k) => k,
+        Err(why) => {
+            eprintln!("{}", why);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn cmd_bom_check(m: &ArgMatches) {
+    let root = Path::new(".");
+    let path = Path::new(m.value_of("bom").unwrap());
+
+    let lines = match bom::read(path, m.value_of("column")) {
+        Err(why) => {
+            eprintln!("couldn't read {}: {}", path.display(), why);
+            std::process::exit(1);
+        }
+        Ok(l) => l,
+    };
+
+    let db = Database::load(root);
+    let checks = bom::check(&db, root, &lines);
+
+    let format = Format::parse(m.value_of("format").unwrap()).unwrap();
+    if format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&checks).unwrap());
+    } else {
+        let columns: Vec<String> = ["row", "mpn", "vendor", "part", "status", "issues"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let rows = checks
+            .iter()
+            .map(|c| {
+                vec![
+                    c.row.to_string(),
+                    c.mpn.clone(),
+                    c.vendor.clone().unwrap_or_default(),
+                    c.part.clone().unwrap_or_default(),
+                    c.status.clone().unwrap_or_default(),
+                    c.issues
+                        .iter()
+                        .map(|i| i.describe())
+                        .collect::<Vec<String>>()
+                        .join("; "),
+                ]
+            })
+            .collect();
+        println!("{}", search::render_rows(&columns, rows, format));
+    }
+
+    let flagged = checks.iter().filter(|c| !c.issues.is_empty()).count();
+    eprintln!("{} lines checked, {} with issues", checks.len(), flagged);
+
+    if let Some(out) = m.value_of("zip") {
+        match bom::export_zip(&checks, root, Path::new(out)) {
+            Err(why) => {
+                eprintln!("couldn't write {}: {}", out, why);
+                std::process::exit(1);
+            }
+            Ok(n) => eprintln!("wrote {} datasheets to {}", n, out),
+        }
+    }
+}
+
+fn cmd_alternates(m: &ArgMatches) {
+    let db = Database::load(Path::new("."));
+    let mpn = m.value_of("mpn").unwrap();
+    let limit: usize = m
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .expect("limit has to be a number");
+
+    let base = match db.resolve(mpn) {
+        Some((p, _)) => p,
+        None => {
+            eprintln!("{} is not in the database", mpn);
+            std::process::exit(1);
+        }
+    };
+
+    let mut candidates = alternates::find(&db, base);
+    candidates.truncate(limit);
+
+    if m.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&candidates).unwrap());
+        return;
+    }
+
+    println!(
+        "Alternates for {} ({}, {})",
+        base.mpn,
+        base.vendor,
+        base.category.as_deref().unwrap_or("no category")
+    );
+
+    for c in &candidates {
+        println!(
+            "\n{:.2}  {} {}  {} match, {} better, {} worse, {} differ{}",
+            c.score,
+            c.part.vendor,
+            c.part.mpn,
+            alternates::count(c, Verdict::Match),
+            alternates::count(c, Verdict::Better),
+            alternates::count(c, Verdict::Worse),
+            alternates::count(c, Verdict::Differs),
+            c.part
+                .lifecycle_risk()
+                .map(|s| format!(", {}", s))
+                .unwrap_or_default()
+        );
+        for x in &c.comparisons {
+            println!(
+                "      {:<8} {}: {} -> {}",
+                format!("{:?}", x.verdict).to_lowercase(),
+                x.param,
+                x.base,
+                x.candidate
+            );
+        }
+    }
+}
+
+fn cmd_models(m: &ArgMatches) {
+    let db = Database::load(Path::new("."));
+    let mpn = m.value_of("mpn").unwrap();
+    let kind: Option<ModelType> = m.value_of("type").map(|t| t.parse().unwrap());
+
+    let part = match db.resolve(mpn) {
+        Some((p, _)) => p,
+        None => {
+            eprintln!("{} is not in the database", mpn);
+            std::process::exit(1);
+        }
+    };
+    let models: Vec<&ModelFile> = part
+        .models
+        .iter()
+        .filter(|x| kind.is_none_or(|k| x.kind == k))
+        .collect();
+
+    if m.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&models).unwrap());
+        return;
+    }
+
+    if models.is_empty() {
+        eprintln!("no models archived for {}", part.mpn);
+        return;
+    }
+    for x in models {
+        println!("{:<7} {}", x.kind.name(), x.path);
+    }
+}
+
+fn cmd_packs(m: &ArgMatches) {
+    let root = Path::new(".");
+    let db = Database::load(root);
+    let mpn = m.value_of("mpn").unwrap();
+
+    // vendors without parts in the database only have the pack index
+    let files: Vec<PackFile> = match db.resolve(mpn) {
+        Some((p, _)) if !p.packs.is_empty() => p.packs.clone(),
+        _ => ["ti", "st", "nxp"]
+            .iter()
+            .flat_map(|v| pack::load(root, v))
+            .filter(|f| f.part.eq_ignore_ascii_case(mpn) || pack::matches(&f.device, mpn))
+            .collect(),
+    };
+    let files: Vec<&PackFile> = files
+        .iter()
+        .filter(|f| !m.is_present("svd") || f.kind == PackKind::Svd)
+        .collect();
+
+    if m.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&files).unwrap());
+        return;
+    }
+
+    if files.is_empty() {
+        eprintln!("no packs archived for {}", mpn);
+        std::process::exit(1);
+    }
+    for f in files {
+        if m.is_present("svd") {
+            println!("{}", f.path);
+        } else {
+            println!("{:<4} {:<16} {}", f.kind.name(), f.device, f.path);
+        }
+    }
+}
+
+fn cmd_cad(m: &ArgMatches) {
+    let db = Database::load(Path::new("."));
+    let mpn = m.value_of("mpn").unwrap();
+
+    let (part, matched) = match db.resolve(mpn) {
+        Some(p) => p,
+        None => {
+            eprintln!("{} is not in the database", mpn);
+            std::process::exit(1);
+        }
+    };
+    // an orderable number only gets the links for its own package
+    let orderables: Vec<Option<&Orderable>> = match &matched {
+        Match::Orderable { opn } => vec![part.orderables.iter().find(|o| o.opn == *opn)],
+        _ if part.orderables.is_empty() => vec![None],
+        _ => part.orderables.iter().map(Some).collect(),
+    };
+
+    let columns: Vec<String> = ["orderable", "package", "portal", "kinds", "login", "link"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    let mut rows = Vec::new();
+    for o in orderables {
+        for c in part.cad.iter().filter(|c| o.is_none_or(|o| c.fits(o))) {
+            rows.push(vec![
+                o.map(|o| o.opn.clone()).unwrap_or_default(),
+                c.package
+                    .clone()
+                    .or_else(|| o.and_then(|o| o.package.clone()))
+                    .unwrap_or_default(),
+                c.portal.clone(),
+                c.kinds
+                    .iter()
+                    .map(|k| k.name())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                if c.login { "yes" } else { "no" }.to_string(),
+                c.path.clone().unwrap_or_else(|| c.absolute_url()),
+            ]);
+        }
+    }
+
+    if rows.is_empty() {
+        eprintln!("no CAD links for {}", mpn);
+        std::process::exit(1);
+    }
+    let format = Format::parse(m.value_of("format").unwrap()).unwrap();
+    println!("{}", search::render_rows(&columns, rows, format));
+}
+
+fn cmd_watch(m: &ArgMatches) {
+    let root = Path::new(".");
+    let path = Path::new(m.value_of("list").unwrap());
+    let mpns = match watch::read_list(path) {
+        Ok(l) => l,
+        Err(why) => {
+            eprintln!("couldn't read {}: {}", path.display(), why);
+            std::process::exit(1);
+        }
+    };
+
+    let db = Database::load(root);
+    if m.is_present("digest") {
+        watch_digest(m, &db, &mpns);
+        return;
+    }
+    let (changes, unknown) = watch::errata(&db, root, &mpns, m.value_of("since"));
+    for mpn in &unknown {
+        eprintln!("{} is not in the database", mpn);
+    }
+
+    let format = Format::parse(m.value_of("format").unwrap()).unwrap();
+    if format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&changes).unwrap());
+        return;
+    }
+
+    let columns: Vec<String> = [
+        "mpn", "vendor", "change", "revision", "date", "title", "link",
+    ]
+    .iter()
+    .map(|c| c.to_string())
+    .collect();
+    let rows = changes
+        .iter()
+        .map(|c| {
+            vec![
+                c.mpn.clone(),
+                c.vendor.clone(),
+                match &c.change {
+                    ChangeKind::New => String::from("new"),
+                    ChangeKind::Revised { from } => format!("revised from {}", from),
+                },
+                c.revision.clone().unwrap_or_default(),
+                c.date.clone().unwrap_or_default(),
+                c.title.clone(),
+                c.url.clone(),
+            ]
+        })
+        .collect();
+    println!("{}", search::render_rows(&columns, rows, format));
+
+    let parts: std::collections::BTreeSet<&String> = changes.iter().map(|c| &c.mpn).collect();
+    eprintln!(
+        "{} of {} parts gained errata",
+        parts.len(),
+        mpns.len() - unknown.len()
+    );
+}
+
+// the digest is printed and written wherever asked, the snapshot is only updated when all of
+// that worked so nothing gets lost
+fn watch_digest(m: &ArgMatches, db: &Database, mpns: &[String]) {
+    let root = Path::new(".");
+    let (digest, state) = watch::digest(db, root, mpns, &chrono::Utc::now().to_rfc3339());
+
+    match Format::parse(m.value_of("format").unwrap()).unwrap() {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&digest).unwrap()),
+        _ => print!("{}", digest.markdown()),
+    }
+
+    let mail = m.value_of("spool").map(|dir| {
+        let mail = watch::Mail {
+            from: m.value_of("mail-from").unwrap().to_string(),
+            to: m.value_of("mail-to").unwrap().to_string(),
+        };
+        (Path::new(dir), mail)
+    });
+    let written = watch::publish(
+        root,
+        &digest,
+        &state,
+        m.value_of("out").map(Path::new),
+        mail.as_ref().map(|(dir, mail)| (*dir, mail)),
+        m.value_of("webhook").map(Path::new),
+    );
+    if let Err(why) = written {
+        eprintln!("couldn't write the digest: {}", why);
+        std::process::exit(1);
+    }
+}
+
+fn cmd_tree(m: &ArgMatches) {
+    let db = Database::load(Path::new("."));
+    let vendors: Vec<&str> = m
+        .values_of("vendor")
+        .map(|v| v.collect())
+        .unwrap_or_default();
+    let trees: BTreeMap<&String, &Category> = db
+        .categories
+        .iter()
+        .filter(|(v, _)| vendors.is_empty() || vendors.contains(&v.as_str()))
+        .collect();
+
+    if m.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&trees).unwrap());
+        return;
+    }
+
+    for tree in trees.values() {
+        for c in tree.walk() {
+            println!("{}{} ({})", "  ".repeat(c.path.len()), c.name, c.total());
+        }
+    }
+}
+
+fn cmd_serve(m: &ArgMatches) {
+    let addr = m
+        .value_of("listen")
+        .unwrap()
+        .parse()
+        .expect("listen has to be an address like 127.0.0.1:8080");
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    if let Err(why) = rt.block_on(llh::serve::run(addr, Path::new("."))) {
+        eprintln!("server error: {}", why);
+        std::process::exit(1);
+    }
+}
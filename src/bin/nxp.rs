@@ -3,14 +3,14 @@ extern crate select;
 extern crate serde;
 
 use llh as _;
+use llh::pack;
 
 use chrono::Utc;
-use futures::{stream, StreamExt};
-use indicatif::{ProgressBar, ProgressStyle};
-use select::predicate::{Attr, Class, Name};
+use clap::{App, Arg};
+use select::predicate::Name;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs;
 use std::path::Path;
 use std::time::Instant;
 
@@ -25,6 +25,7 @@ struct Category {
 }
 
 #[derive(Deserialize, Clone)]
+#[allow(dead_code)]
 struct Product {
     #[serde(alias = "productId")]
     product_id: String,
@@ -46,11 +47,40 @@ struct Product {
 
 #[tokio::main]
 async fn main() -> Result<(), reqwest::Error> {
-    let pb_style = ProgressStyle::default_bar()
-        .template("{msg} {bar:40.magenta/blue} {pos}/{len} ({eta})")
-        .progress_chars("##-");
-
     println!("Start scraping NXP at {}", Utc::now());
+
+    let matches = App::new("NXP Crawler")
+        .version(llh::VERSION)
+        .about("Builds a DB of all the parts and datasheets")
+        .arg(
+            Arg::with_name("download")
+                .short("d")
+                .long("download")
+                .multiple(true)
+                .takes_value(true)
+                .help("Fetch the device packs, pass packs as value"),
+        )
+        .get_matches();
+
+    if matches.is_present("download")
+        && matches
+            .values_of("download")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"packs")
+    {
+        let start = Instant::now();
+
+        // there are no NXP parts in the database yet, so every device keeps its own name
+        let packs = pack::crawl(Path::new("."), "nxp", &[]).await;
+
+        let duration = start.elapsed();
+        println!("Fetching device packs took {:?}", duration);
+        fs::create_dir_all("json/nxp").unwrap();
+        llh::dump_json("json/nxp/packs.json", packs);
+        return Ok(());
+    }
+
     print!("Fetching main page... ");
 
     let mut pages: Vec<String> = Vec::new();
@@ -59,10 +89,7 @@ async fn main() -> Result<(), reqwest::Error> {
         .await?
         .find(Name("a"))
         .filter_map(|n| n.attr("href"))
-        .filter(|a| {
-            a.starts_with("/products/")
-                && !a.contains("?")
-        })
+        .filter(|a| a.starts_with("/products/") && !a.contains("?"))
         .for_each(|x| pages.push(String::from(x)));
 
     for page in pages {
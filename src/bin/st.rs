@@ -3,6 +3,15 @@ extern crate select;
 extern crate serde;
 
 use llh as _;
+use llh::datapage::{self, Category, Column};
+use llh::db::Database;
+use llh::document::{self, DocumentKind};
+use llh::ecad;
+use llh::errata;
+use llh::model;
+use llh::orderable::{self, Orderable};
+use llh::pack;
+use llh::package::Package;
 
 use chrono::Utc;
 use clap::{App, Arg};
@@ -12,44 +21,13 @@ use indicatif::ProgressBar;
 use reqwest::{header::USER_AGENT, Url};
 use select::document::Document;
 use select::predicate::{Attr, Class, Name};
-use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs;
 use std::fs::File;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-#[derive(Deserialize)]
-#[allow(dead_code)]
-struct Category {
-    columns: Vec<HashMap<String, serde_json::Value>>,
-    rows: Vec<Product>,
-    #[serde(alias = "levelTitle")]
-    level_title: String,
-    breadcrumb: String,
-}
-
-#[derive(Deserialize, Clone)]
-struct Product {
-    #[serde(alias = "productId")]
-    product_id: String,
-    path: String,
-    cells: Vec<HashMap<String, String>>,
-    #[serde(alias = "productFolderUrl")]
-    product_folder_url: String,
-    #[serde(alias = "availableInDistributorStock")]
-    available_in_distributor_stock: bool,
-    #[serde(alias = "availableAsFreeSample")]
-    available_as_free_sample: bool,
-    #[serde(alias = "newProductIntroduction")]
-    new_product_introduction: bool,
-    #[serde(alias = "isNewProduct")]
-    is_new_product: bool,
-    #[serde(alias = "isPublic")]
-    is_public: bool,
-}
-
 #[tokio::main]
 async fn main() -> Result<(), reqwest::Error> {
     println!("Start scraping ST at {}", Utc::now());
@@ -67,7 +45,7 @@ async fn main() -> Result<(), reqwest::Error> {
                 .long("database")
                 .multiple(true)
                 .takes_value(true)
-                .help("Build the database, pass datasheets and or techdocs as values"),
+                .help("Build the database, pass datasheets, techdocs and/or orderables as values"),
         )
         .arg(
             Arg::with_name("download")
@@ -75,7 +53,15 @@ async fn main() -> Result<(), reqwest::Error> {
                 .long("download")
                 .multiple(true)
                 .takes_value(true)
-                .help("Fetch all the datasheets and/or techdocs"),
+                .help("Fetch all the datasheets, techdocs, models, packs and/or ecad files"),
+        )
+        .arg(
+            Arg::with_name("kind")
+                .short("k")
+                .long("kind")
+                .multiple(true)
+                .takes_value(true)
+                .help("Only download techdocs of these kinds, e.g. application_note errata"),
         )
         .get_matches();
 
@@ -116,11 +102,8 @@ async fn main() -> Result<(), reqwest::Error> {
                 .find(Attr("name", "didyouknow.productId"))
                 .filter_map(|n| n.attr("value"))
                 .for_each(|x| {
-                    let link = String::from(format!(
-                        "{}.cxst-ps-grid.html/{}.json",
-                        page.replace(".html", ""),
-                        x
-                    ));
+                    let link =
+                        format!("{}.cxst-ps-grid.html/{}.json", page.replace(".html", ""), x);
                     data_pages.insert(String::from(x), link);
                 });
             pb.inc(1);
@@ -136,7 +119,6 @@ async fn main() -> Result<(), reqwest::Error> {
 
         let start = Instant::now();
 
-        // TODO: save all the JSON data into a database
         for p in data_pages.iter() {
             llh::save_json(
                 p.value().to_string(),
@@ -149,6 +131,8 @@ async fn main() -> Result<(), reqwest::Error> {
         let duration = start.elapsed();
         pb.finish_and_clear();
         println!("Fetching all data-pages took {:?}", duration);
+
+        build_data(&data_pages);
     } else {
         let paths = fs::read_dir("json/st/datapages/").unwrap();
 
@@ -161,6 +145,8 @@ async fn main() -> Result<(), reqwest::Error> {
 
             data_pages.insert(key.to_string(), String::from(""));
         }
+        // an archive from before json/st/data.json gets it too
+        build_data(&data_pages);
     }
 
     if matches.is_present("database")
@@ -183,6 +169,26 @@ async fn main() -> Result<(), reqwest::Error> {
         println!("Fetching techdoc database took {:?}", duration);
     }
 
+    if matches.is_present("database")
+        && matches
+            .values_of("database")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"orderables")
+    {
+        let mpb = ProgressBar::new(data_pages.len() as u64);
+        mpb.set_style(llh::PB_STYLE.clone());
+        mpb.set_message("Fetching orderables...");
+
+        let start = Instant::now();
+
+        parse_product_orderables(&mpb, data_pages.as_ref()).await;
+
+        let duration = start.elapsed();
+        mpb.finish_and_clear();
+        println!("Fetching orderables took {:?}", duration);
+    }
+
     if matches.is_present("download")
         && matches
             .values_of("download")
@@ -210,51 +216,154 @@ async fn main() -> Result<(), reqwest::Error> {
             .collect::<Vec<&str>>()
             .contains(&"techdocs")
     {
-        let filename = "json/st/techdocs.json";
-        let path = Path::new(&filename);
-        let display = path.display();
-        let file = match File::open(path) {
-            Err(why) => panic!("could not open {}: {}", display, why),
-            Ok(file) => file,
-        };
+        let kinds =
+            document::kinds(matches.values_of("kind")).unwrap_or_else(|why| panic!("{}", why));
+        let cat: Vec<document::Document> = document::load(Path::new("."), "st")
+            .into_iter()
+            .filter(|d| kinds.contains(&d.kind) && d.local_path().is_some())
+            .collect();
+        if cat.is_empty() {
+            panic!("no techdocs in json/st/documents.json or json/st/techdocs.json");
+        }
 
-        let cat: HashMap<String, String> = match serde_json::from_reader(file) {
-            Err(why) => {
-                panic!("could not parse {}: {}", display, why);
-            }
-            Ok(v) => v,
-        };
+        let start = Instant::now();
 
-        let mpb = ProgressBar::new(cat.len() as u64);
-        mpb.set_style(llh::PB_STYLE.clone());
-        mpb.set_message("Fetching techdocs...");
+        load_techdocs(cat).await;
+
+        let duration = start.elapsed();
+        println!("Fetching techdocs took {:?}", duration);
+    }
+
+    if matches.is_present("download")
+        && matches
+            .values_of("download")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"models")
+    {
+        let docs: Vec<document::Document> = document::load(Path::new("."), "st")
+            .into_iter()
+            .filter(|d| d.kind == DocumentKind::Model && d.local_path().is_some())
+            .collect();
+        if docs.is_empty() {
+            panic!("no models in json/st/documents.json, build the techdocs first");
+        }
+
+        load_techdocs(docs.clone()).await;
 
+        // unpack them into the store of each part
+        let models = model::index(Path::new("."), &docs);
+        println!("Unpacked {} model files", models.len());
+        llh::dump_json("json/st/models.json", models);
+    }
+
+    if matches.is_present("download")
+        && matches
+            .values_of("download")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"packs")
+    {
         let start = Instant::now();
 
-        let pdfs = stream::iter(cat)
-            .map(|p| async move {
-                llh::save_pdf(
-                    format!("https://www.st.com{}", p.1),
-                    format!("pdf/st/techdocs/{}.pdf", p.0.replace("/", "_")),
-                )
-                .await
-            })
-            .buffer_unordered(8);
+        // the device packs are published through the Keil index, not on st.com
+        let parts: Vec<String> = Database::load(Path::new("."))
+            .parts
+            .into_iter()
+            .filter(|p| p.vendor == "st")
+            .map(|p| p.mpn)
+            .collect();
+        let packs = pack::crawl(Path::new("."), "st", &parts).await;
 
-        pdfs.for_each(|x| async {
-            match x {
-                Ok(_) => mpb.inc(1),
-                Err(e) => eprintln!("Got an error: {}", e),
+        let duration = start.elapsed();
+        println!("Fetching device packs took {:?}", duration);
+        llh::dump_json("json/st/packs.json", packs);
+    }
+
+    if matches.is_present("download")
+        && matches
+            .values_of("download")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"ecad")
+    {
+        let links = ecad::load(Path::new("."), "st");
+        if links.is_empty() {
+            panic!("no CAD links in json/st/ecad.json, build the techdocs first");
+        }
+
+        let links = ecad::archive(Path::new("."), links).await;
+        println!(
+            "Archived {} of {} CAD links",
+            links.iter().filter(|c| c.path.is_some()).count(),
+            links.len()
+        );
+        llh::dump_json("json/st/ecad.json", links);
+    }
+
+    // a digest of what changed for the parts in watchlist.txt, once the parameters and status
+    // are fresh
+    if matches.is_present("database") {
+        llh::watch::after_crawl(Path::new("."));
+    }
+
+    Ok(())
+}
+
+async fn load_techdocs(docs: Vec<document::Document>) {
+    let mpb = ProgressBar::new(docs.len() as u64);
+    mpb.set_style(llh::PB_STYLE.clone());
+    mpb.set_message("Fetching techdocs...");
+
+    let pdfs = stream::iter(docs)
+        .map(|d| async move {
+            let path = d.local_path().unwrap();
+            // models and design files have their own limits
+            match d.kind.archive() {
+                Some(a) => llh::save_file(d.absolute_url(), path, a.max_size, a.extensions)
+                    .await
+                    .map(|_| ()),
+                None => llh::save_pdf(d.absolute_url(), path).await,
             }
         })
-        .await;
+        .buffer_unordered(8);
 
-        let duration = start.elapsed();
-        mpb.finish_and_clear();
-        println!("Fetching techdocs took {:?}", duration);
+    pdfs.for_each(|x| async {
+        match x {
+            Ok(_) => mpb.inc(1),
+            Err(e) => eprintln!("Got an error: {}", e),
+        }
+    })
+    .await;
+
+    mpb.finish_and_clear();
+}
+
+// decode the rows of every data page with its columns into json/st/data.json, named
+// parameters per part like json/ti/data.json, the columns go to json/st/columns.json
+fn build_data(data_pages: &DashMap<String, String>) {
+    let mut data: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut columns: BTreeMap<String, Vec<Column>> = BTreeMap::new();
+
+    for p in data_pages.iter() {
+        let path = format!("json/st/datapages/{}.json", p.key());
+        let cat = match datapage::load(Path::new(&path)) {
+            Some(cat) => cat,
+            None => continue,
+        };
+
+        let cols = cat.columns();
+        for row in &cat.rows {
+            if let Some(pn) = row.part_number() {
+                // a part on several pages gets the columns of all of them
+                data.entry(pn.clone()).or_default().extend(row.raw(&cols));
+            }
+        }
+        columns.insert(p.key().clone(), cols);
     }
 
-    Ok(())
+    llh::dump_json("json/st/data.json", data);
+    llh::dump_json("json/st/columns.json", columns);
 }
 
 async fn parse_product_folders(mpb: &ProgressBar, data_pages: &DashMap<String, String>) {
@@ -291,7 +400,7 @@ async fn parse_product_folders(mpb: &ProgressBar, data_pages: &DashMap<String, S
                     }
                 }
                 async move {
-                    if pn.len() == 0 {
+                    if pn.is_empty() {
                         llh::empty().await
                     } else {
                         llh::save_pdf(
@@ -319,6 +428,8 @@ async fn parse_product_folders(mpb: &ProgressBar, data_pages: &DashMap<String, S
 
 async fn parse_product_documentation(mpb: &ProgressBar, data_pages: &DashMap<String, String>) {
     let techdocs = Arc::new(DashMap::new());
+    let documents = Arc::new(Mutex::new(Vec::new()));
+    let cad_links = Arc::new(Mutex::new(Vec::new()));
 
     // parse the files again and download the datasheets (where available)
     for p in data_pages {
@@ -345,35 +456,82 @@ async fn parse_product_documentation(mpb: &ProgressBar, data_pages: &DashMap<Str
 
         let pdfs = stream::iter(cat.rows)
             .map(|p| {
+                let pn = p.part_number().cloned().unwrap_or_default();
                 let folder = p.product_folder_url;
                 async move {
                     // TODO: parse product folder
-                    get_doc_sdi(
+                    let doc = get_doc_sdi(
                         format!("https://www.st.com{}", folder).as_str(),
                         "design-resources.html",
                     )
-                    .await
+                    .await;
+                    (pn, doc)
                 }
             })
             .buffer_unordered(8);
 
-        pdfs.for_each(|x| async {
-            match x {
-                Ok(doc) => {
-                    doc.find(Name("span"))
-                        .filter(|n| n.attr("data-translation-app-exclude").is_some())
-                        .for_each(|n| {
-                            let key = n.text();
-                            let value = n.parent().unwrap().attr("href").unwrap();
-
-                            if key.len() > 0 && !value.contains("/datasheet/") {
-                                techdocs.insert(key.trim().to_string(), String::from(value.trim()));
-                                // eprintln!("adding: {}:{}", key.trim(), value.trim());
-                            }
-                        });
-                    pb_inner.inc(1)
+        pdfs.for_each(|(pn, x)| {
+            let techdocs = &techdocs;
+            let documents = &documents;
+            let cad_links = &cad_links;
+            let pb_inner = &pb_inner;
+            async move {
+                match x {
+                    Ok(doc) => {
+                        doc.find(Name("span"))
+                            .filter(|n| n.attr("data-translation-app-exclude").is_some())
+                            .for_each(|n| {
+                                let key = n.text().trim().to_string();
+                                let value = n.parent().unwrap().attr("href").unwrap().trim();
+                                if key.is_empty() {
+                                    return;
+                                }
+
+                                let kind = DocumentKind::classify(None, value, &key);
+                                if kind != DocumentKind::Datasheet {
+                                    techdocs.insert(key.clone(), String::from(value));
+                                }
+                                // the version and latest update columns of the row
+                                let (revision, date) = document::revision_and_date(n);
+                                documents.lock().unwrap().push(document::Document {
+                                    vendor: String::from("st"),
+                                    url: String::from(value),
+                                    title: key,
+                                    kind,
+                                    parts: vec![pn.clone()],
+                                    revision,
+                                    date,
+                                });
+                            });
+                        // models are also listed in the CAD resources table without the
+                        // translation marker, document::merge drops the doubles
+                        doc.find(Name("a"))
+                            .filter_map(|a| Some((a.text().trim().to_string(), a.attr("href")?)))
+                            .filter(|(key, href)| {
+                                !key.is_empty()
+                                    && DocumentKind::classify(None, href, key)
+                                        == DocumentKind::Model
+                            })
+                            .for_each(|(key, href)| {
+                                documents.lock().unwrap().push(document::Document {
+                                    vendor: String::from("st"),
+                                    url: href.trim().to_string(),
+                                    title: key,
+                                    kind: DocumentKind::Model,
+                                    parts: vec![pn.clone()],
+                                    revision: None,
+                                    date: None,
+                                });
+                            });
+                        // the CAD resources table links the portals per package
+                        cad_links
+                            .lock()
+                            .unwrap()
+                            .extend(ecad::from_page(&doc, "st", &pn));
+                        pb_inner.inc(1)
+                    }
+                    Err(e) => eprintln!("Got an error: {}", e),
                 }
-                Err(e) => eprintln!("Got an error: {}", e),
             }
         })
         .await;
@@ -383,6 +541,134 @@ async fn parse_product_documentation(mpb: &ProgressBar, data_pages: &DashMap<Str
     }
 
     llh::dump_json("json/st/techdocs.json", techdocs.as_ref());
+    let documents = document::merge(documents.lock().unwrap().drain(..));
+    llh::dump_json("json/st/documents.json", &documents);
+    // the errata revisions are kept across crawls for llh watch
+    let mut errata = errata::History::load(Path::new("."), "st");
+    errata.update(&documents, &Utc::now().to_rfc3339());
+    llh::dump_json("json/st/errata.json", errata);
+    llh::dump_json("json/st/ecad.json", cad_links.lock().unwrap().clone());
+}
+
+async fn parse_product_orderables(mpb: &ProgressBar, data_pages: &DashMap<String, String>) {
+    let orderables = Arc::new(DashMap::new());
+
+    for p in data_pages {
+        let filename = format!("json/st/datapages/{}.json", p.key());
+        let path = Path::new(&filename);
+        let display = path.display();
+        let file = match File::open(path) {
+            Err(why) => panic!("could not open {}: {}", display, why),
+            Ok(file) => file,
+        };
+
+        // try parsing it, or skip if it can't be parsed
+        let cat: Category = match serde_json::from_reader(file) {
+            Err(why) => {
+                println!("could not parse {}: {}", display, why);
+                continue;
+            }
+            Ok(v) => v,
+        };
+
+        let pb_inner = ProgressBar::new(cat.rows.len() as u64);
+        pb_inner.set_style(llh::PB_STYLE.clone());
+        pb_inner.set_message(format!("Fetching orderables {}...", p.key()));
+
+        let pages = stream::iter(cat.rows)
+            .map(|p| {
+                let pn = p.part_number().cloned().unwrap_or_default();
+                let folder = p.product_folder_url;
+                async move {
+                    let doc = get_doc_sdi(
+                        format!("https://www.st.com{}", folder).as_str(),
+                        "sample-buy.html",
+                    )
+                    .await;
+                    (pn, doc)
+                }
+            })
+            .buffer_unordered(8);
+
+        pages
+            .for_each(|(pn, x)| {
+                let orderables = &orderables;
+                let pb_inner = &pb_inner;
+                async move {
+                    match x {
+                        Ok(doc) => {
+                            if !pn.is_empty() {
+                                orderables.insert(pn.clone(), parse_order_table(&doc, &pn));
+                            }
+                            pb_inner.inc(1)
+                        }
+                        Err(e) => eprintln!("Got an error: {}", e),
+                    }
+                }
+            })
+            .await;
+
+        pb_inner.finish_and_clear();
+        mpb.inc(1);
+    }
+
+    llh::dump_json("json/st/orderables.json", orderables.as_ref());
+}
+
+// the sample & buy table has one row per order code, the columns are found by their header
+fn parse_order_table(doc: &Document, gpn: &str) -> Vec<Orderable> {
+    let mut out = Vec::new();
+
+    for table in doc.find(Name("table")) {
+        let header: Vec<String> = table
+            .find(Name("th"))
+            .map(|th| th.text().trim().to_lowercase())
+            .collect();
+        let col = |names: &[&str]| {
+            header
+                .iter()
+                .position(|h| names.iter().any(|n| h.contains(n)))
+        };
+
+        let opn_col = match col(&["part number", "order code"]) {
+            Some(c) => c,
+            None => continue,
+        };
+        let package_col = col(&["package"]);
+        let packing_col = col(&["packing"]);
+        let status_col = col(&["status"]);
+
+        for tr in table.find(Name("tr")) {
+            let cells: Vec<String> = tr
+                .find(Name("td"))
+                .map(|td| td.text().trim().to_string())
+                .collect();
+            let cell = |c: Option<usize>| {
+                c.and_then(|c| cells.get(c))
+                    .filter(|s| !s.is_empty())
+                    .cloned()
+            };
+
+            let opn = match cell(Some(opn_col)) {
+                Some(opn) => opn,
+                None => continue,
+            };
+            let package = cell(package_col);
+
+            out.push(Orderable {
+                opn,
+                gpn: gpn.to_string(),
+                vendor: String::from("st"),
+                pins: package.as_deref().and_then(orderable::pins_from_package),
+                canonical: package.as_deref().and_then(|p| Package::parse(p, None)),
+                package,
+                packing: cell(packing_col).and_then(|p| orderable::packing_from_text(&p)),
+                status: cell(status_col),
+            });
+        }
+    }
+
+    out
 }
 
 // get_doc_sdi follows the link and looks for an SDI include comment of the specified type
@@ -400,13 +686,11 @@ async fn get_doc_sdi(link: &str, typ: &str) -> Result<Document, reqwest::Error>
 
     for line in body.split("\n") {
         let l = line.trim_start();
-        if l.starts_with("<!-- SDI include") {
-            if l.contains(typ) {
-                for part in l.split(" ") {
-                    if part.starts_with("/") {
-                        new_link = part.strip_suffix(",").unwrap_or(part);
-                        break;
-                    }
+        if l.starts_with("<!-- SDI include") && l.contains(typ) {
+            for part in l.split(" ") {
+                if part.starts_with("/") {
+                    new_link = part.strip_suffix(",").unwrap_or(part);
+                    break;
                 }
             }
         }
@@ -1,19 +1,28 @@
-#![feature(option_result_contains)]
-
 extern crate reqwest;
 extern crate select;
 extern crate serde;
 
 use llh as _;
+use llh::category::Category;
+use llh::document::{self, DocumentKind};
+use llh::ecad::{self, CadLink};
+use llh::errata;
+use llh::family::{Family, Orphan};
+use llh::merge::{self, Policy, Provenance};
+use llh::model;
+use llh::orderable::{self, Orderable};
+use llh::package::Package;
 
 use chrono::Utc;
 use clap::{App, Arg};
 use futures::{stream, StreamExt};
 use indicatif::ProgressBar;
 use reqwest::{header::USER_AGENT, Url};
+use select::document::Document;
+use select::node::Node;
 use select::predicate::{Attr, Class, Name, Predicate};
 use serde::Deserialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fs::File;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
@@ -37,6 +46,7 @@ struct ParametricControl {
 }
 
 #[derive(Deserialize, Clone)]
+#[allow(dead_code)]
 struct Control {
     id: u32,
     cid: String,
@@ -47,10 +57,13 @@ struct Control {
 #[tokio::main]
 async fn main() -> Result<(), reqwest::Error> {
     let mut top: Vec<String> = Vec::new();
-    let mut cat_lt = HashSet::new();
-    let mut cat_num: HashMap<String, String> = HashMap::new();
+    let mut cat_lt: HashMap<String, Vec<String>> = HashMap::new();
+    let mut tree = Category::root("ti");
+    let mut families: Vec<Family> = Vec::new();
+    let mut orphans: Vec<Orphan> = Vec::new();
     let mut tl: HashMap<String, (String, String)> = HashMap::new();
     let mut db: HashMap<String, HashMap<String, serde_json::Value>> = HashMap::new();
+    let mut provenance: HashMap<String, Provenance> = HashMap::new();
 
     let matches = App::new("TI Crawler")
         .version(llh::VERSION)
@@ -61,7 +74,7 @@ async fn main() -> Result<(), reqwest::Error> {
                 .long("database")
                 .multiple(true)
                 .takes_value(true)
-                .help("Build the database, pass datasheets and or techdocs as values"),
+                .help("Build the database, pass datasheets, techdocs and/or orderables as values"),
         )
         .arg(
             Arg::with_name("download")
@@ -69,10 +82,29 @@ async fn main() -> Result<(), reqwest::Error> {
                 .long("download")
                 .multiple(true)
                 .takes_value(true)
-                .help("Fetch all the datasheets and/or techdocs"),
+                .help("Fetch all the datasheets, techdocs, models and/or ecad files"),
+        )
+        .arg(
+            Arg::with_name("kind")
+                .short("k")
+                .long("kind")
+                .multiple(true)
+                .takes_value(true)
+                .help("Only download techdocs of these kinds, e.g. application_note errata"),
+        )
+        .arg(
+            Arg::with_name("merge")
+                .long("merge")
+                .takes_value(true)
+                .possible_values(&["first", "last", "complete"])
+                .default_value("complete")
+                .help("Which value wins when a part is in several families with different values"),
         )
         .get_matches();
 
+    let policy: Policy = matches.value_of("merge").unwrap().parse().unwrap();
+    let kinds = document::kinds(matches.values_of("kind")).unwrap_or_else(|why| panic!("{}", why));
+
     println!("Start scraping TI at {}", Utc::now());
 
     if matches.is_present("database")
@@ -102,7 +134,7 @@ async fn main() -> Result<(), reqwest::Error> {
         let mut duration = start.elapsed();
         println!("took {:?}", duration);
 
-        if top.len() == 0 {
+        if top.is_empty() {
             panic!("could not parse page, did the layout change again?");
         }
 
@@ -112,7 +144,7 @@ async fn main() -> Result<(), reqwest::Error> {
 
         start = Instant::now();
         for link in top {
-            parse_category(&mut cat_lt, link).await?;
+            parse_category(&mut cat_lt, &mut tree, link).await?;
             pb.inc(1);
         }
         duration = start.elapsed();
@@ -125,23 +157,32 @@ async fn main() -> Result<(), reqwest::Error> {
 
         start = Instant::now();
 
-        for link in cat_lt {
-            parse_sub_category(&mut cat_num, link).await?;
+        // the deepest categories first so a family is filed under the most specific one, and
+        // always the same one
+        let mut pages: Vec<(String, Vec<String>)> = cat_lt.into_iter().collect();
+        pages.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.1.cmp(&b.1)));
+        for (link, path) in pages {
+            parse_sub_category(&mut families, &mut orphans, link, path).await?;
             pb.inc(1);
         }
 
         duration = start.elapsed();
         pb.finish_and_clear();
         println!("Parsing sub categories took {:?}", duration);
+        println!(
+            "Found {} families, {} categories have none",
+            families.len(),
+            orphans.len()
+        );
 
-        let pb = ProgressBar::new(cat_num.len() as u64);
+        let pb = ProgressBar::new(families.len() as u64);
         pb.set_style(llh::PB_STYLE.clone());
         pb.set_message("Fetching criteria information...");
 
         start = Instant::now();
 
-        for c in &cat_num {
-            load_criteria(&mut tl, c.1.clone()).await?;
+        for f in &families {
+            load_criteria(&mut tl, f.id.clone()).await?;
             pb.inc(1);
         }
 
@@ -149,13 +190,13 @@ async fn main() -> Result<(), reqwest::Error> {
         pb.finish_and_clear();
         println!("Fetching criteria information took {:?}", duration);
 
-        let pb = ProgressBar::new(cat_num.len() as u64);
+        let pb = ProgressBar::new(families.len() as u64);
         pb.set_style(llh::PB_STYLE.clone());
         pb.set_message("Fetching results...");
         start = Instant::now();
 
-        for c in &cat_num {
-            load_results(&mut db, c.1.clone()).await?;
+        for f in &mut families {
+            load_results(&mut db, &mut provenance, f, policy).await?;
             pb.inc(1);
         }
 
@@ -163,9 +204,25 @@ async fn main() -> Result<(), reqwest::Error> {
         pb.finish_and_clear();
         println!("Fetching results took {:?}", duration);
 
+        let conflicts = provenance
+            .values()
+            .filter(|p| !p.conflicts.is_empty())
+            .count();
+        if conflicts > 0 {
+            println!(
+                "{} parts have conflicting values across families, see json/ti/provenance.json",
+                conflicts
+            );
+        }
+
         // write out the data we produced
         llh::dump_json("json/ti/categories.json", tl.clone());
         llh::dump_json("json/ti/data.json", db.clone());
+        llh::dump_json("json/ti/provenance.json", provenance.clone());
+        llh::dump_json("json/ti/families.json", families.clone());
+        llh::dump_json("json/ti/tree.json", tree.clone());
+        orphans.sort_by(|a, b| a.path.cmp(&b.path));
+        llh::dump_json("json/ti/categories_without_family.json", orphans.clone());
     }
 
     if matches.is_present("download")
@@ -178,7 +235,7 @@ async fn main() -> Result<(), reqwest::Error> {
         let path = Path::new("json/ti/data.json");
         let display = path.display();
 
-        let file = match File::open(&path) {
+        let file = match File::open(path) {
             Err(why) => panic!("couldn't open {}: {}", display, why),
             Ok(file) => file,
         };
@@ -217,11 +274,12 @@ async fn main() -> Result<(), reqwest::Error> {
             .collect::<Vec<&str>>()
             .contains(&"techdocs")
     {
-        let techdocs = Arc::new(Mutex::new(HashMap::new()));
+        let documents = Arc::new(Mutex::new(Vec::new()));
+        let cad_links = Arc::new(Mutex::new(Vec::new()));
         let path = Path::new("json/ti/data.json");
         let display = path.display();
 
-        let file = match File::open(&path) {
+        let file = match File::open(path) {
             Err(why) => panic!("couldn't open {}: {}", display, why),
             Ok(file) => file,
         };
@@ -240,10 +298,9 @@ async fn main() -> Result<(), reqwest::Error> {
 
         urls.for_each(|x| async {
             match x {
-                Ok(m) => {
-                    // merge with what we already have
-                    let mut techdocs = techdocs.lock().unwrap();
-                    techdocs.extend(m);
+                Ok((docs, cad)) => {
+                    documents.lock().unwrap().extend(docs);
+                    cad_links.lock().unwrap().extend(cad);
                     pb.inc(1);
                 }
                 Err(e) => {
@@ -253,73 +310,253 @@ async fn main() -> Result<(), reqwest::Error> {
         })
         .await;
 
-        let db = techdocs.lock().unwrap();
-        llh::dump_json("json/ti/techdocs.json", db.clone());
+        // one entry per document with all the parts linking it, techdocs.json keeps the
+        // href -> title map without the datasheets for older tools
+        let documents = document::merge(documents.lock().unwrap().drain(..));
+        let techdocs: HashMap<&String, &String> = documents
+            .iter()
+            .filter(|d| d.kind != DocumentKind::Datasheet)
+            .map(|d| (&d.url, &d.title))
+            .collect();
+        llh::dump_json("json/ti/techdocs.json", techdocs);
+        llh::dump_json("json/ti/documents.json", &documents);
+        // the errata revisions are kept across crawls for llh watch
+        let mut errata = errata::History::load(Path::new("."), "ti");
+        errata.update(&documents, &Utc::now().to_rfc3339());
+        llh::dump_json("json/ti/errata.json", errata);
+        // the CAD symbols and footprints of every package, mostly on portals
+        llh::dump_json("json/ti/ecad.json", cad_links.lock().unwrap().clone());
     }
 
-    if matches.is_present("download")
+    if matches.is_present("database")
         && matches
-            .values_of("download")
+            .values_of("database")
             .unwrap()
             .collect::<Vec<&str>>()
-            .contains(&"techdocs")
+            .contains(&"orderables")
     {
-        let path = Path::new("json/ti/techdocs.json");
+        let orderables = Arc::new(Mutex::new(HashMap::new()));
+        let path = Path::new("json/ti/data.json");
         let display = path.display();
 
-        let file = match File::open(&path) {
+        let file = match File::open(path) {
             Err(why) => panic!("couldn't open {}: {}", display, why),
             Ok(file) => file,
         };
 
-        let db: HashMap<String, String> =
-            serde_json::from_reader(file).expect("unable to parse db");
+        println!("Loading database...");
+
+        db = serde_json::from_reader(file).expect("unable to parse db");
+
+        let pb = ProgressBar::new(db.len() as u64);
+        pb.set_style(llh::PB_STYLE.clone());
+        pb.set_message("Fetching orderables...");
+
+        let pages = stream::iter(db.keys())
+            .map(|part| async move { (part, load_orderables(part).await) })
+            .buffer_unordered(3);
+
+        pages
+            .for_each(|(part, x)| {
+                let orderables = &orderables;
+                let pb = &pb;
+                async move {
+                    match x {
+                        Ok(o) => {
+                            orderables.lock().unwrap().insert(part.clone(), o);
+                            pb.inc(1);
+                        }
+                        Err(e) => eprintln!("Got an error: {}", e),
+                    }
+                }
+            })
+            .await;
+
+        pb.finish_and_clear();
+        let orderables = orderables.lock().unwrap();
+        llh::dump_json("json/ti/orderables.json", orderables.clone());
+    }
+
+    if matches.is_present("download")
+        && matches
+            .values_of("download")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"techdocs")
+    {
+        let docs = document::load(Path::new("."), "ti");
+        if docs.is_empty() {
+            panic!("couldn't open json/ti/documents.json or json/ti/techdocs.json");
+        }
+
+        load_techdocs(
+            docs.into_iter()
+                .filter(|d| kinds.contains(&d.kind))
+                .collect(),
+        )
+        .await;
+    }
 
-        load_techdocs(db).await;
+    if matches.is_present("download")
+        && matches
+            .values_of("download")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"models")
+    {
+        let docs: Vec<document::Document> = document::load(Path::new("."), "ti")
+            .into_iter()
+            .filter(|d| d.kind == DocumentKind::Model)
+            .collect();
+        if docs.is_empty() {
+            panic!("no models in json/ti/documents.json, build the techdocs first");
+        }
+
+        load_techdocs(docs.clone()).await;
+
+        // unpack them into the store of each part
+        let models = model::index(Path::new("."), &docs);
+        println!("Unpacked {} model files", models.len());
+        llh::dump_json("json/ti/models.json", models);
+    }
+
+    if matches.is_present("download")
+        && matches
+            .values_of("download")
+            .unwrap()
+            .collect::<Vec<&str>>()
+            .contains(&"ecad")
+    {
+        let links = ecad::load(Path::new("."), "ti");
+        if links.is_empty() {
+            panic!("no CAD links in json/ti/ecad.json, build the techdocs first");
+        }
+
+        let links = ecad::archive(Path::new("."), links).await;
+        println!(
+            "Archived {} of {} CAD links",
+            links.iter().filter(|c| c.path.is_some()).count(),
+            links.len()
+        );
+        llh::dump_json("json/ti/ecad.json", links);
+    }
+
+    // a digest of what changed for the parts in watchlist.txt, once the parameters and status
+    // are fresh
+    if matches.is_present("database") {
+        llh::watch::after_crawl(Path::new("."));
     }
 
     Ok(())
 }
 
-async fn parse_category(cat_lt: &mut HashSet<String>, link: String) -> Result<(), reqwest::Error> {
+// collect the sub category links of a menu page with their place in the navigation tree,
+// level 2 entries belong to the level 1 entry above them
+async fn parse_category(
+    cat_lt: &mut HashMap<String, Vec<String>>,
+    tree: &mut Category,
+    link: String,
+) -> Result<(), reqwest::Error> {
     let doc = llh::get_doc(link.as_str()).await?;
+    let top = heading(&doc, " – Overview");
+    tree.insert(std::slice::from_ref(&top)).url = Some(link.clone());
+
+    let mut level1 = String::new();
+    for n in doc.find(Class("ti_left-nav-container").descendant(Name("a"))) {
+        let href = match n.attr("href") {
+            Some(href) => href,
+            None => continue,
+        };
+        let name = n.text().trim().to_string();
 
-    doc.find(Class("ti_left-nav-container").descendant(Name("a")))
-        .filter_map(|n| n.attr("href"))
-        .for_each(|x| {
-            cat_lt.insert(String::from(x));
-        });
+        let path = if nav_level(n) == 2 && !level1.is_empty() {
+            vec![top.clone(), level1.clone(), name]
+        } else {
+            level1 = name.clone();
+            vec![top.clone(), name]
+        };
+
+        tree.insert(&path).url = Some(href.to_string());
+        cat_lt.entry(href.to_string()).or_insert(path);
+    }
 
     Ok(())
 }
 
+// the ti-nav-level class of the closest list entry around a navigation link
+fn nav_level(node: Node) -> usize {
+    let mut cur = Some(node);
+    while let Some(n) = cur {
+        let class = n.attr("class").unwrap_or("");
+        if class.contains("ti-nav-level2") {
+            return 2;
+        }
+        if class.contains("ti-nav-level1") || class.contains("ti_left-nav-container") {
+            return 1;
+        }
+        cur = n.parent();
+    }
+    1
+}
+
+// every parametric family on a products page, a page can list several selection tables
 async fn parse_sub_category(
-    m: &mut HashMap<String, String>,
+    families: &mut Vec<Family>,
+    orphans: &mut Vec<Orphan>,
     link: String,
+    path: Vec<String>,
 ) -> Result<(), reqwest::Error> {
-    let s = link.replace("overview.html", "products.html");
-    let doc = llh::get_doc(s.as_str()).await?;
-    let mut category = String::new();
-
-    doc.find(Name("h1"))
-        .map(|n| n.text())
-        .for_each(|x| category = x);
+    let source = link.replace("overview.html", "products.html");
+    let doc = llh::get_doc(source.as_str()).await?;
+    let category = heading(&doc, " – Products");
+
+    let mut found = false;
+    for n in doc.find(Class("rst")) {
+        let id = match n.attr("familyid") {
+            Some(id) => id,
+            None => continue,
+        };
+        found = true;
 
-    category = category
-        .replace(" – Products", "")
-        .replace(" - Products", "")
-        .trim()
-        .to_string();
+        // the same family shows up on the pages of its parent categories too
+        if let Some(f) = families.iter_mut().find(|f| f.id == id) {
+            if f.path != path && !f.also.contains(&path) {
+                f.also.push(path.clone());
+            }
+            continue;
+        }
 
-    doc.find(Class("rst"))
-        .filter_map(|n| n.attr("familyid"))
-        .for_each(|x| {
-            m.insert(category.clone(), String::from(x));
+        // the rows only carry the id, the family is named after the page
+        families.push(Family {
+            id: id.to_string(),
+            title: category.clone(),
+            path: path.clone(),
+            also: Vec::new(),
+            source: source.clone(),
+            parts: Vec::new(),
         });
+    }
+
+    if !found {
+        orphans.push(Orphan { path, source });
+    }
 
     Ok(())
 }
 
+// the last h1 of a page without the " – Products" style suffix
+fn heading(doc: &Document, suffix: &str) -> String {
+    let h1 = doc
+        .find(Name("h1"))
+        .map(|n| n.text())
+        .last()
+        .unwrap_or_default();
+    h1.replace(suffix, "")
+        .replace(&suffix.replace('–', "-"), "")
+        .trim()
+        .to_string()
+}
+
 async fn load_criteria(
     m: &mut HashMap<String, (String, String)>,
     id: String,
@@ -346,12 +583,14 @@ async fn load_criteria(
 
 async fn load_results(
     m: &mut HashMap<String, HashMap<String, serde_json::Value>>,
-    id: String,
+    provenance: &mut HashMap<String, Provenance>,
+    family: &mut Family,
+    policy: Policy,
 ) -> Result<(), reqwest::Error> {
     let client = reqwest::Client::new();
     let url = format!(
         "https://www.ti.com/selectiontool/paramdata/family/{}/results?lang=en&output=json",
-        id
+        family.id
     );
     let res = client
         .get(Url::parse(url.as_str()).unwrap())
@@ -364,70 +603,185 @@ async fn load_results(
     res.results.iter().for_each(|c| {
         // the key o1 should be there by default, otherwise parsing doesn't make much sense anyways
         let key = c.get("o1").unwrap().as_str().unwrap();
-
-        if m.contains_key(key) {
-            let v = m.get(key).unwrap();
-            if v.eq(c) {
-                return;
-            } else {
-                if v.len() < c.len() {
-                    m.insert(key.to_string(), c.clone());
-                } /* else {
-                      println!("Duplicate key {} but newer one has less fields", key);
-                  } */
-            }
-        } else {
-            m.insert(key.to_string(), c.clone());
-        }
+        family.parts.push(key.to_string());
+
+        // a part in several families is merged value by value, the policy only decides
+        // which value wins a conflict
+        merge::merge(
+            m.entry(key.to_string()).or_default(),
+            provenance.entry(key.to_string()).or_default(),
+            c,
+            &family.id,
+            policy,
+        );
     });
 
     Ok(())
 }
 
-async fn load_product_page(id: &String) -> Result<HashMap<String, String>, reqwest::Error> {
-    let mut m = HashMap::new();
+// the technical documents linked from a product page, classified by the type TI tags them
+// with where there is one
+// the technical documentation and design & development sections of a product page
+const RESOURCE_SECTIONS: &[&str] = &["tech-docs", "design-development"];
+
+async fn load_product_page(
+    id: &String,
+) -> Result<(Vec<document::Document>, Vec<CadLink>), reqwest::Error> {
     let url = format!("https://www.ti.com/product/{}", id);
-    llh::get_doc(url.as_str())
-        .await?
+    let page = llh::get_doc(url.as_str()).await?;
+    let doc = |a: Node, href: &str| {
+        let title = a.text().trim().to_string();
+        let navtitle = a.attr("data-navtitle").unwrap_or(&title);
+        // the revision is the letter at the end of the literature number
+        let (_, date) = document::revision_and_date(a);
+        document::Document {
+            vendor: String::from("ti"),
+            url: href.to_string(),
+            kind: DocumentKind::classify(a.attr("data-doctype"), href, navtitle),
+            title,
+            parts: vec![id.clone()],
+            revision: document::lit_number(href).and_then(|(_, r)| r),
+            date,
+        }
+    };
+
+    let mut docs: Vec<document::Document> = page
         .find(Name("ti-techdocs").descendant(Name("a")))
-        .filter(|a| {
-            let title = a.attr("data-navtitle").unwrap();
-            !title.contains("Datasheet") && !title.contains("Data sheet")
-        })
-        .for_each(|a| {
-            m.insert(String::from(a.attr("href").unwrap()), a.text());
+        .filter_map(|a| Some(doc(a, a.attr("href")?)))
+        .collect();
+
+    // design files, models, software and tool pages of the design section, some of them on other
+    // hosts, the rest of the page links to the same kinds of pages for navigation
+    for a in page
+        .find(Name("a"))
+        .filter(|a| llh::within(*a, RESOURCE_SECTIONS))
+    {
+        let href = match a.attr("href") {
+            Some(href) => href,
+            None => continue,
+        };
+        if docs.iter().any(|d| d.url == href) {
+            continue;
+        }
+
+        let d = doc(a, href);
+        let resource = matches!(
+            d.kind,
+            DocumentKind::ReferenceDesign
+                | DocumentKind::DesignFile
+                | DocumentKind::Model
+                | DocumentKind::Software
+                | DocumentKind::Tool
+        );
+        if resource && !d.title.is_empty() {
+            docs.push(d);
+        }
+    }
+
+    Ok((docs, ecad::from_page(&page, "ti", id)))
+}
+
+// the ordering table of the product page links every orderable to its part-details page,
+// the rest of the row holds package, pins, packing and status
+async fn load_orderables(gpn: &str) -> Result<Vec<Orderable>, reqwest::Error> {
+    let url = format!("https://www.ti.com/product/{}", gpn);
+    let doc = llh::get_doc(url.as_str()).await?;
+    let mut out: Vec<Orderable> = Vec::new();
+
+    for a in doc.find(Name("a")) {
+        let opn = match a
+            .attr("href")
+            .and_then(|h| h.split("/part-details/").nth(1))
+        {
+            Some(opn) => opn.split(['?', '#']).next().unwrap().trim_matches('/'),
+            None => continue,
+        };
+        if opn.is_empty() || out.iter().any(|o| o.opn == opn) {
+            continue;
+        }
+
+        let cells: Vec<String> = llh::enclosing_row(a)
+            .map(|row| {
+                row.find(Name("td"))
+                    .map(|td| td.text().trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // package cells look like "SOIC (D) | 8"
+        let package = cells
+            .iter()
+            .find(|c| c.contains('|') && orderable::pins_from_package(c).is_some())
+            .cloned();
+        let status = cells
+            .iter()
+            .find(|c| ["ACTIVE", "NRND", "OBSOLETE", "PREVIEW", "LIFEBUY"].contains(&c.as_str()))
+            .cloned();
+
+        out.push(Orderable {
+            opn: opn.to_string(),
+            gpn: gpn.to_string(),
+            vendor: String::from("ti"),
+            pins: package.as_deref().and_then(orderable::pins_from_package),
+            canonical: package.as_deref().and_then(|p| Package::parse(p, None)),
+            package: package.map(|p| p.split('|').next().unwrap().trim().to_string()),
+            packing: orderable::packing_from_text(&cells.join(" ")),
+            status,
         });
+    }
 
-    Ok(m)
+    Ok(out)
 }
 
-async fn load_techdocs(db: HashMap<String, String>) {
-    // filter out only the lit pdfs for now
-    let keys: HashSet<String> = db
-        .keys()
-        .filter(|key| key.starts_with("/lit/pdf"))
-        .map(|key| String::from(key))
+async fn load_techdocs(docs: Vec<document::Document>) {
+    // whatever has a local path for its kind, the models, design files and software within the
+    // limits of their kind, tool pages and other hosts are only listed in documents.json
+    let files: Vec<document::Document> = docs
+        .into_iter()
+        .filter(|d| d.local_path().is_some())
         .collect();
 
-    let pb = ProgressBar::new(keys.len() as u64);
+    let pb = ProgressBar::new(files.len() as u64);
     pb.set_style(llh::PB_STYLE.clone());
     pb.set_message("Fetching techdocs...");
 
-    let pdfs = stream::iter(keys)
+    let skipped = Arc::new(Mutex::new(0));
+    let downloads = stream::iter(files)
         .map(|doc| async move {
-            llh::save_pdf(
-                format!("https://www.ti.com{}", doc),
-                format!("pdf/ti/lit/{}.pdf", doc.replace("/lit/pdf/", "")),
-            )
-            .await
+            let path = doc.local_path().unwrap();
+            match doc.kind.archive() {
+                Some(a) => {
+                    llh::save_file(doc.absolute_url(), path, a.max_size, a.extensions).await
+                }
+                None => llh::save_pdf(doc.absolute_url(), path).await.map(|_| true),
+            }
         })
         .buffer_unordered(3);
 
-    pdfs.for_each(|x| async {
-        match x {
-            Ok(_) => pb.inc(1),
-            Err(e) => eprintln!("Got an error: {}", e),
-        }
-    })
-    .await;
+    downloads
+        .for_each(|x| {
+            let skipped = &skipped;
+            let pb = &pb;
+            async move {
+                match x {
+                    Ok(saved) => {
+                        if !saved {
+                            *skipped.lock().unwrap() += 1;
+                        }
+                        pb.inc(1)
+                    }
+                    Err(e) => eprintln!("Got an error: {}", e),
+                }
+            }
+        })
+        .await;
+
+    pb.finish_and_clear();
+    let skipped = *skipped.lock().unwrap();
+    if skipped > 0 {
+        println!(
+            "Skipped {} files over the size limit or of an unexpected type",
+            skipped
+        );
+    }
 }
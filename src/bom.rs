@@ -0,0 +1,337 @@
+// bom checks the part numbers of a CSV bill of materials against the archive
+
+use crate::db::{Database, Match};
+use serde::Serialize;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use zip::write::FileOptions;
+
+#[derive(Serialize, Debug)]
+pub struct Line {
+    pub row: usize,
+    pub mpn: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Check {
+    pub row: usize,
+    pub mpn: String,
+    pub vendor: Option<String>,
+    pub part: Option<String>,
+    #[serde(rename = "match")]
+    pub matched: Option<Match>,
+    pub status: Option<String>,
+    pub datasheet: Option<String>,
+    pub issues: Vec<Issue>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Issue {
+    Unknown,
+    // not a listed orderable, the part was guessed from a known orderable suffix
+    Unlisted { suffix: String },
+    Lifecycle { status: String },
+    MissingDatasheet,
+}
+
+// header names that usually hold the manufacturer part number, in order of preference
+const MPN_HEADERS: &[&str] = &[
+    "mpn",
+    "manufacturer part number",
+    "mfr part number",
+    "mfr. part number",
+    "manufacturer pn",
+    "mfr pn",
+    "part number",
+    "partnumber",
+    "pn",
+];
+
+impl Issue {
+    pub fn describe(&self) -> String {
+        match self {
+            Issue::Unknown => String::from("unknown part"),
+            Issue::Unlisted { suffix } => {
+                format!("unlisted orderable, matched by its suffix {}", suffix)
+            }
+            Issue::Lifecycle { status } => format!("lifecycle: {}", status),
+            Issue::MissingDatasheet => String::from("no datasheet in archive"),
+        }
+    }
+}
+
+// read the MPN column of a BOM, column picks it by header name instead of guessing
+pub fn read(path: &Path, column: Option<&str>) -> io::Result<Vec<Line>> {
+    let text = fs::read_to_string(path)?;
+    let rows = parse_csv(&text);
+
+    let header: Vec<String> = match rows.first() {
+        Some(h) => h.iter().map(|c| c.trim().to_lowercase()).collect(),
+        None => return Ok(Vec::new()),
+    };
+
+    let idx = match column {
+        Some(c) => header
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(c.trim()))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("no column named {} in {}", c, path.display()),
+                )
+            })?,
+        None => MPN_HEADERS
+            .iter()
+            .find_map(|m| header.iter().position(|h| h == m))
+            .unwrap_or(0),
+    };
+
+    Ok(rows
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(n, r)| {
+            let mpn = r.get(idx)?.trim();
+            if mpn.is_empty() {
+                None
+            } else {
+                Some(Line {
+                    row: n + 1,
+                    mpn: mpn.to_string(),
+                })
+            }
+        })
+        .collect())
+}
+
+pub fn check(db: &Database, root: &Path, lines: &[Line]) -> Vec<Check> {
+    lines
+        .iter()
+        .map(|l| {
+            let mut c = Check {
+                row: l.row,
+                mpn: l.mpn.clone(),
+                vendor: None,
+                part: None,
+                matched: None,
+                status: None,
+                datasheet: None,
+                issues: Vec::new(),
+            };
+
+            let (part, m) = match db.resolve(&l.mpn) {
+                Some(r) => r,
+                None => {
+                    c.issues.push(Issue::Unknown);
+                    return c;
+                }
+            };
+
+            c.vendor = Some(part.vendor.clone());
+            c.part = Some(part.mpn.clone());
+            // /NOPB alone is still the generic part
+            if let Match::Suffix { suffix } = &m {
+                if !suffix.starts_with('/') {
+                    c.issues.push(Issue::Unlisted {
+                        suffix: suffix.clone(),
+                    });
+                }
+            }
+            c.matched = Some(m);
+            c.status = part.status().map(String::from);
+            c.datasheet = part.datasheet(root);
+
+            if let Some(status) = part.lifecycle_risk() {
+                c.issues.push(Issue::Lifecycle {
+                    status: status.to_string(),
+                });
+            }
+            if c.datasheet.is_none() {
+                c.issues.push(Issue::MissingDatasheet);
+            }
+
+            c
+        })
+        .collect()
+}
+
+// write every datasheet the BOM references into a zip, returns how many were added
+pub fn export_zip(checks: &[Check], root: &Path, out: &Path) -> io::Result<usize> {
+    let mut zip = zip::ZipWriter::new(File::create(out)?);
+    let options = FileOptions::default();
+    let mut added = Vec::new();
+
+    for ds in checks.iter().filter_map(|c| c.datasheet.as_ref()) {
+        if added.contains(ds) {
+            continue;
+        }
+
+        let name = Path::new(ds)
+            .strip_prefix("pdf")
+            .unwrap_or_else(|_| Path::new(ds))
+            .to_string_lossy()
+            .to_string();
+        zip.start_file(name, options)?;
+        zip.write_all(&fs::read(root.join(ds))?)?;
+        added.push(ds.clone());
+    }
+
+    zip.finish()?;
+    Ok(added.len())
+}
+
+// a small CSV reader, quoted fields may contain separators, quotes and newlines
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    // BOM tools from Europe like to export with semicolons
+    let first = text.lines().next().unwrap_or("");
+    let sep = if first.matches(';').count() > first.matches(',').count() {
+        ';'
+    } else {
+        ','
+    };
+
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == sep && !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mpn_column() {
+        let dir = std::env::temp_dir().join(format!("llh-bom-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bom.csv");
+        fs::write(
+            &path,
+            "\u{feff}Ref;Part Number;Mfr Part Number\r\nU1;1234;\"OPA2197IDR\"\r\nU2;5678;\nU3;9;TSV912\n",
+        )
+        .unwrap();
+
+        let lines = read(&path, None).unwrap();
+        assert_eq!(
+            lines
+                .iter()
+                .map(|l| (l.row, l.mpn.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(2, "OPA2197IDR"), (4, "TSV912")]
+        );
+        let lines = read(&path, Some(" part number")).unwrap();
+        assert_eq!(lines[0].mpn, "1234");
+        assert!(read(&path, Some("mpn")).is_err());
+
+        fs::write(&path, "Ref,Value\nU1,OPA2197\n").unwrap();
+        assert_eq!(read(&path, None).unwrap()[0].mpn, "U1");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn quoted_fields() {
+        let rows = parse_csv("a,\"b, \"\"c\"\"\nd\",e\n");
+        assert_eq!(rows, vec![vec!["a", "b, \"c\"\nd", "e"]]);
+    }
+
+    #[test]
+    fn suffixes() {
+        let root = std::env::temp_dir().join(format!("llh-bom-db-{}", std::process::id()));
+        let dir = root.join("json/ti");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("data.json"),
+            serde_json::json!({"OPA2197": {"o1": "OPA2197"}}).to_string(),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("orderables.json"),
+            serde_json::json!({"OPA2197": [{"opn": "OPA2197IDR", "gpn": "OPA2197", "vendor": "ti",
+                "package": null, "pins": null, "packing": null, "status": null}]})
+            .to_string(),
+        )
+        .unwrap();
+        let db = Database::load(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let matched = |mpn: &str| db.resolve(mpn).map(|(p, m)| (p.mpn.clone(), m));
+        let suffix = |s: &str| {
+            Some((
+                String::from("OPA2197"),
+                Match::Suffix {
+                    suffix: s.to_string(),
+                },
+            ))
+        };
+        assert_eq!(
+            matched("opa2197"),
+            Some((String::from("OPA2197"), Match::Exact))
+        );
+        assert_eq!(
+            matched("opa2197idr "),
+            Some((
+                String::from("OPA2197"),
+                Match::Orderable {
+                    opn: String::from("OPA2197IDR")
+                }
+            ))
+        );
+        // the same package in a tube
+        assert_eq!(matched("OPA2197IDT"), suffix("IDT"));
+        assert_eq!(matched("OPA2197IDR/NOPB"), suffix("IDR/NOPB"));
+        assert_eq!(matched("OPA2197/NOPB"), suffix("/NOPB"));
+        // never listed by any orderable
+        assert_eq!(matched("OPA2197XYZ"), None);
+        assert_eq!(matched("OPA"), None);
+
+        let lines = ["OPA2197IDT", "OPA2197/NOPB", "LM358"]
+            .iter()
+            .enumerate()
+            .map(|(row, mpn)| Line {
+                row,
+                mpn: mpn.to_string(),
+            })
+            .collect::<Vec<_>>();
+        let checks = check(&db, &root, &lines);
+        assert_eq!(
+            checks[0].issues,
+            vec![
+                Issue::Unlisted {
+                    suffix: String::from("IDT")
+                },
+                Issue::MissingDatasheet
+            ]
+        );
+        assert_eq!(checks[1].issues, vec![Issue::MissingDatasheet]);
+        assert_eq!(checks[2].issues, vec![Issue::Unknown]);
+    }
+}
@@ -0,0 +1,99 @@
+// category is the category tree of a vendor, with the breadcrumb of every node, the TI tree
+// comes from the left navigation of the menu pages, the ST one from the data page breadcrumbs
+
+use serde::{Deserialize, Serialize};
+
+// separates the names of a breadcrumb when it is written as one string
+pub const SEPARATOR: &str = " > ";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Category {
+    pub name: String,
+    // names from the top of the tree down to and including this one, empty for the root
+    pub path: Vec<String>,
+    pub url: Option<String>,
+    // parts directly in this category
+    #[serde(default)]
+    pub parts: usize,
+    #[serde(default)]
+    pub children: Vec<Category>,
+}
+
+impl Category {
+    pub fn root(vendor: &str) -> Category {
+        Category {
+            name: vendor.to_string(),
+            ..Category::default()
+        }
+    }
+
+    // the node at path, created with all its parents if it isn't there yet
+    pub fn insert(&mut self, path: &[String]) -> &mut Category {
+        let mut node = self;
+        for (i, name) in path.iter().enumerate() {
+            let idx = match node.children.iter().position(|c| c.name == *name) {
+                Some(idx) => idx,
+                None => {
+                    node.children.push(Category {
+                        name: name.clone(),
+                        path: path[..=i].to_vec(),
+                        ..Category::default()
+                    });
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[idx];
+        }
+        node
+    }
+
+    pub fn find(&self, path: &[String]) -> Option<&Category> {
+        let mut node = self;
+        for name in path {
+            node = node.children.iter().find(|c| c.name == *name)?;
+        }
+        Some(node)
+    }
+
+    // parts in this category and everything below it
+    pub fn total(&self) -> usize {
+        self.parts + self.children.iter().map(|c| c.total()).sum::<usize>()
+    }
+
+    // depth first, parents before their children
+    pub fn walk(&self) -> Vec<&Category> {
+        let mut out = vec![self];
+        for c in &self.children {
+            out.extend(c.walk());
+        }
+        out
+    }
+}
+
+// split a breadcrumb like "Products/Amplifiers/Op Amps" or "Amplifiers > Op Amps", the
+// catch-all top levels are dropped
+pub fn split_breadcrumb(s: &str) -> Vec<String> {
+    // names like "DC/DC converters" only survive when there is another separator
+    let seps: &[char] = if s.contains(['>', '|', '›']) {
+        &['>', '|', '›']
+    } else {
+        &['/']
+    };
+    s.split(seps)
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .filter(|x| !x.eq_ignore_ascii_case("home") && !x.eq_ignore_ascii_case("products"))
+        .map(String::from)
+        .collect()
+}
+
+pub fn join(path: &[String]) -> String {
+    path.join(SEPARATOR)
+}
+
+pub fn parse(s: &str) -> Vec<String> {
+    s.split(SEPARATOR)
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect()
+}
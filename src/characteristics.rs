@@ -0,0 +1,589 @@
+// characteristics reads the "Electrical Characteristics" and "Absolute Maximum Ratings" tables
+// of the datasheets from the text of the PDF index into min/typ/max rows with their test
+// conditions, in json/{vendor}/characteristics.json, the parts get them as parameters too
+
+use crate::db::open_json;
+use crate::fulltext::{self, Index};
+use crate::history;
+use crate::param::{self, Param, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Table {
+    AbsoluteMaximum,
+    Electrical,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Spec {
+    pub table: Table,
+    // VOS or Vio, if the row has one
+    pub symbol: Option<String>,
+    pub parameter: String,
+    pub conditions: Option<String>,
+    // in the unit of the row, ±100 is 100
+    pub min: Option<f64>,
+    pub typ: Option<f64>,
+    pub max: Option<f64>,
+    pub unit: String,
+    pub page: u32,
+    // the row had fewer numbers than the table has columns, which ones they are is a guess
+    #[serde(default)]
+    pub guessed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Characteristics {
+    pub vendor: String,
+    pub part: String,
+    // the PDF below the archive root
+    pub source: String,
+    pub specs: Vec<Spec>,
+}
+
+const HEADINGS: &[(&str, Table)] = &[
+    ("absolute maximum ratings", Table::AbsoluteMaximum),
+    ("electrical characteristics", Table::Electrical),
+];
+
+// parameters with a guaranteed minimum, a lone number next to a typical one is the minimum
+const LOWER_BOUNDS: &[&str] = &[
+    "gain",
+    "rejection",
+    "cmrr",
+    "psrr",
+    "bandwidth",
+    "slew",
+    "swing",
+    "short-circuit",
+    "output current",
+];
+
+// page headers and footers in the middle of a table
+const NOISE: &[&str] = &[
+    "copyright",
+    "submit document feedback",
+    "product folder links",
+    "www.",
+];
+
+// the table starts at most this many lines after the heading, or it was the table of contents
+const LINES: usize = 12;
+// and goes on for at most this many pages
+const PAGES: usize = 2;
+
+impl Table {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Table::AbsoluteMaximum => "absolute_maximum",
+            Table::Electrical => "electrical",
+        }
+    }
+}
+
+impl std::str::FromStr for Table {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Table, String> {
+        match s {
+            "absolute_maximum" | "abs" => Ok(Table::AbsoluteMaximum),
+            "electrical" => Ok(Table::Electrical),
+            _ => Err(format!("unknown table {}", s)),
+        }
+    }
+}
+
+impl Spec {
+    // "Input offset voltage (Max) (µV)" for every column the row has, absolute maximum ratings
+    // get their own names so they don't mix with the electrical characteristics
+    pub fn params(&self, source: &str) -> Vec<(String, Param)> {
+        let prefix = match self.table {
+            Table::AbsoluteMaximum => "Absolute maximum ",
+            Table::Electrical => "",
+        };
+        let parameter = match self.table {
+            Table::AbsoluteMaximum => self.parameter.to_lowercase(),
+            Table::Electrical => self.parameter.clone(),
+        };
+        [("Min", self.min), ("Typ", self.typ), ("Max", self.max)]
+            .iter()
+            .filter_map(|(column, v)| {
+                let raw = format!("{} {}", (*v)?, self.unit);
+                let (value, unit) = param::parse_quantity(&raw, None)?;
+                let name = format!("{}{} ({}) ({})", prefix, parameter, column, self.unit);
+                let param = Param {
+                    raw,
+                    value: Value::Number { value, unit },
+                    source: Some(format!("{} page {}", source, self.page)),
+                };
+                Some((name, param))
+            })
+            .collect()
+    }
+}
+
+// the number of a cell, "±10", "–0.5" or "4.5", without a unit
+fn number(token: &str) -> Option<f64> {
+    let t = token.replace(['–', '−'], "-");
+    let (t, tolerance) = match t.strip_prefix('±').or_else(|| t.strip_prefix("+/-")) {
+        Some(rest) => (rest, true),
+        None => (t.as_str(), false),
+    };
+    if !t.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.') {
+        return None;
+    }
+    let n = t.parse::<f64>().ok()?;
+    Some(if tolerance { n.abs() } else { n })
+}
+
+// TI symbols are upper case like VOS, or have no vowels like Tstg, or are a ratio like dVOS/dT,
+// ST ones are announced by a symbol column
+fn symbol_like(token: &str) -> bool {
+    let n = token.chars().count();
+    let upper = token.starts_with(|c: char| c.is_ascii_uppercase())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    let vowels = token.chars().any(|c| "aeiouAEIOU".contains(c));
+    let ratio = token.contains('/') && token.chars().skip(1).any(|c| c.is_ascii_uppercase());
+    (2..=8).contains(&n)
+        && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '/')
+        && (upper || ratio || (!vowels && token.starts_with(|c: char| c.is_ascii_uppercase())))
+}
+
+const OPERATORS: &[&str] = &["=", "≤", "≥", "<", ">"];
+
+// the conditions start at "TA = 25°C", "VS = ±18 V", "over temperature" or a supply rail as in
+// "(V–) + 0.6 V < VO"
+fn condition_start(tokens: &[&str]) -> usize {
+    tokens
+        .iter()
+        .enumerate()
+        .position(|(i, t)| {
+            let next = tokens.get(i + 1).copied().unwrap_or("");
+            t.contains('=')
+                || OPERATORS.contains(&next)
+                || t.starts_with("(V")
+                || ["at", "over", "with"].contains(&t.to_lowercase().as_str())
+        })
+        .unwrap_or(tokens.len())
+}
+
+// the header of the table, the value columns in their order and whether there is a symbol column
+struct Header {
+    columns: Vec<&'static str>,
+    symbol: bool,
+}
+
+fn header(line: &str) -> Option<Header> {
+    let words: Vec<String> = line
+        .split_whitespace()
+        .map(|w| w.trim_end_matches('.').to_lowercase())
+        .collect();
+    if !words.iter().any(|w| w == "unit" || w == "units") {
+        return None;
+    }
+    let columns: Vec<&'static str> = words
+        .iter()
+        .filter_map(|w| match w.as_str() {
+            "min" => Some("min"),
+            "typ" | "nom" => Some("typ"),
+            // absolute maximum ratings with a single value column
+            "max" | "value" | "rating" => Some("max"),
+            _ => None,
+        })
+        .collect();
+    if columns.is_empty() {
+        return None;
+    }
+    Some(Header {
+        columns,
+        symbol: words.first().is_some_and(|w| w == "symbol"),
+    })
+}
+
+// a row is the parameter, the conditions, the numbers and the unit, in that order, the
+// parameter is empty when the row only adds conditions to the one above it
+fn row(line: &str, h: &Header, table: Table, page: u32) -> Option<Spec> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let unit = *tokens.last()?;
+    param::normalise_unit(unit)?;
+
+    // the numbers before the unit, the one after an "=" belongs to the conditions
+    let mut values = Vec::new();
+    let mut start = tokens.len() - 1;
+    while start > 0 && values.len() < h.columns.len() {
+        let n = match number(tokens[start - 1]) {
+            Some(n) => n,
+            None => break,
+        };
+        if start >= 2 && OPERATORS.contains(&tokens[start - 2]) {
+            break;
+        }
+        values.insert(0, n);
+        start -= 1;
+    }
+    if values.is_empty() {
+        return None;
+    }
+
+    let rest = &tokens[..start];
+    let (symbol, rest) = match rest.first() {
+        // TA in "TA = 25°C" starts the conditions
+        Some(s)
+            if (h.symbol || symbol_like(s))
+                && !rest.get(1).is_some_and(|n| OPERATORS.contains(n)) =>
+        {
+            (Some(s.to_string()), &rest[1..])
+        }
+        _ => (None, rest),
+    };
+    let split = condition_start(rest);
+    let parameter = rest[..split].join(" ").trim_end_matches(',').to_string();
+    let conditions = Some(rest[split..].join(" ")).filter(|c| !c.is_empty());
+
+    let mut spec = Spec {
+        table,
+        symbol,
+        parameter,
+        conditions,
+        min: None,
+        typ: None,
+        max: None,
+        unit: unit.to_string(),
+        page,
+        guessed: values.len() < h.columns.len(),
+    };
+    let columns: Vec<&str> = if !spec.guessed {
+        h.columns.clone()
+    } else {
+        let lower = spec.parameter.to_lowercase();
+        let bound = LOWER_BOUNDS.iter().any(|b| lower.contains(b));
+        let order: &[&str] = match (table, bound) {
+            (Table::AbsoluteMaximum, _) => &["min", "max"],
+            (_, true) => &["min", "typ", "max"],
+            (_, false) => &["typ", "max", "min"],
+        };
+        let mut c: Vec<&str> = order
+            .iter()
+            .copied()
+            .filter(|c| h.columns.contains(c))
+            .take(values.len())
+            .collect();
+        // keep them in the order of the table
+        c.sort_by_key(|x| h.columns.iter().position(|y| y == x));
+        // a lone number is the rating, or the typical value of a characteristic
+        if values.len() == 1 {
+            let lone = match table {
+                Table::AbsoluteMaximum => "max",
+                Table::Electrical => "typ",
+            };
+            if h.columns.contains(&lone) {
+                c = vec![lone];
+            }
+        }
+        c
+    };
+    for (c, v) in columns.iter().zip(values) {
+        match *c {
+            "min" => spec.min = Some(v),
+            "typ" => spec.typ = Some(v),
+            _ => spec.max = Some(v),
+        }
+    }
+    Some(spec)
+}
+
+// the next section, a caption or the footnotes end a table
+fn end(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    let caption = lower.starts_with("table ") || lower.starts_with("figure ");
+    history::heading(line) || caption || line.starts_with("(1)")
+}
+
+// the rows of the table after the heading at lines[at], None if there is no header nearby
+fn rows(lines: &[(u32, &str)], at: usize, table: Table) -> Option<Vec<Spec>> {
+    let (start, h) = lines[at + 1..]
+        .iter()
+        .take(LINES)
+        // the next table starts before this one had a header
+        .take_while(|(_, l)| !end(l))
+        .enumerate()
+        .find_map(|(i, (_, l))| header(l).map(|h| (at + 1 + i, h)))?;
+
+    let mut out: Vec<Spec> = Vec::new();
+    // a parameter that wraps onto the line with the numbers
+    let mut pending: Option<&str> = None;
+    let mut i = start + 1;
+    while i < lines.len() {
+        let (page, line) = lines[i];
+        i += 1;
+        let lower = line.to_lowercase();
+        if NOISE.iter().any(|n| lower.contains(n)) || header(line).is_some() {
+            continue;
+        }
+        if end(line) {
+            break;
+        }
+        // the unit wrapped onto a line of its own
+        let mut row = row(line, &h, table, page);
+        if let (None, Some((_, unit))) = (&row, lines.get(i)) {
+            if !unit.contains(' ') && param::normalise_unit(unit).is_some() {
+                row = self::row(&format!("{} {}", line, unit), &h, table, page);
+                i += 1;
+            }
+        }
+        match row {
+            Some(mut s) => {
+                match pending.take() {
+                    Some(p) if s.parameter.is_empty() => s.parameter = p.to_string(),
+                    Some(p) => s.parameter = format!("{} {}", p, s.parameter),
+                    // more conditions for the parameter above
+                    None if s.parameter.is_empty() => {
+                        if let Some(last) = out.last() {
+                            s.parameter = last.parameter.clone();
+                            s.symbol = s.symbol.or_else(|| last.symbol.clone());
+                        }
+                    }
+                    None => {}
+                }
+                if !s.parameter.is_empty() {
+                    out.push(s);
+                }
+            }
+            // group headings like OFFSET VOLTAGE are upper case, the rest is a wrapped parameter
+            None => pending = Some(line).filter(|l| l.chars().any(|c| c.is_lowercase())),
+        }
+    }
+    Some(out)
+}
+
+pub fn parse(vendor: &str, part: &str, source: &str, pages: &[String]) -> Characteristics {
+    let lines: Vec<(u32, &str)> = pages
+        .iter()
+        .enumerate()
+        .flat_map(|(n, p)| p.lines().map(move |l| (n as u32 + 1, l.trim())))
+        .filter(|(_, l)| !l.is_empty())
+        .collect();
+
+    let mut specs: Vec<Spec> = Vec::new();
+    for (at, (page, line)) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+        let table = match HEADINGS.iter().find(|(h, _)| lower.contains(h)) {
+            Some((_, t)) => *t,
+            None => continue,
+        };
+        // the table of contents has the heading too, but no header after it
+        if let Some(rows) = rows(&lines, at, table) {
+            specs.extend(rows.into_iter().filter(|s| s.page <= page + PAGES as u32));
+        }
+    }
+    // a table that is found from two headings is in there twice
+    let mut seen = Vec::new();
+    specs.retain(|s| {
+        let key = (s.table, s.parameter.clone(), s.conditions.clone(), s.page);
+        if seen.contains(&key) {
+            false
+        } else {
+            seen.push(key);
+            true
+        }
+    });
+
+    Characteristics {
+        vendor: vendor.to_string(),
+        part: part.to_string(),
+        source: source.to_string(),
+        specs,
+    }
+}
+
+pub fn load(root: &Path, vendor: &str) -> Vec<Characteristics> {
+    open_json(&root.join(format!("json/{}/characteristics.json", vendor))).unwrap_or_default()
+}
+
+// the parameters of a part from its tables, the first row of a parameter is the one at the
+// standard conditions, later rows are over temperature or other supplies
+pub fn params(c: &Characteristics) -> BTreeMap<String, Param> {
+    let mut out = BTreeMap::new();
+    for s in &c.specs {
+        for (name, p) in s.params(&c.source) {
+            out.entry(name).or_insert(p);
+        }
+    }
+    out
+}
+
+// extract the tables of the datasheets indexed in this run again and drop those of the removed
+// ones, a vendor without characteristics.json gets every indexed datasheet
+pub fn update(root: &Path, index: &Index, changed: &[String], removed: &[String]) {
+    for vendor in ["ti", "st"].iter() {
+        let file = root.join(format!("json/{}/characteristics.json", vendor));
+        let redo = match fulltext::datasheets(index, vendor, !file.exists(), changed, removed) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        let mut all: Vec<Characteristics> = load(root, vendor)
+            .into_iter()
+            .filter(|c| !changed.contains(&c.source) && !removed.contains(&c.source))
+            .collect();
+        for key in redo {
+            let (_, part) = fulltext::datasheet_part(key).unwrap();
+            let c = parse(vendor, &part, key, &index.pages(key));
+            if !c.specs.is_empty() {
+                all.push(c);
+            }
+        }
+        all.sort_by(|a, b| a.part.cmp(&b.part));
+        // only the PDFs of a vendor may have been downloaded so far
+        fs::create_dir_all(file.parent().unwrap()).expect("couldn't create the vendor directory");
+        crate::dump_json(file.to_str().unwrap(), all);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENTS: &str = "Table of Contents\n6.1 Absolute Maximum Ratings .......... 3\n\
+        6.5 Electrical Characteristics .......... 4\n1 Features .......... 1";
+
+    const TI: &str = "6.1 Absolute Maximum Ratings\n\
+        over operating free-air temperature range (unless otherwise noted) (1)\nMIN MAX UNIT\n\
+        VS Supply voltage, VS = (V+) – (V–) 0 40 V\nInput current 10 mA\n\
+        Tstg Storage temperature –65 150 °C\n(1) Stresses beyond those listed.\n\
+        6.5 Electrical Characteristics\n\
+        at TA = 25°C, VS = ±4 V to ±18 V, RL = 10 kOhm (unless otherwise noted)\n\
+        PARAMETER TEST CONDITIONS MIN TYP MAX UNIT\nOFFSET VOLTAGE\n\
+        VOS Input offset voltage ±10 ±100 µV\nTA = –40°C to +125°C ±20 ±250 µV\n\
+        PSRR Power-supply rejection ratio VS = ±4 V to ±18 V 120 140 dB\n\
+        GBW Gain-bandwidth product 10 MHz\n\
+        Open-loop voltage gain (V–) + 0.6 V < VO < (V+) – 0.6 V 126 140\ndB\n\
+        Submit Document Feedback Copyright (c) 2024 Texas Instruments Incorporated\n\
+        IQ Quiescent current per amplifier IO = 0 A 1 1.2 1.5 mA\n7 Detailed Description";
+
+    const ST: &str = "Table 2. Absolute maximum ratings (AMR)\nSymbol Parameter Value Unit\n\
+        VCC Supply voltage 6 V\nTable 3. Electrical characteristics at VCC+ = +5 V\n\
+        Symbol Parameter Conditions Min. Typ. Max. Unit\n\
+        Vio Input offset voltage T = 25 °C 0.1 4.5 mV\n4 Electrical characteristic curves";
+
+    fn specs(text: &str) -> Vec<Spec> {
+        let pages = [CONTENTS.to_string(), text.to_string()];
+        parse("ti", "X", "x.pdf", &pages).specs
+    }
+
+    fn find<'a>(specs: &'a [Spec], table: Table, parameter: &str) -> &'a Spec {
+        specs
+            .iter()
+            .find(|s| s.table == table && s.parameter == parameter)
+            .unwrap()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number("±10"), Some(10.0));
+        assert_eq!(number("–0.5"), Some(-0.5));
+        assert_eq!(number("+/-2"), Some(2.0));
+        assert_eq!(number("4.5"), Some(4.5));
+        assert_eq!(number("VOS"), None);
+    }
+
+    #[test]
+    fn symbols() {
+        assert!(symbol_like("VOS"));
+        assert!(symbol_like("Tstg"));
+        assert!(symbol_like("dVOS/dT"));
+        assert!(!symbol_like("Input"));
+        assert!(!symbol_like("V"));
+    }
+
+    #[test]
+    fn the_contents_are_no_table() {
+        let s = specs(TI);
+        assert!(s.iter().all(|s| s.page == 2));
+    }
+
+    #[test]
+    fn ti_absolute_maximum_ratings() {
+        let s = specs(TI);
+        let vs = find(&s, Table::AbsoluteMaximum, "Supply voltage");
+        assert_eq!(vs.symbol.as_deref(), Some("VS"));
+        assert_eq!(vs.conditions.as_deref(), Some("VS = (V+) – (V–)"));
+        assert_eq!((vs.min, vs.max), (Some(0.0), Some(40.0)));
+        let tstg = find(&s, Table::AbsoluteMaximum, "Storage temperature");
+        assert_eq!(
+            (tstg.min, tstg.max, &tstg.unit[..]),
+            (Some(-65.0), Some(150.0), "°C")
+        );
+        // a lone number is the rating
+        let input = find(&s, Table::AbsoluteMaximum, "Input current");
+        assert_eq!(
+            (input.min, input.max, input.guessed),
+            (None, Some(10.0), true)
+        );
+    }
+
+    #[test]
+    fn ti_electrical_characteristics() {
+        let s = specs(TI);
+        let vos: Vec<&Spec> = s
+            .iter()
+            .filter(|s| s.parameter == "Input offset voltage")
+            .collect();
+        assert_eq!(vos.len(), 2);
+        assert_eq!(
+            (vos[0].typ, vos[0].max, &vos[0].unit[..]),
+            (Some(10.0), Some(100.0), "µV")
+        );
+        // the row below only adds conditions
+        assert_eq!(vos[1].symbol.as_deref(), Some("VOS"));
+        assert_eq!(vos[1].conditions.as_deref(), Some("TA = –40°C to +125°C"));
+        assert_eq!(vos[1].max, Some(250.0));
+
+        // a guaranteed minimum and a typical value
+        let psrr = find(&s, Table::Electrical, "Power-supply rejection ratio");
+        assert_eq!(
+            (psrr.min, psrr.typ, psrr.max),
+            (Some(120.0), Some(140.0), None)
+        );
+        // a lone number is typical
+        let gbw = find(&s, Table::Electrical, "Gain-bandwidth product");
+        assert_eq!((gbw.typ, gbw.guessed), (Some(10.0), true));
+        // the unit on a line of its own
+        let aol = find(&s, Table::Electrical, "Open-loop voltage gain");
+        assert_eq!((aol.min, &aol.unit[..]), (Some(126.0), "dB"));
+        // past the page footer
+        let iq = find(&s, Table::Electrical, "Quiescent current per amplifier");
+        assert_eq!(
+            (iq.min, iq.typ, iq.max, iq.guessed),
+            (Some(1.0), Some(1.2), Some(1.5), false)
+        );
+    }
+
+    #[test]
+    fn st_tables() {
+        let s = specs(ST);
+        let vcc = find(&s, Table::AbsoluteMaximum, "Supply voltage");
+        assert_eq!((vcc.symbol.as_deref(), vcc.max), (Some("VCC"), Some(6.0)));
+        let vio = find(&s, Table::Electrical, "Input offset voltage");
+        assert_eq!(vio.symbol.as_deref(), Some("Vio"));
+        assert_eq!(vio.conditions.as_deref(), Some("T = 25 °C"));
+        assert_eq!((vio.typ, vio.max), (Some(0.1), Some(4.5)));
+    }
+
+    #[test]
+    fn parameters_of_a_part() {
+        let c = parse("ti", "X", "x.pdf", &[CONTENTS.to_string(), TI.to_string()]);
+        let p = params(&c);
+        let vos = &p["Input offset voltage (Max) (µV)"];
+        // the first row is the one at the standard conditions
+        assert_eq!(vos.raw, "100 µV");
+        assert_eq!(vos.source.as_deref(), Some("x.pdf page 2"));
+        assert!(p.contains_key("Absolute maximum supply voltage (Max) (V)"));
+    }
+}
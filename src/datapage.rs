@@ -0,0 +1,144 @@
+// datapage is the parametric table ST serves per category, saved by the crawler under
+// json/st/datapages/{id}.json, rows only carry column ids so they are decoded with the columns
+
+use crate::db::open_json;
+use crate::param::{self, Param};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+#[derive(Deserialize)]
+pub struct Category {
+    pub columns: Vec<HashMap<String, serde_json::Value>>,
+    pub rows: Vec<Product>,
+    #[serde(alias = "levelTitle")]
+    pub level_title: String,
+    #[serde(default)]
+    pub breadcrumb: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Product {
+    #[serde(alias = "productId", default)]
+    pub product_id: String,
+    #[serde(default)]
+    pub path: String,
+    pub cells: Vec<HashMap<String, String>>,
+    #[serde(alias = "productFolderUrl")]
+    pub product_folder_url: String,
+    #[serde(alias = "availableInDistributorStock", default)]
+    pub available_in_distributor_stock: bool,
+    #[serde(alias = "availableAsFreeSample", default)]
+    pub available_as_free_sample: bool,
+    #[serde(alias = "newProductIntroduction", default)]
+    pub new_product_introduction: bool,
+    #[serde(alias = "isNewProduct", default)]
+    pub is_new_product: bool,
+    #[serde(alias = "isPublic", default)]
+    pub is_public: bool,
+}
+
+// a column of the table, ST isn't consistent in how it names the fields so they are looked up
+// under a few names
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Column {
+    pub id: String,
+    pub name: String,
+    pub unit: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+// the column holding the part number
+pub const PART_NUMBER: &str = "1";
+
+pub fn load(path: &Path) -> Option<Category> {
+    open_json(path)
+}
+
+impl Column {
+    fn from_json(c: &HashMap<String, serde_json::Value>) -> Option<Column> {
+        let text = |keys: &[&str]| {
+            keys.iter().find_map(|k| match c.get(*k)? {
+                serde_json::Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+        };
+
+        Some(Column {
+            id: text(&["id", "columnId"])?,
+            name: text(&["name", "title", "label"])?,
+            unit: text(&["unit", "unitLabel", "unitOfMeasure"]),
+            kind: text(&["type", "dataType", "columnType"]),
+        })
+    }
+
+    // the name the parameter is stored under, with the unit at the end like the TI columns
+    pub fn param_name(&self) -> String {
+        match &self.unit {
+            Some(u) if !self.name.contains(&format!("({})", u)) => {
+                format!("{} ({})", self.name, u)
+            }
+            _ => self.name.clone(),
+        }
+    }
+
+    // the unit of the values, "Operating Temperature (°C) max" has it in the middle
+    pub fn value_unit(&self) -> Option<String> {
+        if let Some(u) = &self.unit {
+            return Some(u.clone());
+        }
+        self.name
+            .split('(')
+            .skip(1)
+            .filter_map(|s| s.split(')').next())
+            .map(|s| s.trim())
+            .filter(|s| param::normalise_unit(s).is_some())
+            .last()
+            .map(String::from)
+    }
+}
+
+impl Category {
+    pub fn columns(&self) -> Vec<Column> {
+        self.columns.iter().filter_map(Column::from_json).collect()
+    }
+}
+
+impl Product {
+    pub fn cell(&self, id: &str) -> Option<&String> {
+        self.cells
+            .iter()
+            .find(|c| c.get("columnId").map(|x| x.as_str()) == Some(id))
+            .and_then(|c| c.get("value"))
+    }
+
+    pub fn part_number(&self) -> Option<&String> {
+        self.cell(PART_NUMBER)
+    }
+
+    // the values of a row by parameter name, cells of unknown columns keep their id
+    pub fn params(&self, columns: &[Column]) -> BTreeMap<String, Param> {
+        self.cells
+            .iter()
+            .filter_map(|c| Some((c.get("columnId")?, c.get("value")?)))
+            .filter(|(id, v)| *id != PART_NUMBER && !v.trim().is_empty())
+            .map(|(id, v)| match columns.iter().find(|c| c.id == *id) {
+                Some(c) => (
+                    c.param_name(),
+                    Param::parse_with_unit(v, c.value_unit().as_deref()),
+                ),
+                None => (id.clone(), Param::parse(v)),
+            })
+            .collect()
+    }
+
+    // the raw values of a row by parameter name, as written to json/st/data.json
+    pub fn raw(&self, columns: &[Column]) -> BTreeMap<String, String> {
+        self.params(columns)
+            .into_iter()
+            .map(|(name, p)| (name, p.raw))
+            .collect()
+    }
+}
@@ -0,0 +1,623 @@
+// db loads what the crawlers left under json/ into one list of parts that can be queried offline
+
+use crate::category::{self, Category};
+use crate::characteristics::{self, Characteristics, Spec};
+use crate::datapage;
+use crate::ecad::{self, CadLink};
+use crate::family;
+use crate::fulltext;
+use crate::merge::{Conflict, Provenance};
+use crate::model::{self, ModelFile};
+use crate::orderable::{self, Orderable};
+use crate::pack::{self, PackFile};
+use crate::package::Package;
+use crate::param::{self, Param, Value};
+use crate::pdfmeta::PdfMeta;
+use crate::pinout::{self, Pinout};
+use crate::taxonomy::Taxonomy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Part {
+    pub vendor: String,
+    pub mpn: String,
+    pub category: Option<String>,
+    // breadcrumb down to the category, which is the last entry
+    #[serde(default)]
+    pub category_path: Vec<String>,
+    #[serde(default)]
+    pub unified_category: Option<String>,
+    // the other categories that list the part
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also: Vec<Vec<String>>,
+    // values the copies of the part disagreed on, by parameter name, see json/ti/provenance.json
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<Conflict>,
+    pub url: Option<String>,
+    pub params: BTreeMap<String, Param>,
+    #[serde(default)]
+    pub orderables: Vec<Orderable>,
+    // simulation models unpacked by the crawlers
+    #[serde(default)]
+    pub models: Vec<ModelFile>,
+    // CMSIS .pdsc and .svd files of the device pack
+    #[serde(default)]
+    pub packs: Vec<PackFile>,
+    // CAD symbols, footprints and 3D models, per package where the page said so
+    #[serde(default)]
+    pub cad: Vec<CadLink>,
+    // what the downloaded datasheet says about itself, once it is indexed
+    #[serde(default)]
+    pub datasheet_meta: Option<PdfMeta>,
+    // the pin tables of the datasheet, one per package
+    #[serde(default)]
+    pub pins: Vec<Pinout>,
+    // the electrical characteristics and absolute maximum ratings of the datasheet
+    #[serde(default)]
+    pub characteristics: Vec<Spec>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Database {
+    pub parts: Vec<Part>,
+    #[serde(skip)]
+    by_mpn: HashMap<String, usize>,
+    #[serde(skip)]
+    by_opn: HashMap<String, usize>,
+    // what the listed orderables add to their generic part, IDR or T6TR
+    #[serde(skip)]
+    suffixes: HashSet<String>,
+    // vendor -> category tree
+    #[serde(skip)]
+    pub categories: BTreeMap<String, Category>,
+}
+
+// how a part number from a BOM was matched to a part in the database
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Match {
+    Exact,
+    Orderable { opn: String },
+    Suffix { suffix: String },
+}
+
+// orderable numbers are the generic part plus package, grade and packing codes,
+// e.g. OPA2197IDR or STM32F407VGT6TR, at most this many characters are stripped and only
+// suffixes some listed orderable has
+const MAX_SUFFIX: usize = 10;
+const MIN_GENERIC: usize = 3;
+
+// packing codes at the end of orderable numbers, reels and tubes
+const PACKING: &[&str] = &["TR", "R", "T"];
+
+// parameters holding the lifecycle status, by slug
+pub const STATUS_FIELDS: &[&str] = &["status", "lifecycle", "marketing_status", "product_status"];
+
+// lifecycle states that are a risk in a new design
+const LIFECYCLE_RISKS: &[&str] = &[
+    "nrnd",
+    "not recommended",
+    "obsolete",
+    "discontinued",
+    "eol",
+    "end of life",
+    "last time buy",
+    "ltb",
+];
+
+impl Database {
+    // load every vendor that has data below root, missing vendors are skipped
+    pub fn load(root: &Path) -> Database {
+        let mut db = Database::default();
+        load_ti(&mut db, root);
+        load_st(&mut db, root);
+
+        let mut orderables = orderable::load(root, "ti");
+        orderables.extend(orderable::load(root, "st"));
+        let mut models: HashMap<String, Vec<ModelFile>> = HashMap::new();
+        for m in model::load(root, "ti")
+            .into_iter()
+            .chain(model::load(root, "st"))
+        {
+            models.entry(m.part.clone()).or_default().push(m);
+        }
+        let mut packs: HashMap<String, Vec<PackFile>> = HashMap::new();
+        for f in pack::load(root, "ti")
+            .into_iter()
+            .chain(pack::load(root, "st"))
+        {
+            packs.entry(f.part.clone()).or_default().push(f);
+        }
+        let mut cad: HashMap<String, Vec<CadLink>> = HashMap::new();
+        for c in ecad::load(root, "ti")
+            .into_iter()
+            .chain(ecad::load(root, "st"))
+        {
+            cad.entry(c.part.clone()).or_default().push(c);
+        }
+        let mut pins: HashMap<String, Vec<Pinout>> = HashMap::new();
+        for p in pinout::load(root, "ti")
+            .into_iter()
+            .chain(pinout::load(root, "st"))
+        {
+            pins.entry(p.part.clone()).or_default().push(p);
+        }
+        let mut characteristics: HashMap<String, Characteristics> = HashMap::new();
+        for c in characteristics::load(root, "ti")
+            .into_iter()
+            .chain(characteristics::load(root, "st"))
+        {
+            characteristics.insert(c.part.clone(), c);
+        }
+        let taxonomy = Taxonomy::load(root);
+        let manifest = fulltext::manifest(root);
+
+        // the crawled TI tree also has the categories without parts
+        if let Some(tree) = open_json::<Category>(&root.join("json/ti/tree.json")) {
+            db.categories.insert(String::from("ti"), tree);
+        }
+
+        for (i, p) in db.parts.iter_mut().enumerate() {
+            if !p.category_path.is_empty() {
+                db.categories
+                    .entry(p.vendor.clone())
+                    .or_insert_with(|| Category::root(&p.vendor))
+                    .insert(&p.category_path)
+                    .parts += 1;
+            }
+            p.unified_category = p
+                .category
+                .as_deref()
+                .and_then(|c| taxonomy.classify(&p.vendor, c));
+            db.by_mpn.entry(p.mpn.to_uppercase()).or_insert(i);
+            if let Some(o) = orderables.remove(&p.mpn) {
+                p.orderables = o;
+            }
+            // orderables crawled before the packages were normalised
+            for o in p.orderables.iter_mut().filter(|o| o.canonical.is_none()) {
+                o.canonical = o.package.as_deref().and_then(|s| Package::parse(s, o.pins));
+            }
+            if let Some(m) = models.remove(&p.mpn) {
+                p.models = m;
+            }
+            if let Some(f) = packs.remove(&p.mpn) {
+                p.packs = f;
+            }
+            if let Some(c) = cad.remove(&p.mpn) {
+                p.cad = c;
+            }
+            if let Some(x) = pins.remove(&p.mpn) {
+                p.pins = x;
+            }
+            // the vendor's parameters win over those read from the datasheet
+            if let Some(c) = characteristics.remove(&p.mpn) {
+                for (name, param) in characteristics::params(&c) {
+                    p.params.entry(name).or_insert(param);
+                }
+                p.characteristics = c.specs;
+            }
+            p.datasheet_meta = p
+                .datasheet_path()
+                .and_then(|path| manifest.get(&path))
+                .and_then(|d| d.meta.clone());
+            for o in &p.orderables {
+                let opn = o.opn.to_uppercase();
+                if let Some(suffix) = opn
+                    .split('/')
+                    .next()
+                    .unwrap()
+                    .strip_prefix(&p.mpn.to_uppercase())
+                {
+                    db.suffixes.insert(suffix.to_string());
+                    // the same package on another reel
+                    if let Some(stem) = PACKING.iter().find_map(|x| suffix.strip_suffix(x)) {
+                        db.suffixes.insert(stem.to_string());
+                    }
+                }
+                db.by_opn.entry(opn).or_insert(i);
+            }
+        }
+
+        db
+    }
+
+    pub fn get(&self, mpn: &str) -> Option<&Part> {
+        self.by_mpn
+            .get(&mpn.trim().to_uppercase())
+            .map(|i| &self.parts[*i])
+    }
+
+    // find the part for an orderable number, known orderables first, then the longest known
+    // generic prefix for orderables the vendor data didn't list
+    pub fn resolve(&self, mpn: &str) -> Option<(&Part, Match)> {
+        if let Some(p) = self.get(mpn) {
+            return Some((p, Match::Exact));
+        }
+
+        // folded the way by_opn is keyed
+        let key = mpn.trim().to_uppercase();
+        if let Some(i) = self.by_opn.get(&key) {
+            let p = &self.parts[*i];
+            if let Some(o) = p.orderables.iter().find(|o| o.opn.to_uppercase() == key) {
+                return Some((p, Match::Orderable { opn: o.opn.clone() }));
+            }
+        }
+
+        // TI marks lead-free variants with /NOPB, /3K etc, they are never part of the GPN
+        let mpn = mpn.trim().to_uppercase();
+        let base = mpn.split('/').next().unwrap_or(&mpn);
+        let chars: Vec<(usize, char)> = base.char_indices().collect();
+
+        for strip in 0..=MAX_SUFFIX.min(chars.len().saturating_sub(MIN_GENERIC)) {
+            let cut = chars.len() - strip;
+            let end = chars.get(cut).map_or(base.len(), |(i, _)| *i);
+            let suffix = &base[end..];
+            if strip > 0
+                && !self.suffixes.contains(suffix)
+                && !PACKING.iter().any(|x| {
+                    suffix
+                        .strip_suffix(x)
+                        .is_some_and(|s| self.suffixes.contains(s))
+                })
+            {
+                continue;
+            }
+            if let Some(p) = self.get(&base[..end]) {
+                return Some((
+                    p,
+                    Match::Suffix {
+                        suffix: mpn[end..].to_string(),
+                    },
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+impl Part {
+    pub fn status(&self) -> Option<&str> {
+        STATUS_FIELDS
+            .iter()
+            .find_map(|f| self.params.iter().find(|(name, _)| slug(name) == *f))
+            .map(|(_, p)| p.raw.as_str())
+    }
+
+    // the lifecycle status if it makes the part a risk for new designs
+    pub fn lifecycle_risk(&self) -> Option<&str> {
+        self.status().filter(|s| {
+            let s = s.to_lowercase();
+            LIFECYCLE_RISKS.iter().any(|r| s.contains(r))
+        })
+    }
+
+    // where the crawlers put the datasheet, relative to root
+    pub fn datasheet_path(&self) -> Option<String> {
+        match self.vendor.as_str() {
+            "ti" => Some(format!("pdf/ti/gpn/{}.pdf", self.mpn)),
+            "st" => Some(format!("pdf/st/datasheets/{}.pdf", self.mpn)),
+            _ => None,
+        }
+    }
+
+    // where the crawlers download the datasheet from
+    pub fn datasheet_url(&self) -> Option<String> {
+        match self.vendor.as_str() {
+            "ti" => Some(format!("https://www.ti.com/lit/gpn/{}", self.mpn)),
+            "st" => Some(format!(
+                "https://www.st.com/resource/en/datasheet/{}.pdf",
+                self.mpn
+            )),
+            _ => None,
+        }
+    }
+
+    // the downloaded datasheet relative to root
+    pub fn datasheet(&self, root: &Path) -> Option<String> {
+        let path = self.datasheet_path()?;
+        if root.join(&path).exists() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    // look up a parameter by its slug, "gbw" matches "GBW (Typ) (MHz)" through "gbw_typ"
+    pub fn param(&self, field: &str) -> Option<(&String, &Param)> {
+        let prefix = format!("{}_", field);
+        self.params
+            .iter()
+            .find(|(name, _)| slug(name) == field)
+            .or_else(|| {
+                self.params
+                    .iter()
+                    .find(|(name, _)| slug(name).starts_with(&prefix))
+            })
+    }
+
+    // the packages the part comes in, from the orderables if known and the package parameter
+    // otherwise
+    pub fn packages(&self) -> Vec<Package> {
+        let mut out: Vec<Package> = Vec::new();
+        let found: Vec<Package> = if self.orderables.is_empty() {
+            self.params
+                .iter()
+                .filter(|(name, _)| slug(name).contains("package"))
+                .flat_map(|(_, p)| match &p.value {
+                    Value::Enum { values } => values.clone(),
+                    _ => vec![p.raw.clone()],
+                })
+                .filter_map(|s| Package::parse(&s, None))
+                .collect()
+        } else {
+            self.orderables
+                .iter()
+                .filter_map(|o| o.canonical.clone())
+                .collect()
+        };
+        for p in found {
+            if !out.iter().any(|x| x.name == p.name && x.body == p.body) {
+                out.push(p);
+            }
+        }
+        out
+    }
+}
+
+// turn a parameter name into an identifier usable in filters, the unit suffix is dropped
+pub fn slug(name: &str) -> String {
+    let mut name = name.trim();
+    if param::unit_from_name(name).is_some() {
+        name = name[..name.rfind('(').unwrap()].trim();
+    }
+
+    let mut s = String::new();
+    for c in name.chars().flat_map(|c| c.to_lowercase()) {
+        if c.is_alphanumeric() {
+            s.push(c);
+        } else if !s.is_empty() && !s.ends_with('_') {
+            s.push('_');
+        }
+    }
+
+    s.trim_end_matches('_').to_string()
+}
+
+pub(crate) fn open_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let file = File::open(path).ok()?;
+    match serde_json::from_reader(file) {
+        Err(why) => {
+            eprintln!("could not parse {}: {}", path.display(), why);
+            None
+        }
+        Ok(v) => Some(v),
+    }
+}
+
+fn load_ti(db: &mut Database, root: &Path) {
+    let data: HashMap<String, HashMap<String, serde_json::Value>> =
+        match open_json(&root.join("json/ti/data.json")) {
+            Some(data) => data,
+            None => return,
+        };
+    let names: HashMap<String, (String, String)> =
+        open_json(&root.join("json/ti/categories.json")).unwrap_or_default();
+
+    // a part listed in several families or categories gets the category of the first one, the
+    // others are kept with it
+    let mut paths: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+    for f in family::load(root) {
+        for gpn in &f.parts {
+            let known = paths.entry(gpn.clone()).or_default();
+            for path in std::iter::once(&f.path).chain(&f.also) {
+                if !known.contains(path) {
+                    known.push(path.clone());
+                }
+            }
+        }
+    }
+    let mut provenance: HashMap<String, Provenance> =
+        open_json(&root.join("json/ti/provenance.json")).unwrap_or_default();
+
+    for (gpn, row) in data {
+        let mut params = BTreeMap::new();
+
+        for (cid, v) in row {
+            if cid == "o1" {
+                continue;
+            }
+            let name = names.get(&cid).map(|n| n.0.clone()).unwrap_or(cid);
+            let unit = param::unit_from_name(&name);
+            params.insert(name, Param::from_json(&v, unit.as_deref()));
+        }
+
+        let mut also = paths.remove(&gpn).unwrap_or_default().into_iter();
+        let path = also.next().unwrap_or_default();
+        let conflicts = provenance
+            .remove(&gpn)
+            .map(|p| p.conflicts)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|mut c| {
+                if let Some(n) = names.get(&c.param) {
+                    c.param = n.0.clone();
+                }
+                c
+            })
+            .collect();
+        db.parts.push(Part {
+            vendor: String::from("ti"),
+            url: Some(format!("https://www.ti.com/product/{}", gpn)),
+            category: path.last().cloned(),
+            category_path: path,
+            mpn: gpn,
+            unified_category: None,
+            also: also.collect(),
+            conflicts,
+            params,
+            orderables: Vec::new(),
+            models: Vec::new(),
+            packs: Vec::new(),
+            cad: Vec::new(),
+            datasheet_meta: None,
+            pins: Vec::new(),
+            characteristics: Vec::new(),
+        });
+    }
+}
+
+// a part on several data pages is one part with the columns of all of them, in the category of
+// the first page, like json/st/data.json has it
+fn load_st(db: &mut Database, root: &Path) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for path in json_files(&root.join("json/st/datapages")) {
+        let cat = match datapage::load(&path) {
+            Some(cat) => cat,
+            None => continue,
+        };
+
+        // the breadcrumb may or may not end with the page itself
+        let mut path = category::split_breadcrumb(&cat.breadcrumb);
+        if path.last() != Some(&cat.level_title) {
+            path.push(cat.level_title.clone());
+        }
+
+        let columns = cat.columns();
+        for row in &cat.rows {
+            let pn = match row.part_number() {
+                Some(pn) => pn,
+                None => continue,
+            };
+            if let Some(&i) = seen.get(pn) {
+                db.parts[i].params.extend(row.params(&columns));
+                continue;
+            }
+            seen.insert(pn.clone(), db.parts.len());
+            db.parts.push(Part {
+                vendor: String::from("st"),
+                mpn: pn.clone(),
+                category: Some(cat.level_title.clone()),
+                category_path: path.clone(),
+                unified_category: None,
+                also: Vec::new(),
+                conflicts: Vec::new(),
+                url: Some(format!("https://www.st.com{}", row.product_folder_url)),
+                params: row.params(&columns),
+                orderables: Vec::new(),
+                models: Vec::new(),
+                packs: Vec::new(),
+                cad: Vec::new(),
+                datasheet_meta: None,
+                pins: Vec::new(),
+                characteristics: Vec::new(),
+            });
+        }
+    }
+}
+
+pub(crate) fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Err(_) => return Vec::new(),
+        Ok(paths) => paths
+            .filter_map(|p| p.ok())
+            .map(|p| p.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .collect(),
+    };
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(title: &str, column: &str, value: &str) -> String {
+        serde_json::json!({
+            "columns": [{"id": 1, "name": "Part Number"}, {"id": 2, "name": column}],
+            "rows": [{
+                "cells": [{"columnId": "1", "value": "TSV912"}, {"columnId": "2", "value": value}],
+                "productFolderUrl": "/en/amplifiers/tsv912.html"
+            }],
+            "levelTitle": title,
+            "breadcrumb": "Products/Amplifiers"
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn st_parts_on_several_pages_are_one() {
+        let root = std::env::temp_dir().join(format!("llh-db-{}", std::process::id()));
+        let dir = root.join("json/st/datapages");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("1.json"), page("Op Amps", "GBP typ (MHz)", "8")).unwrap();
+        fs::write(
+            dir.join("2.json"),
+            page("Low power", "Supply Voltage (V) max", "5.5"),
+        )
+        .unwrap();
+
+        let mut db = Database::default();
+        load_st(&mut db, &root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(db.parts.len(), 1);
+        let p = &db.parts[0];
+        assert_eq!(p.category.as_deref(), Some("Op Amps"));
+        assert!(p.param("gbp").is_some());
+        assert!(p.param("supply_voltage_v").is_some());
+    }
+
+    #[test]
+    fn ti_parts_keep_every_family_and_conflict() {
+        let root = std::env::temp_dir().join(format!("llh-db-ti-{}", std::process::id()));
+        let dir = root.join("json/ti");
+        fs::create_dir_all(&dir).unwrap();
+        let write =
+            |name: &str, v: serde_json::Value| fs::write(dir.join(name), v.to_string()).unwrap();
+        write(
+            "data.json",
+            serde_json::json!({"OPA2197": {"o1": "OPA2197", "p1": "36"}}),
+        );
+        write(
+            "categories.json",
+            serde_json::json!({"p1": ["Total supply voltage (Max) (V)", ""]}),
+        );
+        write(
+            "families.json",
+            serde_json::json!([
+                {"id": "1", "title": "Op amps", "path": ["Amplifiers", "Op amps"],
+                 "also": [["Amplifiers", "Precision op amps"]], "source": "", "parts": ["OPA2197"]},
+                {"id": "2", "title": "Audio", "path": ["Audio", "Op amps"], "source": "",
+                 "parts": ["OPA2197"]}
+            ]),
+        );
+        write(
+            "provenance.json",
+            serde_json::json!({"OPA2197": {"sources": [], "params": {},
+                "conflicts": [{"param": "p1", "values": [["1", "36"], ["2", "40"]]}]}}),
+        );
+
+        let mut db = Database::default();
+        load_ti(&mut db, &root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let p = &db.parts[0];
+        assert_eq!(p.category_path, vec!["Amplifiers", "Op amps"]);
+        assert_eq!(
+            p.also,
+            vec![
+                vec!["Amplifiers", "Precision op amps"],
+                vec!["Audio", "Op amps"]
+            ]
+        );
+        assert_eq!(p.conflicts.len(), 1);
+        assert_eq!(p.conflicts[0].param, "Total supply voltage (Max) (V)");
+        assert_eq!(p.conflicts[0].values[1].1, serde_json::json!("40"));
+    }
+}
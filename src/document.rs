@@ -0,0 +1,755 @@
+// document classifies the technical documents of the vendors (datasheets, errata, app notes, ...)
+// from the vendor's own labels, the URL and the title, in that order
+
+use crate::db::open_json;
+use select::node::Node;
+use select::predicate::Name;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    Datasheet,
+    Errata,
+    ReferenceManual,
+    ProgrammingManual,
+    ApplicationNote,
+    UserGuide,
+    TechnicalNote,
+    WhitePaper,
+    ProductBrief,
+    SelectionGuide,
+    ReferenceDesign,
+    DesignFile,
+    Model,
+    Software,
+    Tool,
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Document {
+    pub vendor: String,
+    pub url: String,
+    pub title: String,
+    pub kind: DocumentKind,
+    // generic part numbers whose product page links the document
+    #[serde(default)]
+    pub parts: Vec<String>,
+    // as the product page lists them, the date as YYYY-MM-DD
+    #[serde(default)]
+    pub revision: Option<String>,
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+// names for every kind, the first is the canonical one used in JSON and on the command line
+const NAMES: &[(DocumentKind, &[&str])] = &[
+    (DocumentKind::Datasheet, &["datasheet", "data sheet", "ds"]),
+    (
+        DocumentKind::Errata,
+        &["errata", "errata sheet", "silicon errata"],
+    ),
+    (
+        DocumentKind::ReferenceManual,
+        &["reference_manual", "reference manual", "trm"],
+    ),
+    (
+        DocumentKind::ProgrammingManual,
+        &["programming_manual", "programming manual"],
+    ),
+    (
+        DocumentKind::ApplicationNote,
+        &["application_note", "application note", "app note", "an"],
+    ),
+    (
+        DocumentKind::UserGuide,
+        &["user_guide", "user guide", "user manual", "ug"],
+    ),
+    (
+        DocumentKind::TechnicalNote,
+        &["technical_note", "technical note", "technical article"],
+    ),
+    (
+        DocumentKind::WhitePaper,
+        &["white_paper", "white paper", "wp"],
+    ),
+    (
+        DocumentKind::ProductBrief,
+        &[
+            "product_brief",
+            "product brief",
+            "data brief",
+            "product overview",
+        ],
+    ),
+    (
+        DocumentKind::SelectionGuide,
+        &["selection_guide", "selection guide", "brochure"],
+    ),
+    (
+        DocumentKind::ReferenceDesign,
+        &["reference_design", "reference design"],
+    ),
+    (
+        DocumentKind::DesignFile,
+        &[
+            "design_file",
+            "design file",
+            "design files",
+            "gerber",
+            "cad",
+        ],
+    ),
+    (
+        DocumentKind::Model,
+        &["model", "simulation model", "spice", "ibis"],
+    ),
+    (DocumentKind::Software, &["software", "firmware", "sw"]),
+    (DocumentKind::Tool, &["tool", "tool page"]),
+    (DocumentKind::Other, &["other"]),
+];
+
+// path fragments of the document URLs, TI files its literature by type under /lit/
+const URLS: &[(&str, DocumentKind)] = &[
+    ("/lit/ds/", DocumentKind::Datasheet),
+    ("/lit/gpn/", DocumentKind::Datasheet),
+    ("/lit/er/", DocumentKind::Errata),
+    ("/lit/an/", DocumentKind::ApplicationNote),
+    ("/lit/ug/", DocumentKind::UserGuide),
+    ("/lit/wp/", DocumentKind::WhitePaper),
+    ("/lit/po/", DocumentKind::ProductBrief),
+    ("/lit/sg/", DocumentKind::SelectionGuide),
+    ("/lit/sl/", DocumentKind::SelectionGuide),
+    ("/lit/ta/", DocumentKind::TechnicalNote),
+    // sbom, slvm, slom and snom are TI's model packages, tidc/tidr the files of the reference
+    // designs
+    ("/lit/zip/sbom", DocumentKind::Model),
+    ("/lit/zip/slvm", DocumentKind::Model),
+    ("/lit/zip/slom", DocumentKind::Model),
+    ("/lit/zip/snom", DocumentKind::Model),
+    ("/lit/zip/tidc", DocumentKind::DesignFile),
+    ("/lit/zip/tidr", DocumentKind::DesignFile),
+    ("/lit/df/", DocumentKind::DesignFile),
+    ("/lit/sw/", DocumentKind::Software),
+    ("/tool/download/", DocumentKind::Software),
+    ("/tool/tida-", DocumentKind::ReferenceDesign),
+    ("/tool/tidep-", DocumentKind::ReferenceDesign),
+    ("/tool/", DocumentKind::Tool),
+    ("/resource/en/ibis_model/", DocumentKind::Model),
+    ("/resource/en/spice_model/", DocumentKind::Model),
+    ("/resource/en/cad_symbols/", DocumentKind::DesignFile),
+    ("/resource/en/datasheet/", DocumentKind::Datasheet),
+    ("/resource/en/errata_sheet/", DocumentKind::Errata),
+    (
+        "/resource/en/reference_manual/",
+        DocumentKind::ReferenceManual,
+    ),
+    (
+        "/resource/en/programming_manual/",
+        DocumentKind::ProgrammingManual,
+    ),
+    (
+        "/resource/en/application_note/",
+        DocumentKind::ApplicationNote,
+    ),
+    ("/resource/en/user_manual/", DocumentKind::UserGuide),
+    ("/resource/en/technical_note/", DocumentKind::TechnicalNote),
+    ("/resource/en/white_paper/", DocumentKind::WhitePaper),
+    ("/resource/en/data_brief/", DocumentKind::ProductBrief),
+    (
+        "/resource/en/product_presentation/",
+        DocumentKind::ProductBrief,
+    ),
+    ("/resource/en/brochure/", DocumentKind::SelectionGuide),
+];
+
+// file types of the last path segment, checked after the paths
+const EXTENSIONS: &[(&str, DocumentKind)] = &[
+    ("ibs", DocumentKind::Model),
+    ("cir", DocumentKind::Model),
+    ("lib", DocumentKind::Model),
+    ("tsc", DocumentKind::Model),
+];
+
+// title words, checked after the URL, errata first as "errata for the datasheet" is no datasheet
+const TITLES: &[(&str, DocumentKind)] = &[
+    ("errata", DocumentKind::Errata),
+    ("spice", DocumentKind::Model),
+    ("ibis", DocumentKind::Model),
+    ("tina-ti", DocumentKind::Model),
+    ("simulation model", DocumentKind::Model),
+    ("gerber", DocumentKind::DesignFile),
+    ("design files", DocumentKind::DesignFile),
+    ("bill of materials", DocumentKind::DesignFile),
+    ("layout files", DocumentKind::DesignFile),
+    ("reference design", DocumentKind::ReferenceDesign),
+    ("firmware", DocumentKind::Software),
+    ("software", DocumentKind::Software),
+    ("reference manual", DocumentKind::ReferenceManual),
+    ("technical reference", DocumentKind::ReferenceManual),
+    ("programming manual", DocumentKind::ProgrammingManual),
+    ("programmer's guide", DocumentKind::ProgrammingManual),
+    ("application note", DocumentKind::ApplicationNote),
+    ("application report", DocumentKind::ApplicationNote),
+    ("user's guide", DocumentKind::UserGuide),
+    ("user guide", DocumentKind::UserGuide),
+    ("user manual", DocumentKind::UserGuide),
+    ("technical note", DocumentKind::TechnicalNote),
+    ("white paper", DocumentKind::WhitePaper),
+    ("data brief", DocumentKind::ProductBrief),
+    ("product brief", DocumentKind::ProductBrief),
+    ("product overview", DocumentKind::ProductBrief),
+    ("selection guide", DocumentKind::SelectionGuide),
+    ("datasheet", DocumentKind::Datasheet),
+    ("data sheet", DocumentKind::Datasheet),
+];
+
+// kinds that are archived besides the PDFs, each with the largest file worth keeping and the
+// file types that are expected, anything else (installers, login pages) is skipped
+pub struct Archive {
+    pub kind: DocumentKind,
+    pub dir: &'static str,
+    pub max_size: u64,
+    pub extensions: &'static [&'static str],
+}
+
+const MB: u64 = 1024 * 1024;
+
+// hosts below the vendor's domain that don't serve documents, the forums
+const FORUMS: &[&str] = &["e2e.ti.com", "community.st.com"];
+
+pub const ARCHIVE: &[Archive] = &[
+    Archive {
+        kind: DocumentKind::Model,
+        dir: "models",
+        max_size: 50 * MB,
+        extensions: &[
+            "zip", "lib", "cir", "mod", "sp", "ckt", "ibs", "tsc", "tsm", "txt",
+        ],
+    },
+    Archive {
+        kind: DocumentKind::DesignFile,
+        dir: "design-files",
+        max_size: 100 * MB,
+        extensions: &["zip", "pdf"],
+    },
+    Archive {
+        kind: DocumentKind::Software,
+        dir: "software",
+        max_size: 200 * MB,
+        extensions: &["zip"],
+    },
+];
+
+// the file type of the last segment of a path without the query
+fn extension(path: &str) -> Option<&str> {
+    let name = path.rsplit('/').next().unwrap_or("");
+    match name.rsplit_once('.') {
+        Some((_, ext)) if !ext.is_empty() && ext.len() <= 4 => Some(ext),
+        _ => None,
+    }
+}
+
+impl DocumentKind {
+    // vendor label first, then the URL and the title
+    pub fn classify(label: Option<&str>, url: &str, title: &str) -> DocumentKind {
+        if let Some(k) = label.and_then(|l| l.parse().ok()) {
+            return k;
+        }
+
+        let url = url.to_lowercase();
+        let path = url.split(['?', '#']).next().unwrap_or("");
+        if let Some((_, k)) = URLS.iter().find(|(u, _)| path.contains(u)) {
+            return *k;
+        }
+        if let Some(ext) = extension(path) {
+            if let Some((_, k)) = EXTENSIONS.iter().find(|(e, _)| *e == ext) {
+                return *k;
+            }
+        }
+
+        let title = title.to_lowercase();
+        TITLES
+            .iter()
+            .find(|(t, _)| title.contains(t))
+            .map_or(DocumentKind::Other, |(_, k)| *k)
+    }
+
+    pub fn all() -> Vec<DocumentKind> {
+        NAMES.iter().map(|(k, _)| *k).collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        NAMES.iter().find(|(k, _)| k == self).unwrap().1[0]
+    }
+
+    // how documents of this kind are archived if they aren't plain PDFs
+    pub fn archive(&self) -> Option<&'static Archive> {
+        ARCHIVE.iter().find(|a| a.kind == *self)
+    }
+}
+
+impl FromStr for DocumentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DocumentKind, String> {
+        let s = s.trim().to_lowercase().replace('-', "_");
+        NAMES
+            .iter()
+            .find(|(_, names)| names.iter().any(|n| *n == s || n.replace(' ', "_") == s))
+            .map(|(k, _)| *k)
+            .ok_or_else(|| format!("unknown document kind {}", s))
+    }
+}
+
+impl fmt::Display for DocumentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Document {
+    // the URL on the vendor's site, the crawlers keep them relative
+    pub fn absolute_url(&self) -> String {
+        let host = match self.vendor.as_str() {
+            "ti" => "https://www.ti.com",
+            "st" => "https://www.st.com",
+            _ => "",
+        };
+        if self.url.starts_with('/') {
+            format!("{}{}", host, self.url)
+        } else {
+            self.url.clone()
+        }
+    }
+
+    // what stays the same across revisions, the literature number for TI, else the URL
+    pub fn key(&self) -> String {
+        match lit_number(&self.url) {
+            Some((number, _)) if self.vendor == "ti" => number,
+            _ => self.url.split(['?', '#']).next().unwrap_or("").to_string(),
+        }
+    }
+
+    // resources on other hosts or the forums are recorded but never archived
+    pub fn external(&self) -> bool {
+        if self.url.starts_with('/') {
+            return false;
+        }
+        let url = self.absolute_url().to_lowercase();
+        let host = url
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split(['/', '?', '#']).next())
+            .unwrap_or("");
+        !host.ends_with(&format!(".{}.com", self.vendor)) || FORUMS.contains(&host)
+    }
+
+    // the file type from the URL, TI's /lit/ links have none but tell it by the path
+    pub fn extension(&self) -> Option<String> {
+        let path = self.url.split(['?', '#']).next().unwrap_or("");
+        match extension(path) {
+            Some(ext) => Some(ext.to_lowercase()),
+            _ if path.contains("/lit/zip/") => Some(String::from("zip")),
+            _ if path.contains("/lit/") || path.contains("/resource/") => Some(String::from("pdf")),
+            _ => None,
+        }
+    }
+
+    // where the crawler saves the document, if it downloads documents of this kind at all
+    pub fn local_path(&self) -> Option<String> {
+        if let Some(a) = self.kind.archive() {
+            if self.external() {
+                return None;
+            }
+            let ext = self.extension()?;
+            let path = self.url.split(['?', '#']).next().unwrap_or("");
+            let name = path.trim_end_matches('/').rsplit('/').next()?;
+            let stem = name.strip_suffix(&format!(".{}", ext)).unwrap_or(name);
+            return Some(format!("files/{}/{}/{}.{}", self.vendor, a.dir, stem, ext));
+        }
+
+        match self.vendor.as_str() {
+            // the literature below /lit/ whatever its kind, the datasheets where the datasheet
+            // crawl puts them, tool pages aren't documents
+            "ti" if !self.external() => {
+                let path = self.url.split(['?', '#']).next().unwrap_or("");
+                let path = &path[path.find("/lit/")?..];
+                if let Some(gpn) = path.strip_prefix("/lit/gpn/") {
+                    return Some(format!(
+                        "pdf/ti/gpn/{}.pdf",
+                        gpn.trim_end_matches('/').to_uppercase()
+                    ));
+                }
+                lit_number(path)?;
+                let name = path.trim_end_matches('/').rsplit('/').next()?;
+                let name = name.strip_suffix(".pdf").unwrap_or(name);
+                Some(format!("pdf/ti/lit/{}.pdf", name))
+            }
+            "st" => Some(format!(
+                "pdf/st/techdocs/{}.pdf",
+                self.title.replace('/', "_")
+            )),
+            _ => None,
+        }
+    }
+}
+
+// the kind of every downloaded document by its path below the archive, datasheets are saved
+// by part number and never show up in the document lists
+pub struct Kinds(HashMap<String, DocumentKind>);
+
+impl Kinds {
+    pub fn load(root: &Path) -> Kinds {
+        Kinds(
+            ["ti", "st"]
+                .iter()
+                .flat_map(|v| load(root, v))
+                .filter_map(|d| Some((d.local_path()?, d.kind)))
+                .collect(),
+        )
+    }
+
+    pub fn of(&self, path: &str) -> DocumentKind {
+        if path.starts_with("pdf/ti/gpn/") || path.starts_with("pdf/st/datasheets/") {
+            return DocumentKind::Datasheet;
+        }
+        self.0.get(path).copied().unwrap_or(DocumentKind::Other)
+    }
+}
+
+// the kinds named on the command line, without any everything but datasheets, which are
+// fetched by part number, and software, which has to be asked for
+pub fn kinds<'a>(
+    names: Option<impl Iterator<Item = &'a str>>,
+) -> Result<Vec<DocumentKind>, String> {
+    match names {
+        Some(names) => names.map(|n| n.parse()).collect(),
+        None => Ok(DocumentKind::all()
+            .into_iter()
+            .filter(|k| *k != DocumentKind::Datasheet && *k != DocumentKind::Software)
+            .collect()),
+    }
+}
+
+// the documents of a vendor, archives from before the classification only have techdocs.json
+// and are classified on the fly
+pub fn load(root: &Path, vendor: &str) -> Vec<Document> {
+    if let Some(docs) = open_json(&root.join(format!("json/{}/documents.json", vendor))) {
+        return docs;
+    }
+
+    let techdocs: HashMap<String, String> =
+        open_json(&root.join(format!("json/{}/techdocs.json", vendor))).unwrap_or_default();
+    let mut docs: Vec<Document> = techdocs
+        .into_iter()
+        .map(|(a, b)| {
+            // TI keeps href -> title, ST title -> href
+            let (url, title) = if vendor == "st" { (b, a) } else { (a, b) };
+            Document {
+                vendor: vendor.to_string(),
+                kind: DocumentKind::classify(None, &url, &title),
+                url,
+                title,
+                parts: Vec::new(),
+                revision: None,
+                date: None,
+            }
+        })
+        .collect();
+    docs.sort_by(|a, b| a.url.cmp(&b.url));
+    docs
+}
+
+// merge documents found on several product pages into one entry per URL
+pub fn merge(docs: impl IntoIterator<Item = Document>) -> Vec<Document> {
+    let mut by_url: HashMap<String, Document> = HashMap::new();
+    for d in docs {
+        match by_url.get_mut(&d.url) {
+            Some(e) => {
+                e.revision = e.revision.take().or(d.revision);
+                e.date = e.date.take().or(d.date);
+                for p in d.parts {
+                    if !e.parts.contains(&p) {
+                        e.parts.push(p);
+                    }
+                }
+            }
+            None => {
+                by_url.insert(d.url.clone(), d);
+            }
+        }
+    }
+
+    let mut out: Vec<Document> = by_url.into_values().collect();
+    out.sort_by(|a, b| a.url.cmp(&b.url));
+    out
+}
+
+// TI literature numbers end in the revision letters, sprz412m is revision M of sprz412, the
+// first release has none and AA follows Z
+pub fn lit_number(url: &str) -> Option<(String, Option<String>)> {
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    if !path.contains("/lit/") {
+        return None;
+    }
+    let name = path.trim_end_matches('/').rsplit('/').next()?;
+    let name = name.split('.').next().unwrap_or(name).to_lowercase();
+    let letters = name.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+    let digits = name[letters..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    if letters == 0 || digits == 0 {
+        return None;
+    }
+    let base = &name[..letters + digits];
+    let rest = &name[letters + digits..];
+    match rest.len() {
+        0 => Some((base.to_string(), None)),
+        1 | 2 if rest.chars().all(|c| c.is_ascii_alphabetic()) => {
+            Some((base.to_string(), Some(rest.to_uppercase())))
+        }
+        _ => None,
+    }
+}
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+// the first date in a text as YYYY-MM-DD, or YYYY-MM without a day: "2024-01-08",
+// "08 Jan 2024", "08-Jan-2024", "Jan 8, 2024" and "January 2024"
+pub fn parse_date(s: &str) -> Option<String> {
+    for word in s.split_whitespace() {
+        let parts: Vec<&str> = word
+            .trim_matches(|c: char| !c.is_ascii_digit())
+            .split('-')
+            .collect();
+        if parts.len() == 3
+            && parts[0].len() == 4
+            && parts
+                .iter()
+                .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Some(format!("{}-{:0>2}-{:0>2}", parts[0], parts[1], parts[2]));
+        }
+    }
+
+    let tokens: Vec<String> = s
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect();
+    let number = |i: usize, len: std::ops::RangeInclusive<usize>| {
+        tokens
+            .get(i)
+            .filter(|t| len.contains(&t.len()) && t.chars().all(|c| c.is_ascii_digit()))
+            .and_then(|t| t.parse::<u32>().ok())
+    };
+    for (i, t) in tokens.iter().enumerate() {
+        let month = match MONTHS.iter().position(|m| {
+            t.len() >= 3 && t.chars().all(|c| c.is_ascii_alphabetic()) && m.starts_with(t.as_str())
+        }) {
+            Some(m) => m + 1,
+            None => continue,
+        };
+        // "Rev 9 - April 2019" has no day
+        let before = i.checked_sub(1).and_then(|j| number(j, 1..=2)).filter(|_| {
+            i < 2 || !["rev", "revision", "ver", "version"].contains(&tokens[i - 2].as_str())
+        });
+        let (day, year) = match (before, number(i + 1, 1..=2), number(i + 1, 4..=4)) {
+            (Some(d), _, Some(y)) => (Some(d), y),
+            (_, Some(d), _) => match number(i + 2, 4..=4) {
+                Some(y) => (Some(d), y),
+                None => continue,
+            },
+            (None, None, Some(y)) => (None, y),
+            _ => continue,
+        };
+        return Some(match day.filter(|d| (1..=31).contains(d)) {
+            Some(d) => format!("{}-{:02}-{:02}", year, month, d),
+            None => format!("{}-{:02}", year, month),
+        });
+    }
+    None
+}
+
+// "Rev 7", "Revision C", "Ver 7.0" or a plain "7.0" in a version column
+pub fn parse_revision(s: &str) -> Option<String> {
+    let lower = s.trim().to_lowercase();
+    let mut rest = lower.as_str();
+    let mut prefixed = false;
+    for p in ["revision", "rev.", "rev", "version", "ver.", "ver", "v"].iter() {
+        if let Some(r) = rest.strip_prefix(p) {
+            rest = r.trim_start_matches([':', ' ']);
+            prefixed = true;
+            break;
+        }
+    }
+    let number = rest.chars().any(|c| c.is_ascii_digit())
+        && rest.chars().all(|c| c.is_ascii_digit() || c == '.');
+    let letter = prefixed && rest.len() == 1 && rest.chars().all(|c| c.is_ascii_alphabetic());
+    if (number && rest.len() <= 6) || letter {
+        Some(rest.to_uppercase())
+    } else {
+        None
+    }
+}
+
+// revision and date from the table row or list item a document link is in
+pub fn revision_and_date(a: Node) -> (Option<String>, Option<String>) {
+    let cells: Vec<String> = match crate::enclosing_row(a) {
+        Some(row) => row.find(Name("td")).map(|td| td.text()).collect(),
+        None => a
+            .parent()
+            .and_then(|p| p.parent())
+            .map(|p| vec![p.text()])
+            .unwrap_or_default(),
+    };
+    (
+        cells.iter().find_map(|c| parse_revision(c)),
+        cells.iter().find_map(|c| parse_date(c)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_wins() {
+        let k = DocumentKind::classify(Some("Errata"), "https://www.ti.com/lit/ds/x", "");
+        assert_eq!(k, DocumentKind::Errata);
+    }
+
+    #[test]
+    fn url_paths() {
+        let cases = [
+            (
+                "https://www.ti.com/lit/gpn/opa2197",
+                DocumentKind::Datasheet,
+            ),
+            ("https://www.ti.com/lit/zip/sbomb32", DocumentKind::Model),
+            (
+                "https://www.ti.com/tool/TIDA-01234",
+                DocumentKind::ReferenceDesign,
+            ),
+            ("/resource/en/errata_sheet/es0392.pdf", DocumentKind::Errata),
+        ];
+        for (url, kind) in cases.iter() {
+            assert_eq!(DocumentKind::classify(None, url, ""), *kind, "{}", url);
+        }
+    }
+
+    #[test]
+    fn extensions_of_the_last_segment() {
+        let model = |url| DocumentKind::classify(None, url, "") == DocumentKind::Model;
+        assert!(model("https://example.com/models/opa2197.lib"));
+        assert!(model("https://example.com/models/opa2197.IBS?download=1"));
+        assert!(!model("https://example.com/library/opa2197.pdf"));
+        assert!(!model("https://example.com/x.cirrus/notes.pdf"));
+        assert!(!model("https://example.com/get?file=opa2197.tsc"));
+    }
+
+    #[test]
+    fn titles_after_the_url() {
+        let k = DocumentKind::classify(
+            None,
+            "https://example.com/x.pdf",
+            "Errata for the Datasheet",
+        );
+        assert_eq!(k, DocumentKind::Errata);
+        let k = DocumentKind::classify(None, "https://example.com/x.pdf", "Something");
+        assert_eq!(k, DocumentKind::Other);
+    }
+
+    #[test]
+    fn ti_local_paths() {
+        let doc = |url: &str, kind| Document {
+            vendor: String::from("ti"),
+            url: url.to_string(),
+            title: String::new(),
+            kind,
+            parts: Vec::new(),
+            revision: None,
+            date: None,
+        };
+        let path = |url, kind| doc(url, kind).local_path();
+        assert_eq!(
+            path("/lit/pdf/sboa123", DocumentKind::ApplicationNote).as_deref(),
+            Some("pdf/ti/lit/sboa123.pdf")
+        );
+        assert_eq!(
+            path(
+                "https://www.ti.com/lit/ug/sbou123a/sbou123a.pdf?ts=1",
+                DocumentKind::UserGuide
+            )
+            .as_deref(),
+            Some("pdf/ti/lit/sbou123a.pdf")
+        );
+        assert_eq!(
+            path("/lit/gpn/opa2197", DocumentKind::Datasheet).as_deref(),
+            Some("pdf/ti/gpn/OPA2197.pdf")
+        );
+        assert_eq!(
+            path("/lit/zip/sbomb32", DocumentKind::Model).as_deref(),
+            Some("files/ti/models/sbomb32.zip")
+        );
+        assert_eq!(path("/tool/OPA2197EVM", DocumentKind::Tool), None);
+        assert_eq!(
+            path("https://e2e.ti.com/x.pdf", DocumentKind::TechnicalNote),
+            None
+        );
+    }
+
+    #[test]
+    fn external_hosts() {
+        let doc = |vendor: &str, url: &str| Document {
+            vendor: vendor.to_string(),
+            url: url.to_string(),
+            title: String::new(),
+            kind: DocumentKind::TechnicalNote,
+            parts: Vec::new(),
+            revision: None,
+            date: None,
+        };
+        assert!(!doc("ti", "/lit/pdf/sboa123").external());
+        assert!(!doc("ti", "https://www.ti.com/lit/pdf/sboa123").external());
+        assert!(!doc("st", "https://www.st.com/resource/en/an1234.pdf").external());
+        assert!(doc("ti", "https://e2e.ti.com/support/amplifiers/f/14/t/123").external());
+        assert!(doc("st", "https://community.st.com/s/question/1").external());
+        assert!(doc("ti", "https://www.analog.com/ti.com/x.pdf").external());
+        assert!(doc("ti", "https://github.com/TexasInstruments/x").external());
+    }
+
+    #[test]
+    fn revisions() {
+        assert_eq!(parse_revision("Rev. C").as_deref(), Some("C"));
+        assert_eq!(parse_revision("7.0").as_deref(), Some("7.0"));
+        assert_eq!(parse_revision("C"), None);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("2021-3-5").as_deref(), Some("2021-03-05"));
+        assert_eq!(parse_date("12 Mar 2021").as_deref(), Some("2021-03-12"));
+        assert_eq!(parse_date("Rev 9 - April 2019").as_deref(), Some("2019-04"));
+    }
+}
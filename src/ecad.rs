@@ -0,0 +1,371 @@
+// ecad collects the links to CAD symbols, footprints and 3D models found on the product pages,
+// most point to third-party portals that need a login, those are only recorded, files the
+// vendor hosts itself are archived under files/{vendor}/ecad/{part}/, all are in
+// json/{vendor}/ecad.json
+
+use crate::db::open_json;
+use crate::orderable::{self, Orderable};
+use futures::{stream, StreamExt};
+use indicatif::ProgressBar;
+use select::document::Document;
+use select::predicate::Name;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum CadKind {
+    Symbol,
+    Footprint,
+    #[serde(rename = "3d")]
+    Model3d,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CadLink {
+    pub vendor: String,
+    pub part: String,
+    // the package of the row the link was in, e.g. "SOIC (D)", none if it is for every package
+    pub package: Option<String>,
+    pub portal: String,
+    pub kinds: Vec<CadKind>,
+    pub url: String,
+    pub title: String,
+    // portals only hand out the files after a login
+    pub login: bool,
+    // the archived copy below the archive root
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+// host fragments of the CAD portals, all of them need an account
+const PORTALS: &[(&str, &str)] = &[
+    ("ultralibrarian.com", "Ultra Librarian"),
+    ("snapeda.com", "SnapEDA"),
+    ("componentsearchengine.com", "SamacSys"),
+    ("samacsys.com", "SamacSys"),
+    ("accelerated-designs.com", "Accelerated Designs"),
+    ("pcblibraries.com", "PCB Libraries"),
+];
+
+// URL fragments of the CAD files the vendors host themselves
+const HOSTED: &[&str] = &["/resource/en/cad_symbols/", "/lit/zip/cad", "/cad/"];
+
+// file types worth archiving, anything bigger than this is a whole library
+pub const EXTENSIONS: &[&str] = &["zip", "step", "stp", "bxl", "lbr", "kicad_mod"];
+pub const MAX_SIZE: u64 = 20 * 1024 * 1024;
+
+// words of the title and file name, a lone "step" or "pcb" says nothing, "step-down converter"
+const KINDS: &[(&str, CadKind)] = &[
+    ("symbol", CadKind::Symbol),
+    ("schematic", CadKind::Symbol),
+    ("footprint", CadKind::Footprint),
+    ("land pattern", CadKind::Footprint),
+    ("pcb library", CadKind::Footprint),
+    ("3d", CadKind::Model3d),
+    ("step model", CadKind::Model3d),
+    ("step file", CadKind::Model3d),
+];
+
+// file types that are 3D models whatever the title says
+const MODEL_EXTENSIONS: &[&str] = &["step", "stp"];
+
+impl CadKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CadKind::Symbol => "symbol",
+            CadKind::Footprint => "footprint",
+            CadKind::Model3d => "3d",
+        }
+    }
+}
+
+impl CadLink {
+    // where the crawler saves the file if it can be downloaded without a login, per part as the
+    // vendors name the files of different parts alike, a name without extension is only known
+    // to be a zip below TI's /lit/zip/
+    pub fn local_path(&self) -> Option<String> {
+        if self.login {
+            return None;
+        }
+        let path = self.url.split(['?', '#']).next().unwrap_or("");
+        let name = path.trim_end_matches('/').rsplit('/').next()?;
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((stem, ext)) => (stem, ext.to_lowercase()),
+            None if path.contains("/lit/zip/") => (name, String::from("zip")),
+            None => return None,
+        };
+        if !EXTENSIONS.contains(&ext.as_str()) {
+            return None;
+        }
+        Some(format!(
+            "files/{}/ecad/{}/{}.{}",
+            self.vendor,
+            self.part.replace('/', "_"),
+            stem,
+            ext
+        ))
+    }
+
+    pub fn absolute_url(&self) -> String {
+        match self.vendor.as_str() {
+            "ti" if self.url.starts_with('/') => format!("https://www.ti.com{}", self.url),
+            "st" if self.url.starts_with('/') => format!("https://www.st.com{}", self.url),
+            _ => self.url.clone(),
+        }
+    }
+
+    // whether the link covers the package of an orderable
+    pub fn fits(&self, o: &Orderable) -> bool {
+        match (&self.package, &o.package) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            _ => true,
+        }
+    }
+}
+
+// the portal, the kinds of files and whether a login is needed, none if it is no CAD link
+pub fn classify(vendor: &str, url: &str, title: &str) -> Option<(String, Vec<CadKind>, bool)> {
+    let lower = url.to_lowercase();
+    // the title and the file name, hosts and queries say nothing about the contents
+    let name = lower.split(['?', '#']).next().unwrap_or("");
+    let name = name.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    let text = format!("{} {}", title.to_lowercase(), name);
+
+    let (portal, login) = if let Some((_, name)) = PORTALS.iter().find(|(h, _)| lower.contains(h)) {
+        (name.to_string(), true)
+    } else if HOSTED.iter().any(|h| lower.contains(h))
+        && (lower.starts_with('/') || lower.contains(&format!(".{}.com/", vendor)))
+    {
+        (vendor.to_string(), false)
+    } else {
+        return None;
+    };
+
+    // whole words, plurals too
+    let padded = format!(
+        " {} ",
+        text.chars()
+            .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+            .collect::<String>()
+    );
+    let mut kinds: Vec<CadKind> = Vec::new();
+    for (word, k) in KINDS {
+        let found =
+            padded.contains(&format!(" {} ", word)) || padded.contains(&format!(" {}s ", word));
+        if found && !kinds.contains(k) {
+            kinds.push(*k);
+        }
+    }
+    let model = name
+        .rsplit_once('.')
+        .is_some_and(|(_, e)| MODEL_EXTENSIONS.contains(&e));
+    if model && !kinds.contains(&CadKind::Model3d) {
+        kinds.push(CadKind::Model3d);
+    }
+    // the portals hand out symbol, footprint and 3D model in one go
+    if kinds.is_empty() {
+        kinds = vec![CadKind::Symbol, CadKind::Footprint, CadKind::Model3d];
+    }
+    kinds.sort();
+
+    Some((portal, kinds, login))
+}
+
+// a table cell naming a package, "SOIC (D) | 8" or "LQFP 100 14x14x1.4 mm"
+fn package_cell(c: &str) -> Option<String> {
+    let first = c.split_whitespace().next()?;
+    let letters = first.chars().take_while(|x| x.is_ascii_uppercase()).count();
+    if c.len() > 40 || letters < 2 || first.chars().any(|x| x.is_lowercase()) {
+        return None;
+    }
+    orderable::pins_from_package(c)?;
+    // the orderables keep TI packages without the pin count
+    Some(c.split('|').next().unwrap().trim().to_string())
+}
+
+// every CAD link on a product page, with the package of the table row it is in
+pub fn from_page(doc: &Document, vendor: &str, part: &str) -> Vec<CadLink> {
+    let mut out: Vec<CadLink> = Vec::new();
+
+    for a in doc.find(Name("a")) {
+        let href = match a.attr("href") {
+            Some(href) => href.trim(),
+            None => continue,
+        };
+        let title = a.text().trim().to_string();
+        let (portal, kinds, login) = match classify(vendor, href, &title) {
+            Some(c) => c,
+            None => continue,
+        };
+
+        let package = crate::enclosing_row(a).and_then(|row| {
+            row.find(Name("td"))
+                .find_map(|td| package_cell(td.text().trim()))
+        });
+        if out.iter().any(|c| c.url == href && c.package == package) {
+            continue;
+        }
+
+        out.push(CadLink {
+            vendor: vendor.to_string(),
+            part: part.to_string(),
+            package,
+            portal,
+            kinds,
+            url: href.to_string(),
+            title,
+            login,
+            path: None,
+        });
+    }
+
+    out
+}
+
+pub fn load(root: &Path, vendor: &str) -> Vec<CadLink> {
+    open_json(&root.join(format!("json/{}/ecad.json", vendor))).unwrap_or_default()
+}
+
+// download the files that need no login, the links get the path of their copy
+pub async fn archive(root: &Path, links: Vec<CadLink>) -> Vec<CadLink> {
+    let pb = ProgressBar::new(links.len() as u64);
+    pb.set_style(crate::PB_STYLE.clone());
+    pb.set_message("Fetching CAD files...");
+
+    let out: Vec<CadLink> = stream::iter(links)
+        .map(|mut c| async move {
+            if let Some(path) = c.local_path() {
+                let file = root.join(&path).display().to_string();
+                match crate::save_file(c.absolute_url(), file, MAX_SIZE, EXTENSIONS).await {
+                    Ok(true) => c.path = Some(path),
+                    Ok(false) => {}
+                    Err(why) => eprintln!("couldn't fetch {}: {}", c.url, why),
+                }
+            }
+            c
+        })
+        .buffer_unordered(8)
+        .inspect(|_| pb.inc(1))
+        .collect()
+        .await;
+
+    pb.finish_and_clear();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portals() {
+        let (portal, kinds, login) = classify(
+            "ti",
+            "https://webench.ti.com/cad/redirect?u=https://www.ultralibrarian.com/x",
+            "Download CAD",
+        )
+        .unwrap();
+        assert_eq!(portal, "Ultra Librarian");
+        assert!(login);
+        // a portal hands out all of them
+        assert_eq!(
+            kinds,
+            vec![CadKind::Symbol, CadKind::Footprint, CadKind::Model3d]
+        );
+        let (portal, kinds, _) =
+            classify("st", "https://www.snapeda.com/parts/X", "Footprint").unwrap();
+        assert_eq!(
+            (portal.as_str(), kinds),
+            ("SnapEDA", vec![CadKind::Footprint])
+        );
+    }
+
+    #[test]
+    fn hosted() {
+        let (portal, kinds, login) = classify(
+            "st",
+            "/resource/en/cad_symbols/stm32f4_symbol.zip",
+            "ECAD models",
+        )
+        .unwrap();
+        assert_eq!((portal.as_str(), login), ("st", false));
+        assert_eq!(kinds, vec![CadKind::Symbol]);
+        let (_, kinds, _) = classify(
+            "ti",
+            "https://www.ti.com/lit/zip/cad123",
+            "Symbol and footprint",
+        )
+        .unwrap();
+        assert_eq!(kinds, vec![CadKind::Symbol, CadKind::Footprint]);
+        // the path of another vendor's site isn't ours to archive
+        assert!(classify("ti", "https://www.example.com/cad/x.zip", "CAD").is_none());
+        assert!(classify("ti", "/lit/ds/symlink/opa2197.pdf", "Datasheet").is_none());
+    }
+
+    #[test]
+    fn kinds_from_the_file_name_not_the_host() {
+        let (_, kinds, _) = classify("st", "/resource/en/cad_symbols/x.stp?step=1", "").unwrap();
+        assert_eq!(kinds, vec![CadKind::Model3d]);
+        let (_, kinds, _) =
+            classify("st", "https://www.st.com/cad/pcb-tools/x.zip?a=symbol", "").unwrap();
+        assert_eq!(
+            kinds,
+            vec![CadKind::Symbol, CadKind::Footprint, CadKind::Model3d]
+        );
+    }
+
+    #[test]
+    fn words_not_substrings() {
+        let kinds = |title: &str| classify("ti", "/lit/zip/cad123", title).unwrap().1;
+        // a portal-style link without a word for the contents has all three
+        let all = vec![CadKind::Symbol, CadKind::Footprint, CadKind::Model3d];
+        assert_eq!(kinds("Step-down converter CAD"), all);
+        assert_eq!(kinds("PCB design files"), all);
+        assert_eq!(kinds("STEP model"), vec![CadKind::Model3d]);
+        assert_eq!(
+            kinds("Symbols and footprints"),
+            vec![CadKind::Symbol, CadKind::Footprint]
+        );
+        assert_eq!(kinds("Land-pattern"), vec![CadKind::Footprint]);
+        let (_, kinds, _) = classify("st", "/cad/x.step", "Download").unwrap();
+        assert_eq!(kinds, vec![CadKind::Model3d]);
+    }
+
+    #[test]
+    fn local_paths() {
+        let link = |part: &str, url: &str, login| CadLink {
+            vendor: String::from("ti"),
+            part: part.to_string(),
+            package: None,
+            portal: String::from("ti"),
+            kinds: Vec::new(),
+            url: url.to_string(),
+            title: String::new(),
+            login,
+            path: None,
+        };
+        let path = |part, url| link(part, url, false).local_path();
+        // the same file name for two parts
+        assert_eq!(
+            path("OPA2197", "/cad/cad.zip?part=OPA2197").as_deref(),
+            Some("files/ti/ecad/OPA2197/cad.zip")
+        );
+        assert_eq!(
+            path("LM358", "/cad/cad.zip?part=LM358").as_deref(),
+            Some("files/ti/ecad/LM358/cad.zip")
+        );
+        assert_eq!(
+            path("OPA2197", "/lit/zip/sbom123").as_deref(),
+            Some("files/ti/ecad/OPA2197/sbom123.zip")
+        );
+        assert_eq!(
+            path("OPA2197", "https://www.ti.com/cad/OPA2197.STEP").as_deref(),
+            Some("files/ti/ecad/OPA2197/OPA2197.step")
+        );
+        // no extension to go by, or not a CAD file
+        assert_eq!(path("OPA2197", "/cad/download?id=1"), None);
+        assert_eq!(path("OPA2197", "/cad/readme.pdf"), None);
+        assert_eq!(link("OPA2197", "/cad/cad.zip", true).local_path(), None);
+    }
+}
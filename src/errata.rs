@@ -0,0 +1,208 @@
+// errata keeps the revision history of the errata sheets across crawls in
+// json/{vendor}/errata.json, so the parts that gained new errata since a crawl can be listed
+
+use crate::db::open_json;
+use crate::document::{Document, DocumentKind};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Revision {
+    pub revision: Option<String>,
+    pub date: Option<String>,
+    pub url: String,
+    // the crawl that first saw it
+    pub seen: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Erratum {
+    pub vendor: String,
+    // the literature number without revision for TI, the URL for ST
+    pub key: String,
+    pub title: String,
+    pub parts: Vec<String>,
+    // oldest first
+    pub revisions: Vec<Revision>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct History {
+    // the time of every crawl, RFC 3339
+    pub crawls: Vec<String>,
+    pub errata: Vec<Erratum>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    New,
+    Revised { from: String },
+}
+
+#[derive(Clone, Debug)]
+pub struct Change<'a> {
+    pub part: String,
+    pub erratum: &'a Erratum,
+    pub revision: &'a Revision,
+    pub change: ChangeKind,
+}
+
+impl Erratum {
+    pub fn latest(&self) -> Option<&Revision> {
+        self.revisions.last()
+    }
+}
+
+impl Revision {
+    // the revision, else the date, for the reports
+    pub fn label(&self) -> String {
+        match (&self.revision, &self.date) {
+            (Some(r), _) => r.clone(),
+            (None, Some(d)) => d.clone(),
+            (None, None) => String::from("-"),
+        }
+    }
+}
+
+impl History {
+    pub fn load(root: &Path, vendor: &str) -> History {
+        open_json(&root.join(format!("json/{}/errata.json", vendor))).unwrap_or_default()
+    }
+
+    // record a crawl, errata whose revision, date or URL changed get a new revision
+    pub fn update(&mut self, docs: &[Document], now: &str) {
+        self.crawls.push(now.to_string());
+
+        for d in docs.iter().filter(|d| d.kind == DocumentKind::Errata) {
+            let revision = Revision {
+                revision: d.revision.clone(),
+                date: d.date.clone(),
+                url: d.url.clone(),
+                seen: now.to_string(),
+            };
+            let key = d.key();
+            let e = match self
+                .errata
+                .iter_mut()
+                .find(|e| e.vendor == d.vendor && e.key == key)
+            {
+                Some(e) => e,
+                None => {
+                    self.errata.push(Erratum {
+                        vendor: d.vendor.clone(),
+                        key,
+                        title: d.title.clone(),
+                        parts: Vec::new(),
+                        revisions: Vec::new(),
+                    });
+                    self.errata.last_mut().unwrap()
+                }
+            };
+
+            e.title = d.title.clone();
+            for p in &d.parts {
+                if !e.parts.contains(p) {
+                    e.parts.push(p.clone());
+                }
+            }
+            let same = e.latest().is_some_and(|r| {
+                r.revision == revision.revision && r.date == revision.date && r.url == revision.url
+            });
+            if !same {
+                e.revisions.push(revision);
+            }
+        }
+
+        self.errata.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+
+    // the crawl before the last, what the last crawl found is new, with a single crawl there is
+    // nothing to compare to and nothing is
+    pub fn previous_crawl(&self) -> Option<&String> {
+        self.crawls
+            .iter()
+            .rev()
+            .nth(1)
+            .or_else(|| self.crawls.last())
+    }
+
+    // the errata of part that were published or revised after since, a date or RFC 3339 time
+    pub fn changes(&self, part: &str, since: &str) -> Vec<Change<'_>> {
+        let mut out = Vec::new();
+        for e in self
+            .errata
+            .iter()
+            .filter(|e| e.parts.iter().any(|p| p.eq_ignore_ascii_case(part)))
+        {
+            for (i, r) in e.revisions.iter().enumerate() {
+                if r.seen.as_str() <= since {
+                    continue;
+                }
+                out.push(Change {
+                    part: part.to_string(),
+                    erratum: e,
+                    revision: r,
+                    change: match i {
+                        0 => ChangeKind::New,
+                        _ => ChangeKind::Revised {
+                            from: e.revisions[i - 1].label(),
+                        },
+                    },
+                });
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errata(url: &str, revision: &str) -> Document {
+        Document {
+            vendor: String::from("st"),
+            url: url.to_string(),
+            title: String::from("STM32F407 errata"),
+            kind: DocumentKind::Errata,
+            parts: vec![String::from("STM32F407VG")],
+            revision: Some(revision.to_string()),
+            date: None,
+        }
+    }
+
+    #[test]
+    fn previous_crawl() {
+        let mut h = History::default();
+        assert_eq!(h.previous_crawl(), None);
+
+        let url = "https://www.st.com/resource/en/errata_sheet/es0182.pdf";
+        h.update(&[errata(url, "12")], "2026-01-01T00:00:00Z");
+        // a single crawl has nothing new
+        assert_eq!(h.previous_crawl().unwrap(), "2026-01-01T00:00:00Z");
+        assert!(h
+            .changes("stm32f407vg", h.previous_crawl().unwrap())
+            .is_empty());
+
+        h.update(&[errata(url, "12")], "2026-02-01T00:00:00Z");
+        h.update(
+            &[errata(&format!("{}?v=13", url), "13")],
+            "2026-03-01T00:00:00Z",
+        );
+        assert_eq!(h.previous_crawl().unwrap(), "2026-02-01T00:00:00Z");
+        assert_eq!(h.errata.len(), 1);
+        assert_eq!(h.errata[0].revisions.len(), 2);
+
+        let changes = h.changes("STM32F407VG", h.previous_crawl().unwrap());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].change,
+            ChangeKind::Revised {
+                from: String::from("12")
+            }
+        );
+        assert_eq!(h.changes("STM32F407VG", "").len(), 2);
+        assert!(h.changes("STM32F429ZI", "").is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+// family is a TI parametric selection table, the crawler finds them on the products page of
+// each category and keeps them in json/ti/families.json
+
+use crate::db::open_json;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Family {
+    pub id: String,
+    pub title: String,
+    // category names from the top of the menu down, e.g. ["Amplifiers", "Op amps"]
+    pub path: Vec<String>,
+    // the other categories that list the family, the parts take their category from path
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also: Vec<Vec<String>>,
+    // the products page the family was found on
+    pub source: String,
+    // generic part numbers in the parametric results
+    #[serde(default)]
+    pub parts: Vec<String>,
+}
+
+// a category page without any parametric family, its parts never make it into data.json
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Orphan {
+    pub path: Vec<String>,
+    pub source: String,
+}
+
+pub fn load(root: &Path) -> Vec<Family> {
+    open_json(&root.join("json/ti/families.json")).unwrap_or_default()
+}
@@ -0,0 +1,617 @@
+// fulltext keeps a page-level inverted index over the downloaded PDFs in json/index/, the
+// postings are appended in segments as documents are added and merged into postings.json when
+// documents were removed or the segments pile up
+
+use crate::db::open_json;
+use crate::pdfmeta::PdfMeta;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DocEntry {
+    pub id: u32,
+    pub size: u64,
+    pub mtime: u64,
+    pub pages: u32,
+    // number, revision and date the PDF gives itself
+    #[serde(default)]
+    pub meta: Option<PdfMeta>,
+}
+
+type Postings = BTreeMap<String, Vec<(u32, u32)>>;
+
+#[derive(Serialize, Deserialize, Default)]
+struct Manifest {
+    next_id: u32,
+    docs: BTreeMap<String, DocEntry>,
+    // size and mtime of the files no text could be extracted from, tried again when they change
+    #[serde(default)]
+    failed: BTreeMap<String, (u64, u64)>,
+}
+
+pub struct Index {
+    root: PathBuf,
+    manifest: Manifest,
+    postings: Postings,
+    // added since the last save, the next segment
+    pending: Postings,
+    segments: u32,
+    // documents were removed, their postings are left until the next merge
+    removed: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Hit {
+    pub path: String,
+    pub page: u32,
+    pub snippet: String,
+}
+
+const SNIPPET_CONTEXT: usize = 80;
+
+// segments on disk before they are merged into postings.json
+const MAX_SEGMENTS: u32 = 16;
+
+// only the manifest, for what is known about the documents without the postings
+pub fn manifest(root: &Path) -> BTreeMap<String, DocEntry> {
+    open_json::<Manifest>(&root.join("json/index/manifest.json"))
+        .map(|m| m.docs)
+        .unwrap_or_default()
+}
+
+impl Index {
+    pub fn open(root: &Path) -> Index {
+        let dir = root.join("json/index");
+        let mut postings: Postings = open_json(&dir.join("postings.json")).unwrap_or_default();
+        let segments = segment_numbers(&dir);
+        for n in &segments {
+            let segment: Postings = open_json(&segment_path(&dir, *n)).unwrap_or_default();
+            for (term, v) in segment {
+                postings.entry(term).or_default().extend(v);
+            }
+        }
+        Index {
+            root: root.to_path_buf(),
+            manifest: open_json(&dir.join("manifest.json")).unwrap_or_default(),
+            postings,
+            pending: BTreeMap::new(),
+            segments: segments.last().copied().unwrap_or(0),
+            removed: false,
+        }
+    }
+
+    // the postings added since the last save go to a new segment, the segment before the
+    // manifest so the manifest never names documents without postings
+    pub fn save(&mut self) {
+        let dir = self.root.join("json/index");
+        fs::create_dir_all(dir.join("postings")).expect("couldn't create the index directory");
+        if !self.pending.is_empty() {
+            self.segments += 1;
+            let path = segment_path(&dir, self.segments);
+            crate::write_json(path.to_str().unwrap(), &self.pending);
+            self.pending.clear();
+        }
+        crate::write_json(dir.join("manifest.json").to_str().unwrap(), &self.manifest);
+    }
+
+    // save, and merge the segments into postings.json without the postings of removed
+    // documents when there are any or there are too many segments
+    pub fn compact(&mut self) {
+        if !self.removed && self.segments < MAX_SEGMENTS {
+            return self.save();
+        }
+
+        let ids: HashSet<u32> = self.manifest.docs.values().map(|d| d.id).collect();
+        self.postings.retain(|_, v| {
+            v.retain(|(doc, _)| ids.contains(doc));
+            !v.is_empty()
+        });
+        self.pending.clear();
+
+        let dir = self.root.join("json/index");
+        fs::create_dir_all(&dir).expect("couldn't create the index directory");
+        crate::write_json(dir.join("postings.json").to_str().unwrap(), &self.postings);
+        crate::write_json(dir.join("manifest.json").to_str().unwrap(), &self.manifest);
+        for n in segment_numbers(&dir) {
+            let _ = fs::remove_file(segment_path(&dir, n));
+        }
+        self.segments = 0;
+        self.removed = false;
+    }
+
+    pub fn docs(&self) -> &BTreeMap<String, DocEntry> {
+        &self.manifest.docs
+    }
+
+    // files that are new or changed since they were indexed or failed, or indexed without
+    // metadata
+    pub fn stale(&self, files: &[PathBuf]) -> Vec<PathBuf> {
+        files
+            .iter()
+            .filter(|f| {
+                let key = self.key(f);
+                let stamp = file_stamp(f);
+                if stamp.is_some() && self.manifest.failed.get(&key) == stamp.as_ref() {
+                    return false;
+                }
+                match (self.manifest.docs.get(&key), stamp) {
+                    // indexed before the metadata was read
+                    (Some(d), Some((size, mtime))) => {
+                        d.size != size || d.mtime != mtime || d.meta.is_none()
+                    }
+                    _ => true,
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    // indexed or failed documents whose file is gone
+    pub fn missing(&self, files: &[PathBuf]) -> Vec<String> {
+        let present: HashSet<String> = files.iter().map(|f| self.key(f)).collect();
+        self.manifest
+            .docs
+            .keys()
+            .chain(self.manifest.failed.keys())
+            .filter(|k| !present.contains(*k))
+            .cloned()
+            .collect()
+    }
+
+    // the postings stay until the next merge, search only looks at documents in the manifest
+    pub fn remove(&mut self, paths: &[String]) {
+        for p in paths {
+            self.manifest.failed.remove(p);
+            if let Some(d) = self.manifest.docs.remove(p) {
+                let _ = fs::remove_file(self.text_path(d.id));
+                self.removed = true;
+            }
+        }
+    }
+
+    // a file no text could be extracted from, skipped until it changes
+    pub fn fail(&mut self, file: &Path) {
+        let key = self.key(file);
+        self.remove(std::slice::from_ref(&key));
+        if let Some(stamp) = file_stamp(file) {
+            self.manifest.failed.insert(key, stamp);
+        }
+    }
+
+    // (re)index a document from its extracted page texts
+    pub fn add(&mut self, file: &Path, pages: Vec<String>, meta: Option<PdfMeta>) {
+        let key = self.key(file);
+        self.remove(std::slice::from_ref(&key));
+
+        let (size, mtime) = file_stamp(file).unwrap_or((0, 0));
+        let id = self.manifest.next_id;
+        self.manifest.next_id += 1;
+
+        for (n, text) in pages.iter().enumerate() {
+            let terms: BTreeSet<String> = tokenize(text).into_iter().collect();
+            for t in terms {
+                self.postings
+                    .entry(t.clone())
+                    .or_default()
+                    .push((id, n as u32 + 1));
+                self.pending.entry(t).or_default().push((id, n as u32 + 1));
+            }
+        }
+
+        let text = self.text_path(id);
+        fs::create_dir_all(text.parent().unwrap()).expect("couldn't create the index directory");
+        if let Err(why) = fs::write(&text, serde_json::to_string(&pages).unwrap()) {
+            panic!("couldn't write to {}: {}", text.display(), why);
+        }
+
+        self.manifest.docs.insert(
+            key,
+            DocEntry {
+                id,
+                size,
+                mtime,
+                pages: pages.len() as u32,
+                meta,
+            },
+        );
+    }
+
+    // pages that contain all of the terms, ordered by document and page
+    pub fn search(&self, query: &str, prefix: Option<&str>, limit: usize) -> Vec<Hit> {
+        self.search_where(
+            query,
+            |path| prefix.is_none_or(|p| path.starts_with(p)),
+            limit,
+        )
+    }
+
+    // like search, keep decides which documents to look at by their path
+    pub fn search_where<F: Fn(&str) -> bool>(
+        &self,
+        query: &str,
+        keep: F,
+        limit: usize,
+    ) -> Vec<Hit> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut pages: Option<BTreeSet<(u32, u32)>> = None;
+        for t in &terms {
+            let found: BTreeSet<(u32, u32)> = self
+                .postings
+                .get(t)
+                .map(|v| v.iter().cloned().collect())
+                .unwrap_or_default();
+            pages = Some(match pages {
+                None => found,
+                Some(p) => p.intersection(&found).cloned().collect(),
+            });
+        }
+
+        let paths: BTreeMap<u32, &String> = self
+            .manifest
+            .docs
+            .iter()
+            .filter(|(k, _)| keep(k))
+            .map(|(k, d)| (d.id, k))
+            .collect();
+
+        let mut hits = Vec::new();
+        let mut texts: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+
+        for (doc, page) in pages.unwrap_or_default() {
+            let path = match paths.get(&doc) {
+                Some(p) => p,
+                None => continue,
+            };
+            if hits.len() >= limit {
+                break;
+            }
+
+            let text = texts
+                .entry(doc)
+                .or_insert_with(|| open_json(&self.text_path(doc)).unwrap_or_default());
+
+            hits.push(Hit {
+                path: path.to_string(),
+                page,
+                snippet: text
+                    .get(page as usize - 1)
+                    .map(|t| snippet(t, &terms))
+                    .unwrap_or_default(),
+            });
+        }
+
+        hits
+    }
+
+    // the extracted text of an indexed document by page
+    pub fn pages(&self, key: &str) -> Vec<String> {
+        match self.manifest.docs.get(key) {
+            Some(d) => open_json(&self.text_path(d.id)).unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    // the path below the root the documents are known by
+    pub fn key(&self, file: &Path) -> String {
+        file.strip_prefix(&self.root)
+            .unwrap_or(file)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn text_path(&self, id: u32) -> PathBuf {
+        self.root.join(format!("json/index/text/{}.json", id))
+    }
+}
+
+// the vendor and part of a datasheet by where the crawlers put it
+pub fn datasheet_part(path: &str) -> Option<(&'static str, String)> {
+    let (vendor, rest) = if let Some(rest) = path.strip_prefix("pdf/ti/gpn/") {
+        ("ti", rest)
+    } else {
+        ("st", path.strip_prefix("pdf/st/datasheets/")?)
+    };
+    Some((vendor, rest.strip_suffix(".pdf")?.to_string()))
+}
+
+// the indexed datasheets of a vendor to extract tables from again, those indexed in this run or
+// all of them, none if nothing changed for the vendor
+pub fn datasheets<'a>(
+    index: &'a Index,
+    vendor: &str,
+    all: bool,
+    changed: &[String],
+    removed: &[String],
+) -> Option<Vec<&'a String>> {
+    let redo: Vec<&String> = index
+        .docs()
+        .keys()
+        .filter(|k| datasheet_part(k).is_some_and(|(v, _)| v == vendor))
+        .filter(|k| all || changed.contains(k))
+        .collect();
+    let dropped = removed
+        .iter()
+        .any(|r| datasheet_part(r).is_some_and(|(v, _)| v == vendor));
+    if redo.is_empty() && !dropped {
+        None
+    } else {
+        Some(redo)
+    }
+}
+
+// extract the text of every page, pdf-extract panics on some broken files so those are skipped
+pub fn extract_pages(file: &Path) -> Option<Vec<String>> {
+    let file = file.to_path_buf();
+    match std::panic::catch_unwind(move || pdf_extract::extract_text_by_pages(&file)) {
+        Ok(Ok(pages)) => Some(pages),
+        _ => None,
+    }
+}
+
+// all PDFs below dir, sorted so runs are reproducible
+pub fn pdf_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(d) = dirs.pop() {
+        let entries = match fs::read_dir(&d) {
+            Err(_) => continue,
+            Ok(e) => e,
+        };
+        for e in entries.filter_map(|e| e.ok()) {
+            let path = e.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.len() >= 2 && t.len() <= 40)
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+fn segment_path(dir: &Path, n: u32) -> PathBuf {
+    dir.join(format!("postings/{}.json", n))
+}
+
+// the numbers of the segments in json/index/postings, in the order they were written
+fn segment_numbers(dir: &Path) -> Vec<u32> {
+    let mut numbers: Vec<u32> = fs::read_dir(dir.join("postings"))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    e.file_name()
+                        .to_str()?
+                        .strip_suffix(".json")?
+                        .parse()
+                        .ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    numbers.sort_unstable();
+    numbers
+}
+
+fn file_stamp(file: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(file).ok()?;
+    let mtime = meta
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((meta.len(), mtime))
+}
+
+// a short piece of the page around the first matching term, whitespace collapsed
+fn snippet(text: &str, terms: &[String]) -> String {
+    let lower = text.to_lowercase();
+    let pos = terms
+        .iter()
+        .filter_map(|t| lower.find(t.as_str()))
+        .min()
+        .unwrap_or(0);
+
+    // lowercasing can change byte offsets, so only cut on char boundaries of the original
+    let mut start = pos.saturating_sub(SNIPPET_CONTEXT).min(text.len());
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (pos + SNIPPET_CONTEXT).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+
+    text[start..end]
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("llh-index-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("pdf/ti/gpn")).unwrap();
+        root
+    }
+
+    // a file to index, its contents don't matter, only its size and time
+    fn pdf(root: &Path, name: &str) -> PathBuf {
+        let file = root.join("pdf/ti/gpn").join(name);
+        fs::write(&file, name).unwrap();
+        file
+    }
+
+    fn pages(hits: &[Hit]) -> Vec<(&str, u32)> {
+        hits.iter().map(|h| (h.path.as_str(), h.page)).collect()
+    }
+
+    fn meta() -> Option<PdfMeta> {
+        Some(PdfMeta::default())
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize("Input offset-voltage: ±10 µV, VOS"),
+            vec!["input", "offset", "voltage", "10", "µv", "vos"]
+        );
+        // single characters and runs longer than 40 are no words
+        assert!(tokenize(&format!("a {}", "x".repeat(41))).is_empty());
+    }
+
+    #[test]
+    fn snippets() {
+        let text = format!(
+            "{} the   offset\nvoltage {}",
+            "a ".repeat(100),
+            "b ".repeat(100)
+        );
+        let s = snippet(&text, &[String::from("offset")]);
+        assert!(s.contains("the offset voltage"));
+        assert!(s.len() < text.len());
+        // cut on char boundaries
+        let text = format!("{}offset", "µ".repeat(100));
+        assert!(snippet(&text, &[String::from("offset")]).ends_with("offset"));
+    }
+
+    #[test]
+    fn add_search_and_reopen() {
+        let root = root("add");
+        let (a, b) = (pdf(&root, "OPA2197.pdf"), pdf(&root, "LM358.pdf"));
+        let mut index = Index::open(&root);
+        index.add(
+            &a,
+            vec![
+                String::from("Features"),
+                String::from("Input offset voltage 10 µV"),
+            ],
+            meta(),
+        );
+        index.add(&b, vec![String::from("input offset voltage 2 mV")], meta());
+
+        let hits = index.search("offset voltage", None, 10);
+        assert_eq!(
+            pages(&hits),
+            vec![("pdf/ti/gpn/OPA2197.pdf", 2), ("pdf/ti/gpn/LM358.pdf", 1)]
+        );
+        assert_eq!(hits[0].snippet, "Input offset voltage 10 µV");
+        let hits = index.search("offset", Some("pdf/ti/gpn/LM"), 10);
+        assert_eq!(pages(&hits), vec![("pdf/ti/gpn/LM358.pdf", 1)]);
+        assert!(index.search("offset features", None, 10).is_empty());
+
+        // the postings go to a segment, the next run reads them back
+        index.save();
+        assert!(root.join("json/index/postings/1.json").exists());
+        let index = Index::open(&root);
+        assert_eq!(index.search("voltage", None, 1).len(), 1);
+        assert_eq!(index.pages("pdf/ti/gpn/LM358.pdf").len(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn removed_documents_are_compacted_away() {
+        let root = root("remove");
+        let (a, b) = (pdf(&root, "OPA2197.pdf"), pdf(&root, "LM358.pdf"));
+        let mut index = Index::open(&root);
+        index.add(&a, vec![String::from("offset")], meta());
+        index.save();
+        index.add(&b, vec![String::from("offset")], meta());
+        index.save();
+        assert!(root.join("json/index/postings/2.json").exists());
+
+        fs::remove_file(&a).unwrap();
+        let files = vec![b];
+        let missing = index.missing(&files);
+        assert_eq!(missing, vec!["pdf/ti/gpn/OPA2197.pdf"]);
+        index.remove(&missing);
+        assert_eq!(index.search("offset", None, 10).len(), 1);
+
+        // the segments are merged into postings.json without the removed document
+        index.compact();
+        assert!(segment_numbers(&root.join("json/index")).is_empty());
+        let postings: Postings = open_json(&root.join("json/index/postings.json")).unwrap();
+        assert_eq!(postings["offset"].len(), 1);
+        let index = Index::open(&root);
+        assert_eq!(
+            pages(&index.search("offset", None, 10)),
+            vec![("pdf/ti/gpn/LM358.pdf", 1)]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn segments_pile_up_until_compacted() {
+        let root = root("segments");
+        let mut index = Index::open(&root);
+        for n in 0..=MAX_SEGMENTS {
+            let file = pdf(&root, &format!("P{}.pdf", n));
+            index.add(&file, vec![String::from("offset")], meta());
+            if n < MAX_SEGMENTS {
+                index.compact();
+            }
+        }
+        let dir = root.join("json/index");
+        assert_eq!(segment_numbers(&dir).len(), MAX_SEGMENTS as usize);
+        // one too many, all of them are merged
+        index.compact();
+        assert!(segment_numbers(&dir).is_empty());
+        let index = Index::open(&root);
+        assert_eq!(
+            index.search("offset", None, 100).len(),
+            MAX_SEGMENTS as usize + 1
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn stale_and_failed_files() {
+        let root = root("stale");
+        let (a, b) = (pdf(&root, "OPA2197.pdf"), pdf(&root, "BROKEN.pdf"));
+        let c = pdf(&root, "NOMETA.pdf");
+        let files = vec![a.clone(), b.clone(), c.clone()];
+        let mut index = Index::open(&root);
+        assert_eq!(index.stale(&files), files);
+
+        index.add(&a, vec![String::from("offset")], meta());
+        index.add(&c, vec![String::from("offset")], None);
+        index.fail(&b);
+        // indexed before the metadata was read
+        assert_eq!(index.stale(&files), vec![c.clone()]);
+
+        // a changed file is tried again, failed or not
+        fs::write(&a, "a longer OPA2197").unwrap();
+        fs::write(&b, "a longer BROKEN").unwrap();
+        assert_eq!(index.stale(&files), files);
+
+        // a failed file that is gone is missing like an indexed one
+        fs::remove_file(&b).unwrap();
+        assert_eq!(index.missing(&[a, c]), vec!["pdf/ti/gpn/BROKEN.pdf"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
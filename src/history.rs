@@ -0,0 +1,285 @@
+// history finds the revision history at the end of a datasheet in its extracted text, TI writes
+// "Changes from Revision B (March 2019) to Revision C (March 2021)" followed by bullets, ST has
+// a table with date, revision and changes per row
+
+use crate::document;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub revision: Option<String>,
+    pub date: Option<String>,
+    pub changes: Vec<String>,
+}
+
+// the history ends where the next section or the legal notice starts
+const STOPS: &[&str] = &[
+    "important notice",
+    "mechanical, packaging",
+    "package option addendum",
+    "device and documentation support",
+];
+
+// page headers and footers that end up in the middle of the table
+const NOISE: &[&str] = &[
+    "copyright",
+    "submit document feedback",
+    "product folder links",
+    "www.ti.com",
+    "www.st.com",
+    "document revision history",
+];
+
+// at most this many pages after the heading
+const PAGES: usize = 3;
+
+// newest first, empty if there is no history or it can't be made sense of
+pub fn parse(pages: &[String]) -> Vec<Entry> {
+    for (n, page) in pages.iter().enumerate() {
+        // ASCII only so the offsets stay the same
+        let lower = page.to_ascii_lowercase();
+        let mut from = 0;
+        while let Some(i) = lower[from..].find("revision history") {
+            let start = from + i;
+            from = start + "revision history".len();
+            // the heading itself, not the line of the table of contents
+            let text: String = std::iter::once(&page[from..])
+                .chain(pages[n + 1..].iter().take(PAGES).map(|p| p.as_str()))
+                .collect::<Vec<&str>>()
+                .join("\n");
+            let entries = entries(&text);
+            if !entries.is_empty() {
+                return entries;
+            }
+        }
+    }
+    Vec::new()
+}
+
+fn entries(text: &str) -> Vec<Entry> {
+    let mut out: Vec<Entry> = Vec::new();
+    // whether the last change can go on on the next line
+    let mut open = false;
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let lower = line.to_lowercase();
+        if !out.is_empty() && (STOPS.iter().any(|s| lower.starts_with(s)) || heading(line)) {
+            break;
+        }
+        if NOISE.iter().any(|s| lower.contains(s)) || lower.starts_with("date ") {
+            continue;
+        }
+
+        if let Some(e) = ti_heading(line).or_else(|| st_row(line)) {
+            open = !e.changes.is_empty();
+            out.push(e);
+            continue;
+        }
+        let e = match out.last_mut() {
+            Some(e) => e,
+            // no entry yet, this is text before the history
+            None => continue,
+        };
+
+        let bullet = line.starts_with(['•', '-', '–', '*']);
+        let change = leaders(line.trim_start_matches(['•', '-', '–', '*', ' ']));
+        if change.is_empty() || change.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        match e.changes.last_mut() {
+            // a wrapped line, unless the last one was complete and this starts a new sentence
+            Some(last) if open && !bullet && !(last.ends_with('.') && starts_upper(&change)) => {
+                last.push(' ');
+                last.push_str(&change);
+            }
+            _ => e.changes.push(change),
+        }
+        open = true;
+    }
+
+    // ST lists the oldest first
+    if let (Some(first), Some(last)) = (
+        out.first().and_then(|e| e.date.clone()),
+        out.last().and_then(|e| e.date.clone()),
+    ) {
+        if first < last {
+            out.reverse();
+        }
+    }
+    out
+}
+
+// "Changes from Revision B (March 2019) to Revision C (March 2021) Page"
+fn ti_heading(line: &str) -> Option<Entry> {
+    // ASCII only so the offsets stay the same
+    let lower = line.to_ascii_lowercase();
+    if !lower.starts_with("changes from") {
+        return None;
+    }
+    let to = &line[lower.rfind(" to ")? + 4..];
+    let words: Vec<&str> = to.split_whitespace().collect();
+    let revision = words
+        .iter()
+        .position(|w| w.eq_ignore_ascii_case("revision"))
+        .and_then(|i| words.get(i + 1))
+        .map(|r| r.trim_matches(['(', ')', ',']).to_uppercase());
+
+    Some(Entry {
+        revision,
+        date: document::parse_date(to),
+        changes: Vec::new(),
+    })
+}
+
+// "03-Apr-2019 9 Updated Figure 3 and Table 5."
+fn st_row(line: &str) -> Option<Entry> {
+    let mut words = line.split_whitespace();
+    let date = words.next()?;
+    if !date.contains('-') || !date.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let date = document::parse_date(date)?;
+    let revision = document::parse_revision(words.next()?)?;
+    let change = leaders(&words.collect::<Vec<&str>>().join(" "));
+
+    Some(Entry {
+        revision: Some(revision),
+        date: Some(date),
+        changes: if change.is_empty() {
+            Vec::new()
+        } else {
+            vec![change]
+        },
+    })
+}
+
+// a numbered section heading like "9 Device and Documentation Support" or "9.1 Trademarks"
+pub fn heading(line: &str) -> bool {
+    let (number, title) = match line.split_once(' ') {
+        Some(x) => x,
+        None => return false,
+    };
+    let numbered = number
+        .split('.')
+        .all(|n| (1..=2).contains(&n.len()) && n.chars().all(|c| c.is_ascii_digit()));
+    numbered && line.len() < 60 && starts_upper(title) && !title.ends_with('.')
+}
+
+// "Changed the pinout ........ 12" without the dot leaders and the page
+fn leaders(s: &str) -> String {
+    let s = s.trim();
+    let cut = s.trim_end_matches(|c: char| c.is_ascii_digit() || c == ' ');
+    let dotted = cut.trim_end_matches(['.', ' ']);
+    if cut.len() - dotted.len() >= 3 {
+        dotted.trim().to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+fn starts_upper(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_uppercase())
+}
+
+// the entries newer than a revision, by revision if both have one and by date otherwise, only
+// the newest if the revision isn't in the history
+pub fn since<'a>(entries: &'a [Entry], revision: Option<&str>, date: Option<&str>) -> &'a [Entry] {
+    let end = entries
+        .iter()
+        .position(
+            |e| match (revision, e.revision.as_deref(), date, e.date.as_deref()) {
+                (Some(a), Some(b), _, _) => a.eq_ignore_ascii_case(b),
+                (_, _, Some(a), Some(b)) => b <= a,
+                _ => false,
+            },
+        )
+        .unwrap_or(entries.len().min(1));
+    &entries[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ti_heading_with_characters_that_change_length_when_lowercased() {
+        let e =
+            ti_heading("Changes from Revision B (İstanbul) to Revision C (March 2021)").unwrap();
+        assert_eq!(e.revision.as_deref(), Some("C"));
+        assert_eq!(e.date.as_deref(), Some("2021-03"));
+    }
+
+    #[test]
+    fn ti_history() {
+        let pages = vec![
+            String::from("Table of Contents\n9 Revision History .......... 5"),
+            String::from(
+                "9 Revision History\n\
+                 Changes from Revision C (March 2021) to Revision D (May 2024) Page\n\
+                 • Changed the input offset voltage maximum from 250 uV to 100 uV in the\n\
+                 Electrical Characteristics table ........................ 4\n\
+                 • Added the DGK package ........ 1\n\
+                 Changes from Revision B (March 2019) to Revision C (March 2021) Page\n\
+                 • Updated the pinout figure ....... 3\n\
+                 10 Mechanical, Packaging, and Orderable Information",
+            ),
+        ];
+        let h = parse(&pages);
+        assert_eq!(h.len(), 2);
+        assert_eq!(h[0].revision.as_deref(), Some("D"));
+        assert_eq!(h[0].date.as_deref(), Some("2024-05"));
+        assert_eq!(
+            h[0].changes,
+            vec![
+                "Changed the input offset voltage maximum from 250 uV to 100 uV in the \
+                 Electrical Characteristics table",
+                "Added the DGK package",
+            ]
+        );
+        assert_eq!(h[1].revision.as_deref(), Some("C"));
+        assert_eq!(h[1].changes, vec!["Updated the pinout figure"]);
+    }
+
+    #[test]
+    fn st_history_newest_first() {
+        let pages = vec![String::from(
+            "Revision history\n\
+             Date Revision Changes\n\
+             12-Mar-2015 1 Initial release.\n\
+             03-Apr-2019 2 Updated Figure 3 and\n\
+             Table 5.",
+        )];
+        let h = parse(&pages);
+        assert_eq!(h.len(), 2);
+        assert_eq!(h[0].revision.as_deref(), Some("2"));
+        assert_eq!(h[0].date.as_deref(), Some("2019-04-03"));
+        assert_eq!(h[0].changes, vec!["Updated Figure 3 and Table 5."]);
+        assert_eq!(h[1].changes, vec!["Initial release."]);
+    }
+
+    #[test]
+    fn entries_since_a_revision() {
+        let entry = |r: &str, d: &str| Entry {
+            revision: Some(r.to_string()),
+            date: Some(d.to_string()),
+            changes: Vec::new(),
+        };
+        let h = vec![
+            entry("D", "2024-05"),
+            entry("C", "2021-03"),
+            entry("B", "2019-03"),
+        ];
+        assert_eq!(since(&h, Some("B"), None).len(), 2);
+        assert_eq!(since(&h, None, Some("2021-03")).len(), 1);
+        // a revision the history doesn't have, only the newest
+        assert_eq!(since(&h, Some("A"), None).len(), 1);
+    }
+
+    #[test]
+    fn section_headings() {
+        assert!(heading("9 Device and Documentation Support"));
+        assert!(heading("9.1 Trademarks"));
+        assert!(!heading("1 OUT1 O Output of channel 1."));
+        assert!(!heading("2024 was a good year"));
+    }
+}
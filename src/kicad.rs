@@ -0,0 +1,349 @@
+// kicad writes a KiCad 6 symbol library from the pin tables, a box per package table with the
+// inputs on the left, outputs on the right, supplies on top and ground at the bottom, the
+// datasheet URL and the archived PDF are properties of every symbol
+
+use crate::db::Part;
+use crate::pinout::{Pin, PinType, Pinout};
+use std::collections::HashSet;
+use std::path::Path;
+
+// everything is on the 100 mil grid, positions are in grid units until they are written
+const GRID: f64 = 2.54;
+const PIN_LENGTH: i32 = 1;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+fn side(kind: Option<PinType>) -> Side {
+    match kind {
+        Some(PinType::Output) | Some(PinType::Bidirectional) => Side::Right,
+        Some(PinType::Power) => Side::Top,
+        Some(PinType::Ground) | Some(PinType::NoConnect) => Side::Bottom,
+        _ => Side::Left,
+    }
+}
+
+// the electrical type KiCad checks connections by
+fn electrical(kind: Option<PinType>) -> &'static str {
+    match kind {
+        Some(PinType::Input) => "input",
+        Some(PinType::Output) => "output",
+        Some(PinType::Bidirectional) => "bidirectional",
+        Some(PinType::Power) | Some(PinType::Ground) => "power_in",
+        Some(PinType::Passive) => "passive",
+        Some(PinType::NoConnect) => "no_connect",
+        None => "unspecified",
+    }
+}
+
+fn mm(units: i32) -> String {
+    let s = format!("{:.2}", units as f64 * GRID);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// OPA2197_D_DGK for the table of the D and DGK packages
+pub fn name(pinout: &Pinout) -> String {
+    let mut name = pinout.part.clone();
+    if let Some(p) = &pinout.package {
+        for x in p.split(", ") {
+            name.push('_');
+            name.push_str(x);
+        }
+    }
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '+' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn property(id: usize, key: &str, value: &str, at: (i32, i32), hide: bool) -> String {
+    format!(
+        "    (property {} {} (id {}) (at {} {} 0)\n      (effects (font (size 1.27 1.27)){})\n    )\n",
+        quote(key),
+        quote(value),
+        id,
+        mm(at.0),
+        mm(at.1),
+        if hide { " hide" } else { "" }
+    )
+}
+
+fn pin(p: &Pin, at: (i32, i32), angle: u32) -> String {
+    format!(
+        "      (pin {} line (at {} {} {}) (length {})\n        (name {} (effects (font (size 1.27 1.27))))\n        (number {} (effects (font (size 1.27 1.27))))\n      )\n",
+        electrical(p.kind),
+        mm(at.0),
+        mm(at.1),
+        angle,
+        mm(PIN_LENGTH),
+        quote(&p.name),
+        quote(&p.number)
+    )
+}
+
+// the symbol of one package table, None if it has no pins, the local datasheet is relative to
+// root and only set when it was downloaded
+pub fn symbol(root: &Path, part: &Part, pinout: &Pinout) -> Option<String> {
+    // a number twice is a mistake of the table, the first row wins
+    let mut seen = HashSet::new();
+    let pins: Vec<&Pin> = pinout
+        .pins
+        .iter()
+        .filter(|p| seen.insert(p.number.clone()))
+        .collect();
+    if pins.is_empty() {
+        return None;
+    }
+    let on =
+        |s: Side| -> Vec<&Pin> { pins.iter().cloned().filter(|p| side(p.kind) == s).collect() };
+    let (left, right, top, bottom) = (
+        on(Side::Left),
+        on(Side::Right),
+        on(Side::Top),
+        on(Side::Bottom),
+    );
+
+    // the box fits the pins on top and bottom and the names on the left and right, half of it
+    // in grid units so the pins stay on the grid
+    let widest = |v: &[&Pin]| v.iter().map(|p| p.name.chars().count()).max().unwrap_or(0) as i32;
+    let rows = left.len().max(right.len()).max(1) as i32;
+    let columns = top.len().max(bottom.len()) as i32;
+    let half_width = (columns / 2 + 1).max((widest(&left) + widest(&right) + 5) / 4);
+    let half_height = rows / 2 + 1;
+
+    let graphics = format!(
+        "      (rectangle (start {} {}) (end {} {})\n        (stroke (width 0.254) (type default) (color 0 0 0 0))\n        (fill (type background))\n      )\n",
+        mm(-half_width),
+        mm(half_height),
+        mm(half_width),
+        mm(-half_height)
+    );
+
+    let mut out = String::new();
+    let column = |i: usize, n: usize| i as i32 - (n as i32 - 1) / 2;
+    for (i, p) in left.iter().enumerate() {
+        out.push_str(&pin(
+            p,
+            (-half_width - PIN_LENGTH, half_height - 1 - i as i32),
+            0,
+        ));
+    }
+    for (i, p) in right.iter().enumerate() {
+        out.push_str(&pin(
+            p,
+            (half_width + PIN_LENGTH, half_height - 1 - i as i32),
+            180,
+        ));
+    }
+    for (i, p) in top.iter().enumerate() {
+        out.push_str(&pin(
+            p,
+            (column(i, top.len()), half_height + PIN_LENGTH),
+            270,
+        ));
+    }
+    for (i, p) in bottom.iter().enumerate() {
+        out.push_str(&pin(
+            p,
+            (column(i, bottom.len()), -half_height - PIN_LENGTH),
+            90,
+        ));
+    }
+
+    let name = name(pinout);
+    let top_edge = half_height + PIN_LENGTH;
+    let mut s = format!("  (symbol {} (in_bom yes) (on_board yes)\n", quote(&name));
+    s.push_str(&property(
+        0,
+        "Reference",
+        "U",
+        (-half_width, top_edge + 1),
+        false,
+    ));
+    s.push_str(&property(
+        1,
+        "Value",
+        &part.mpn,
+        (half_width, top_edge + 1),
+        false,
+    ));
+    s.push_str(&property(2, "Footprint", "", (0, 0), true));
+    let url = part.datasheet_url().unwrap_or_default();
+    s.push_str(&property(3, "Datasheet", &url, (0, 0), true));
+    let local = part.datasheet(root).unwrap_or_default();
+    s.push_str(&property(4, "Datasheet_Local", &local, (0, 0), true));
+    let package = pinout.package.as_deref().unwrap_or("");
+    s.push_str(&property(5, "Package", package, (0, 0), true));
+    let source = format!("{} page {}", pinout.source, pinout.page);
+    s.push_str(&property(6, "Pin_Table", &source, (0, 0), true));
+    s.push_str(&format!(
+        "    (symbol {}\n{}    )\n",
+        quote(&format!("{}_0_1", name)),
+        graphics
+    ));
+    s.push_str(&format!(
+        "    (symbol {}\n{}    )\n",
+        quote(&format!("{}_1_1", name)),
+        out
+    ));
+    s.push_str("  )\n");
+    Some(s)
+}
+
+pub fn library(symbols: &[String]) -> String {
+    let mut s = String::from("(kicad_symbol_lib (version 20211014) (generator llh)\n");
+    for x in symbols {
+        s.push_str(x);
+    }
+    s.push_str(")\n");
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    fn part() -> Part {
+        Part {
+            vendor: String::from("ti"),
+            mpn: String::from("OPA197"),
+            category: None,
+            category_path: Vec::new(),
+            unified_category: None,
+            also: Vec::new(),
+            conflicts: Vec::new(),
+            url: None,
+            params: BTreeMap::new(),
+            orderables: Vec::new(),
+            models: Vec::new(),
+            packs: Vec::new(),
+            cad: Vec::new(),
+            datasheet_meta: None,
+            pins: Vec::new(),
+            characteristics: Vec::new(),
+        }
+    }
+
+    fn pinout() -> Pinout {
+        let pin = |number: &str, name: &str, kind| Pin {
+            number: number.to_string(),
+            name: name.to_string(),
+            kind: Some(kind),
+            description: String::new(),
+            uncertain: None,
+        };
+        Pinout {
+            vendor: String::from("ti"),
+            part: String::from("OPA197"),
+            package: Some(String::from("DBV")),
+            source: String::from("pdf/ti/gpn/OPA197.pdf"),
+            page: 3,
+            pins: vec![
+                pin("1", "OUT", PinType::Output),
+                pin("2", "V–", PinType::Ground),
+                pin("3", "+IN", PinType::Input),
+                pin("4", "–IN", PinType::Input),
+                pin("5", "V+", PinType::Power),
+                // the table has it twice
+                pin("5", "V+", PinType::Power),
+            ],
+            missing: Vec::new(),
+        }
+    }
+
+    // inputs left, the output right, supplies top and bottom, the second pin 5 left out
+    const SYMBOL: &str = r#"  (symbol "OPA197_DBV" (in_bom yes) (on_board yes)
+    (property "Reference" "U" (id 0) (at -5.08 10.16 0)
+      (effects (font (size 1.27 1.27)))
+    )
+    (property "Value" "OPA197" (id 1) (at 5.08 10.16 0)
+      (effects (font (size 1.27 1.27)))
+    )
+    (property "Footprint" "" (id 2) (at 0 0 0)
+      (effects (font (size 1.27 1.27)) hide)
+    )
+    (property "Datasheet" "https://www.ti.com/lit/gpn/OPA197" (id 3) (at 0 0 0)
+      (effects (font (size 1.27 1.27)) hide)
+    )
+    (property "Datasheet_Local" "" (id 4) (at 0 0 0)
+      (effects (font (size 1.27 1.27)) hide)
+    )
+    (property "Package" "DBV" (id 5) (at 0 0 0)
+      (effects (font (size 1.27 1.27)) hide)
+    )
+    (property "Pin_Table" "pdf/ti/gpn/OPA197.pdf page 3" (id 6) (at 0 0 0)
+      (effects (font (size 1.27 1.27)) hide)
+    )
+    (symbol "OPA197_DBV_0_1"
+      (rectangle (start -5.08 5.08) (end 5.08 -5.08)
+        (stroke (width 0.254) (type default) (color 0 0 0 0))
+        (fill (type background))
+      )
+    )
+    (symbol "OPA197_DBV_1_1"
+      (pin input line (at -7.62 2.54 0) (length 2.54)
+        (name "+IN" (effects (font (size 1.27 1.27))))
+        (number "3" (effects (font (size 1.27 1.27))))
+      )
+      (pin input line (at -7.62 0 0) (length 2.54)
+        (name "–IN" (effects (font (size 1.27 1.27))))
+        (number "4" (effects (font (size 1.27 1.27))))
+      )
+      (pin output line (at 7.62 2.54 180) (length 2.54)
+        (name "OUT" (effects (font (size 1.27 1.27))))
+        (number "1" (effects (font (size 1.27 1.27))))
+      )
+      (pin power_in line (at 0 7.62 270) (length 2.54)
+        (name "V+" (effects (font (size 1.27 1.27))))
+        (number "5" (effects (font (size 1.27 1.27))))
+      )
+      (pin power_in line (at 0 -7.62 90) (length 2.54)
+        (name "V–" (effects (font (size 1.27 1.27))))
+        (number "2" (effects (font (size 1.27 1.27))))
+      )
+    )
+  )
+"#;
+
+    #[test]
+    fn snapshot() {
+        let root = std::env::temp_dir().join(format!("llh-kicad-none-{}", std::process::id()));
+        assert_eq!(symbol(&root, &part(), &pinout()).unwrap(), SYMBOL);
+    }
+
+    #[test]
+    fn local_datasheet_only_when_downloaded() {
+        let root = std::env::temp_dir().join(format!("llh-kicad-{}", std::process::id()));
+        fs::create_dir_all(root.join("pdf/ti/gpn")).unwrap();
+        fs::write(root.join("pdf/ti/gpn/OPA197.pdf"), "").unwrap();
+        let s = symbol(&root, &part(), &pinout()).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert!(s.contains("(property \"Datasheet_Local\" \"pdf/ti/gpn/OPA197.pdf\" (id 4)"));
+    }
+
+    #[test]
+    fn names_and_empty_tables() {
+        assert_eq!(name(&pinout()), "OPA197_DBV");
+        let mut p = pinout();
+        p.package = Some(String::from("D, DGK"));
+        assert_eq!(name(&p), "OPA197_D_DGK");
+        p.pins.clear();
+        assert_eq!(symbol(Path::new("."), &part(), &p), None);
+    }
+}
@@ -4,14 +4,43 @@ extern crate select;
 extern crate serde;
 
 use indicatif::ProgressStyle;
-use reqwest::{header::USER_AGENT, StatusCode, Url};
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use reqwest::{StatusCode, Url};
 use select::document::Document;
+use select::node::Node;
 use serde_json::json;
+use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
 
-pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+pub mod alternates;
+pub mod bom;
+pub mod category;
+pub mod characteristics;
+pub mod datapage;
+pub mod db;
+pub mod document;
+pub mod ecad;
+pub mod errata;
+pub mod family;
+pub mod fulltext;
+pub mod history;
+pub mod kicad;
+pub mod merge;
+pub mod model;
+pub mod orderable;
+pub mod pack;
+pub mod package;
+pub mod param;
+pub mod pdfmeta;
+pub mod pinout;
+pub mod search;
+pub mod serve;
+pub mod taxonomy;
+pub mod watch;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 lazy_static::lazy_static! {
     pub static ref PB_STYLE: ProgressStyle = ProgressStyle::default_bar()
@@ -32,11 +61,46 @@ pub async fn get_doc(link: &str) -> Result<Document, reqwest::Error> {
     Ok(Document::from(body.as_str()))
 }
 
+pub async fn get_text(link: &str) -> Result<String, reqwest::Error> {
+    let client = reqwest::Client::new();
+    client
+        .get(Url::parse(link).unwrap())
+        .header(USER_AGENT, "curl/7.74.0")
+        .send()
+        .await?
+        .text()
+        .await
+}
+
+// the table row a node is in, the crawlers read the other cells of the row for context
+pub fn enclosing_row(node: Node) -> Option<Node> {
+    let mut n = node.parent();
+    while let Some(p) = n {
+        if p.name() == Some("tr") {
+            return Some(p);
+        }
+        n = p.parent();
+    }
+    None
+}
+
+// whether a node is inside an element with one of these ids, the sections of a product page
+pub fn within(node: Node, ids: &[&str]) -> bool {
+    let mut n = node.parent();
+    while let Some(p) = n {
+        if p.attr("id").is_some_and(|id| ids.contains(&id)) {
+            return true;
+        }
+        n = p.parent();
+    }
+    false
+}
+
 pub async fn save_json(link: String, file_name: String) -> Result<(), reqwest::Error> {
     let path = Path::new(file_name.as_str());
     let display = path.display();
 
-    let mut file = match File::create(&path) {
+    let mut file = match File::create(path) {
         Err(why) => panic!("couldn't create {}: {}", display, why),
         Ok(file) => file,
     };
@@ -60,7 +124,7 @@ pub async fn save_json(link: String, file_name: String) -> Result<(), reqwest::E
 
     match file.write_all(serde_json::to_string_pretty(&v).unwrap().as_bytes()) {
         Err(why) => panic!("couldn't write to {}: {}", display, why),
-        Ok(_) => return Ok(()),
+        Ok(_) => Ok(()),
     }
 }
 
@@ -75,7 +139,7 @@ pub async fn save_pdf(link: String, file_name: String) -> Result<(), reqwest::Er
         return Ok(());
     }
 
-    let mut file = match File::create(&path) {
+    let mut file = match File::create(path) {
         Err(why) => panic!("couldn't create {}: {}", display, why),
         Ok(file) => file,
     };
@@ -100,7 +164,71 @@ pub async fn save_pdf(link: String, file_name: String) -> Result<(), reqwest::Er
 
     match file.write_all(&body) {
         Err(why) => panic!("couldn't write to {}: {}", display, why),
-        Ok(_) => return Ok(()),
+        Ok(_) => Ok(()),
+    }
+}
+
+// download anything that isn't a PDF, files larger than max_size or of other types than the
+// expected extensions are skipped, returns whether the file is there afterwards
+pub async fn save_file(
+    link: String,
+    file_name: String,
+    max_size: u64,
+    extensions: &[&str],
+) -> Result<bool, reqwest::Error> {
+    let path = Path::new(file_name.as_str());
+    let display = path.display();
+
+    if path.exists() {
+        return Ok(true);
+    }
+
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if !extensions.contains(&ext) {
+        return Ok(false);
+    }
+
+    let client = reqwest::Client::new();
+    let mut res = match client
+        .get(Url::parse(link.as_str()).unwrap())
+        .header(USER_AGENT, "curl/7.74.0")
+        .send()
+        .await?
+        .error_for_status()
+    {
+        Ok(res) => res,
+        Err(why) => {
+            eprintln!("skipping {}: {}", link, why);
+            return Ok(false);
+        }
+    };
+
+    // tool pages and logins come back as HTML instead of the file
+    let html = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if html || res.content_length().is_some_and(|n| n > max_size) {
+        return Ok(false);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() as u64 > max_size {
+            return Ok(false);
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        if let Err(why) = fs::create_dir_all(dir) {
+            panic!("couldn't create {}: {}", dir.display(), why);
+        }
+    }
+    match fs::write(path, &body) {
+        Err(why) => panic!("couldn't write to {}: {}", display, why),
+        Ok(_) => Ok(true),
     }
 }
 
@@ -109,15 +237,20 @@ pub async fn empty() -> Result<(), reqwest::Error> {
 }
 
 pub fn dump_json<T: serde::Serialize>(f: &str, m: T) {
+    write_json(f, m);
+    println!("successfully wrote to {}", Path::new(f).display());
+}
+
+// dump_json without the message, for files written while a progress bar is up
+pub fn write_json<T: serde::Serialize>(f: &str, m: T) {
     let path = Path::new(f);
     let display = path.display();
 
-    let mut file = match File::create(&path) {
+    let mut file = match File::create(path) {
         Err(why) => panic!("couldn't create {}: {}", display, why),
         Ok(file) => file,
     };
-    match file.write_fmt(format_args!("{}", json!(m))) {
-        Err(why) => panic!("couldn't write to {}: {}", display, why),
-        Ok(_) => println!("successfully wrote to {}", display),
+    if let Err(why) = file.write_fmt(format_args!("{}", json!(m))) {
+        panic!("couldn't write to {}: {}", display, why);
     }
 }
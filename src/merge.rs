@@ -0,0 +1,225 @@
+// merge combines the copies of a part that the crawlers see more than once, e.g. a TI part
+// listed in several families, keeping where every value came from and what disagreed
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+// which value ends up in the part when two copies disagree, conflicts are recorded either way
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Policy {
+    // the copy seen first wins
+    First,
+    // the copy seen last wins
+    Last,
+    // the copy with the most values wins, ties go to the first
+    Complete,
+}
+
+impl FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Policy, String> {
+        match s {
+            "first" => Ok(Policy::First),
+            "last" => Ok(Policy::Last),
+            "complete" => Ok(Policy::Complete),
+            _ => Err(format!(
+                "unknown merge policy {}, use first, last or complete",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Provenance {
+    // every source the part was seen in, with how many values it had there
+    pub sources: Vec<Source>,
+    // key -> the source its value was taken from
+    pub params: BTreeMap<String, String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Source {
+    pub id: String,
+    pub keys: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Conflict {
+    pub param: String,
+    // source id and value, in the order they were seen
+    pub values: Vec<(String, Value)>,
+}
+
+// merge a copy of a part from source into what is known so far
+pub fn merge(
+    row: &mut HashMap<String, Value>,
+    prov: &mut Provenance,
+    copy: &HashMap<String, Value>,
+    source: &str,
+    policy: Policy,
+) {
+    prov.sources.push(Source {
+        id: source.to_string(),
+        keys: copy.len(),
+    });
+
+    for (k, v) in copy {
+        let current = match row.get(k) {
+            Some(c) if !empty(c) => c,
+            // nothing or only an empty value so far, anything is better
+            _ => {
+                if !empty(v) || !row.contains_key(k) {
+                    row.insert(k.clone(), v.clone());
+                    prov.params.insert(k.clone(), source.to_string());
+                }
+                continue;
+            }
+        };
+
+        if empty(v) || current == v {
+            continue;
+        }
+
+        let owner = prov.params.get(k).cloned().unwrap_or_default();
+        match prov.conflicts.iter_mut().find(|c| c.param == *k) {
+            Some(c) => c.values.push((source.to_string(), v.clone())),
+            None => prov.conflicts.push(Conflict {
+                param: k.clone(),
+                values: vec![
+                    (owner.clone(), current.clone()),
+                    (source.to_string(), v.clone()),
+                ],
+            }),
+        }
+
+        let replace = match policy {
+            Policy::First => false,
+            Policy::Last => true,
+            Policy::Complete => copy.len() > keys(prov, &owner),
+        };
+        if replace {
+            row.insert(k.clone(), v.clone());
+            prov.params.insert(k.clone(), source.to_string());
+        }
+    }
+}
+
+fn keys(prov: &Provenance, source: &str) -> usize {
+    prov.sources
+        .iter()
+        .find(|s| s.id == source)
+        .map_or(0, |s| s.keys)
+}
+
+fn empty(v: &Value) -> bool {
+    match v {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::Array(a) => a.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn copy(values: &[(&str, Value)]) -> HashMap<String, Value> {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    // the part as merged from the copies in order
+    fn merged(
+        copies: &[(&str, HashMap<String, Value>)],
+        policy: Policy,
+    ) -> (HashMap<String, Value>, Provenance) {
+        let mut row = HashMap::new();
+        let mut prov = Provenance::default();
+        for (source, c) in copies {
+            merge(&mut row, &mut prov, c, source, policy);
+        }
+        (row, prov)
+    }
+
+    fn copies() -> Vec<(&'static str, HashMap<String, Value>)> {
+        vec![
+            ("a", copy(&[("p1", json!("36")), ("p2", json!(""))])),
+            (
+                "b",
+                copy(&[
+                    ("p1", json!("40")),
+                    ("p2", json!("10")),
+                    ("p3", json!("SOIC")),
+                ]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn policies() {
+        let (row, prov) = merged(&copies(), Policy::First);
+        assert_eq!(row["p1"], json!("36"));
+        assert_eq!(prov.params["p1"], "a");
+        let (row, prov) = merged(&copies(), Policy::Last);
+        assert_eq!(row["p1"], json!("40"));
+        assert_eq!(prov.params["p1"], "b");
+        // b has more values than a
+        let (row, _) = merged(&copies(), Policy::Complete);
+        assert_eq!(row["p1"], json!("40"));
+        let mut reversed = copies();
+        reversed.reverse();
+        let (row, _) = merged(&reversed, Policy::Complete);
+        assert_eq!(row["p1"], json!("40"));
+    }
+
+    #[test]
+    fn empty_values_are_filled_without_conflict() {
+        for policy in &[Policy::First, Policy::Last, Policy::Complete] {
+            let (row, prov) = merged(&copies(), *policy);
+            assert_eq!(row["p2"], json!("10"));
+            assert_eq!(prov.params["p2"], "b");
+            assert_eq!(row["p3"], json!("SOIC"));
+            let conflicts: Vec<&str> = prov.conflicts.iter().map(|c| c.param.as_str()).collect();
+            assert_eq!(conflicts, vec!["p1"]);
+        }
+    }
+
+    #[test]
+    fn conflicts_keep_every_value() {
+        let mut c = copies();
+        c.push(("c", copy(&[("p1", json!("32"))])));
+        // the same value again is no conflict
+        c.push(("d", copy(&[("p1", json!("36"))])));
+        let (_, prov) = merged(&c, Policy::First);
+        assert_eq!(prov.conflicts.len(), 1);
+        assert_eq!(
+            prov.conflicts[0].values,
+            vec![
+                (String::from("a"), json!("36")),
+                (String::from("b"), json!("40")),
+                (String::from("c"), json!("32")),
+            ]
+        );
+        let sources: Vec<(&str, usize)> = prov
+            .sources
+            .iter()
+            .map(|s| (s.id.as_str(), s.keys))
+            .collect();
+        assert_eq!(sources, vec![("a", 2), ("b", 3), ("c", 1), ("d", 1)]);
+    }
+
+    #[test]
+    fn policy_names() {
+        assert_eq!("complete".parse::<Policy>(), Ok(Policy::Complete));
+        assert!("newest".parse::<Policy>().is_err());
+    }
+}
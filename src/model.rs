@@ -0,0 +1,227 @@
+// model keeps the simulation models of the parts, unpacked from the vendor archives into
+// models/{vendor}/{part}/{type}/ and listed in json/{vendor}/models.json
+
+use crate::db::open_json;
+use crate::document::{Document, DocumentKind};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelType {
+    Spice,
+    Pspice,
+    Tina,
+    Ibis,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ModelFile {
+    pub vendor: String,
+    pub part: String,
+    #[serde(rename = "type")]
+    pub kind: ModelType,
+    // below the archive root
+    pub path: String,
+    // the document the file was unpacked from
+    pub source: String,
+}
+
+// file extensions of the model formats, plain SPICE netlists come in many flavours
+const EXTENSIONS: &[(&str, ModelType)] = &[
+    ("ibs", ModelType::Ibis),
+    ("pkg", ModelType::Ibis),
+    ("ebd", ModelType::Ibis),
+    ("tsc", ModelType::Tina),
+    ("tsm", ModelType::Tina),
+    ("olb", ModelType::Pspice),
+    ("lib", ModelType::Spice),
+    ("cir", ModelType::Spice),
+    ("mod", ModelType::Spice),
+    ("sp", ModelType::Spice),
+    ("spi", ModelType::Spice),
+    ("ckt", ModelType::Spice),
+    ("sub", ModelType::Spice),
+];
+
+impl ModelType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelType::Spice => "spice",
+            ModelType::Pspice => "pspice",
+            ModelType::Tina => "tina",
+            ModelType::Ibis => "ibis",
+        }
+    }
+
+    // the type of a file by its extension, a SPICE library from a PSpice package is a PSpice
+    // model, title is the title of the document it came with
+    pub fn of(file: &str, title: &str) -> Option<ModelType> {
+        let ext = Path::new(file).extension()?.to_str()?.to_lowercase();
+        let kind = EXTENSIONS.iter().find(|(e, _)| *e == ext)?.1;
+        let hint = format!("{} {}", file, title).to_lowercase();
+        if kind == ModelType::Spice && hint.contains("pspice") {
+            return Some(ModelType::Pspice);
+        }
+        Some(kind)
+    }
+}
+
+impl std::str::FromStr for ModelType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ModelType, String> {
+        match s.to_lowercase().as_str() {
+            "spice" => Ok(ModelType::Spice),
+            "pspice" => Ok(ModelType::Pspice),
+            "tina" | "tina-ti" => Ok(ModelType::Tina),
+            "ibis" => Ok(ModelType::Ibis),
+            _ => Err(format!(
+                "unknown model type {}, use spice, pspice, tina or ibis",
+                s
+            )),
+        }
+    }
+}
+
+pub fn load(root: &Path, vendor: &str) -> Vec<ModelFile> {
+    open_json(&root.join(format!("json/{}/models.json", vendor))).unwrap_or_default()
+}
+
+// unpack a downloaded model document into the store of every part that links it, archives are
+// unpacked, single files copied, files that are no model are left out
+pub fn store(root: &Path, doc: &Document, file: &Path) -> io::Result<Vec<ModelFile>> {
+    let mut found: Vec<(String, Vec<u8>)> = Vec::new();
+
+    if file
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+    {
+        let mut zip = zip::ZipArchive::new(File::open(file)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        for i in 0..zip.len() {
+            let mut entry = zip
+                .by_index(i)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            // entries that would end up outside the store are skipped
+            let name = match entry.enclosed_name() {
+                Some(n) if entry.is_file() => n.to_path_buf(),
+                _ => continue,
+            };
+            let name = name.to_string_lossy().to_string();
+            if ModelType::of(&name, &doc.title).is_none() {
+                continue;
+            }
+            let mut data = Vec::new();
+            io::copy(&mut entry, &mut data)?;
+            found.push((name, data));
+        }
+    } else if let Some(name) = file.file_name() {
+        found.push((name.to_string_lossy().to_string(), fs::read(file)?));
+    }
+
+    let mut out = Vec::new();
+    for part in &doc.parts {
+        for (name, data) in &found {
+            let kind = match ModelType::of(name, &doc.title) {
+                Some(k) => k,
+                None => continue,
+            };
+            let rel: PathBuf = ["models", &doc.vendor, part, kind.name()]
+                .iter()
+                .collect::<PathBuf>()
+                .join(name);
+            let dest = root.join(&rel);
+            if let Some(dir) = dest.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&dest, data)?;
+
+            out.push(ModelFile {
+                vendor: doc.vendor.clone(),
+                part: part.clone(),
+                kind,
+                path: rel.to_string_lossy().to_string(),
+                source: doc.url.clone(),
+            });
+        }
+    }
+
+    Ok(out)
+}
+
+// unpack every model document that has been downloaded below root, one entry per file and part
+pub fn index(root: &Path, docs: &[Document]) -> Vec<ModelFile> {
+    let mut out: Vec<ModelFile> = Vec::new();
+    for d in docs.iter().filter(|d| d.kind == DocumentKind::Model) {
+        let file = match d.local_path() {
+            Some(p) if root.join(&p).exists() => root.join(p),
+            _ => continue,
+        };
+        match store(root, d, &file) {
+            Ok(files) => {
+                for f in files {
+                    if !out.contains(&f) {
+                        out.push(f);
+                    }
+                }
+            }
+            Err(why) => eprintln!("couldn't unpack {}: {}", file.display(), why),
+        }
+    }
+    out.sort_by(|a, b| (&a.part, a.kind, &a.path).cmp(&(&b.part, b.kind, &b.path)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types() {
+        assert_eq!(ModelType::of("OPA2197.LIB", ""), Some(ModelType::Spice));
+        assert_eq!(ModelType::of("opa2197.tsm", ""), Some(ModelType::Tina));
+        assert_eq!(ModelType::of("opa2197.ibs", ""), Some(ModelType::Ibis));
+        assert_eq!(ModelType::of("opa2197.olb", ""), Some(ModelType::Pspice));
+        assert_eq!(ModelType::of("readme.txt", ""), None);
+        assert_eq!(ModelType::of("Makefile", ""), None);
+    }
+
+    #[test]
+    fn pspice_hint() {
+        assert_eq!(
+            ModelType::of("OPA2197.lib", "OPA2197 PSpice Model"),
+            Some(ModelType::Pspice)
+        );
+        assert_eq!(
+            ModelType::of("PSpice/OPA2197.cir", ""),
+            Some(ModelType::Pspice)
+        );
+        // only SPICE libraries, TINA and IBIS files stay what they are
+        assert_eq!(
+            ModelType::of("OPA2197.tsm", "OPA2197 PSpice Model"),
+            Some(ModelType::Tina)
+        );
+        assert_eq!(
+            ModelType::of("OPA2197.lib", "OPA2197 TINA-TI Spice Model"),
+            Some(ModelType::Spice)
+        );
+    }
+
+    #[test]
+    fn names() {
+        for kind in &[
+            ModelType::Spice,
+            ModelType::Pspice,
+            ModelType::Tina,
+            ModelType::Ibis,
+        ] {
+            assert_eq!(kind.name().parse::<ModelType>(), Ok(*kind));
+        }
+        assert_eq!("TINA-TI".parse::<ModelType>(), Ok(ModelType::Tina));
+        assert!("verilog".parse::<ModelType>().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+// orderable models the orderable part numbers (package, pin count and packing variants)
+// of a generic part, as collected by the crawlers into json/{ti,st}/orderables.json
+
+use crate::db::open_json;
+use crate::package::Package;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Orderable {
+    pub opn: String,
+    pub gpn: String,
+    pub vendor: String,
+    pub package: Option<String>,
+    pub pins: Option<u32>,
+    pub packing: Option<String>,
+    pub status: Option<String>,
+    // the package in the canonical vocabulary, with pitch and body size where known
+    #[serde(default)]
+    pub canonical: Option<Package>,
+}
+
+// packing words as they show up in the order tables, and what they are called here
+const PACKING: &[(&str, &str)] = &[
+    ("cut tape", "cut tape"),
+    ("t&r", "tape and reel"),
+    ("tape and reel", "tape and reel"),
+    ("tape & reel", "tape and reel"),
+    ("reel", "tape and reel"),
+    ("tube", "tube"),
+    ("tray", "tray"),
+    ("bulk", "bulk"),
+];
+
+// package families whose number is part of the name and not a pin count
+const NUMBERED_FAMILIES: &[&str] = &["SOT", "TO", "DO", "SC", "SOD"];
+
+// the pins of the numbered packages when no count follows, SOT-23 has 3 and SOT-23-5 has 5
+const FIXED_PINS: &[(&str, &str, u32)] = &[
+    ("SOT", "23", 3),
+    ("SOT", "89", 3),
+    ("SOT", "223", 4),
+    ("SOT", "323", 3),
+    ("SC", "70", 3),
+    ("TO", "92", 3),
+    ("TO", "220", 3),
+    ("TO", "252", 3),
+    ("TO", "263", 3),
+    ("SOD", "123", 2),
+    ("SOD", "323", 2),
+    ("SOD", "523", 2),
+    ("DO", "214", 2),
+];
+
+// all orderables of a vendor, keyed by generic part number
+pub fn load(root: &Path, vendor: &str) -> HashMap<String, Vec<Orderable>> {
+    open_json(&root.join(format!("json/{}/orderables.json", vendor))).unwrap_or_default()
+}
+
+// the pin count in a package description, "SOIC (D) | 8", "LQFP 100 14x14x1.4 mm" or "SOT-23-5"
+pub fn pins_from_package(s: &str) -> Option<u32> {
+    if let Some((_, pins)) = s.rsplit_once('|') {
+        return pins.trim().parse().ok();
+    }
+
+    let mut prev = "";
+    let mut fixed = None;
+    for t in s.split(|c: char| c.is_whitespace() || c == '-' || c == '(' || c == ')') {
+        if t.is_empty() {
+            continue;
+        }
+
+        // body sizes like 14x14x1.4 are never pin counts
+        let (prefix, digits) = if t.contains(['x', 'X', '.']) {
+            (t, "")
+        } else if t.starts_with(|c: char| c.is_ascii_digit()) {
+            // "8N" from SO-8N
+            let end = t.find(|c: char| !c.is_ascii_digit()).unwrap_or(t.len());
+            (prev, &t[..end])
+        } else {
+            // "TSSOP20" carries the pins at the end
+            let start = t.rfind(|c: char| !c.is_ascii_digit()).map_or(0, |i| i + 1);
+            (&t[..start], &t[start..])
+        };
+
+        let family = NUMBERED_FAMILIES
+            .iter()
+            .any(|f| prefix.eq_ignore_ascii_case(f));
+        if let Ok(n) = digits.parse::<u32>() {
+            if !family && n > 0 && n < 2000 {
+                return Some(n);
+            }
+        }
+        if family && fixed.is_none() {
+            fixed = FIXED_PINS
+                .iter()
+                .find(|(f, number, _)| prefix.eq_ignore_ascii_case(f) && digits == *number)
+                .map(|(_, _, n)| *n);
+        }
+
+        prev = t;
+    }
+
+    fixed
+}
+
+pub fn packing_from_text(s: &str) -> Option<String> {
+    let s = s.to_lowercase();
+    PACKING
+        .iter()
+        .find(|(k, _)| s.contains(k))
+        .map(|(_, v)| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pin_counts() {
+        assert_eq!(pins_from_package("SOIC (D) | 8"), Some(8));
+        assert_eq!(pins_from_package("LQFP 100 14x14x1.4 mm"), Some(100));
+        assert_eq!(pins_from_package("TSSOP20"), Some(20));
+        assert_eq!(pins_from_package("SO-8N"), Some(8));
+        assert_eq!(pins_from_package("SOT-23-5"), Some(5));
+        assert_eq!(pins_from_package("SOT-23"), Some(3));
+        assert_eq!(pins_from_package("SOT23"), Some(3));
+        assert_eq!(pins_from_package("TO-220"), Some(3));
+        assert_eq!(pins_from_package("SOD-123"), Some(2));
+        assert_eq!(pins_from_package("TO-999"), None);
+        assert_eq!(pins_from_package("SOIC"), None);
+    }
+}
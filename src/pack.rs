@@ -0,0 +1,353 @@
+// pack finds the CMSIS device family packs of the MCU parts in the Keil pack index, the .pdsc
+// and .svd files are unpacked into packs/{vendor}/{pack}/ and listed in json/{vendor}/packs.json
+
+use crate::db::open_json;
+use futures::{stream, StreamExt};
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// every public pack, the NXP ones point to NXP's own repository
+pub const INDEX: &str = "https://www.keil.com/pack/index.pidx";
+
+// the largest STM32 packs are a few hundred MB
+const MAX_SIZE: u64 = 1 << 30;
+
+// Dvendor of the pack devices, "STMicroelectronics:13"
+const VENDORS: &[(&str, &str)] = &[
+    ("STMicroelectronics", "st"),
+    ("NXP", "nxp"),
+    ("Freescale", "nxp"),
+    ("Texas Instruments", "ti"),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pack {
+    pub url: String,
+    pub vendor: String,
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Device {
+    pub name: String,
+    pub vendor: Option<&'static str>,
+    // inside the pack
+    pub svd: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum PackKind {
+    Pdsc,
+    Svd,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PackFile {
+    pub vendor: String,
+    pub part: String,
+    // the device of the pack the part was matched to
+    pub device: String,
+    #[serde(rename = "type")]
+    pub kind: PackKind,
+    // vendor.name.version of the pack
+    pub pack: String,
+    // below the archive root
+    pub path: String,
+}
+
+impl Pack {
+    pub fn id(&self) -> String {
+        format!("{}.{}.{}", self.vendor, self.name, self.version)
+    }
+
+    pub fn pdsc_url(&self) -> String {
+        format!("{}{}.{}.pdsc", self.url, self.vendor, self.name)
+    }
+
+    pub fn pack_url(&self) -> String {
+        format!("{}{}.pack", self.url, self.id())
+    }
+
+    // only device family packs describe devices, the rest are middleware and boards
+    pub fn is_device_pack(&self) -> bool {
+        self.name.ends_with("_DFP")
+    }
+}
+
+impl PackKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackKind::Pdsc => "pdsc",
+            PackKind::Svd => "svd",
+        }
+    }
+}
+
+pub fn vendor(dvendor: &str) -> Option<&'static str> {
+    let name = dvendor.split(':').next().unwrap_or("").trim();
+    VENDORS.iter().find(|(v, _)| *v == name).map(|(_, v)| *v)
+}
+
+// the packs in a .pidx file
+pub fn parse_index(xml: &str) -> Vec<Pack> {
+    let doc = match roxmltree::Document::parse(xml) {
+        Ok(doc) => doc,
+        Err(why) => {
+            eprintln!("couldn't parse the pack index: {}", why);
+            return Vec::new();
+        }
+    };
+
+    doc.descendants()
+        .filter(|n| n.has_tag_name("pdsc"))
+        .filter_map(|n| {
+            Some(Pack {
+                url: n.attribute("url")?.to_string(),
+                vendor: n.attribute("vendor")?.to_string(),
+                name: n.attribute("name")?.to_string(),
+                version: n.attribute("version")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+// the devices and variants of a .pdsc, vendor and svd are inherited from the family and
+// sub-family they are in
+pub fn parse_pdsc(xml: &str) -> Vec<Device> {
+    let doc = match roxmltree::Document::parse(xml) {
+        Ok(doc) => doc,
+        Err(why) => {
+            eprintln!("couldn't parse pdsc: {}", why);
+            return Vec::new();
+        }
+    };
+
+    doc.descendants()
+        .filter_map(|n| match n.tag_name().name() {
+            "device" => Some((n, n.attribute("Dname")?)),
+            "variant" => Some((n, n.attribute("Dvariant")?)),
+            _ => None,
+        })
+        .map(|(n, name)| {
+            let vendor = n
+                .ancestors()
+                .find_map(|a| a.attribute("Dvendor"))
+                .and_then(vendor);
+            let svd = n.ancestors().find_map(|a| {
+                a.children()
+                    .filter(|c| c.has_tag_name("debug"))
+                    .find_map(|c| c.attribute("svd"))
+            });
+            Device {
+                name: name.to_string(),
+                vendor,
+                svd: svd.map(|s| s.replace('\\', "/")),
+            }
+        })
+        .collect()
+}
+
+// device names have lower case x for the codes the pack doesn't care about, STM32F407VGTx,
+// either name may go on with package, temperature or packing codes
+pub fn matches(device: &str, mpn: &str) -> bool {
+    device.chars().count().min(mpn.chars().count()) >= 6
+        && device
+            .chars()
+            .zip(mpn.chars())
+            .all(|(d, m)| d == 'x' || d.eq_ignore_ascii_case(&m))
+}
+
+pub fn load(root: &Path, vendor: &str) -> Vec<PackFile> {
+    open_json(&root.join(format!("json/{}/packs.json", vendor))).unwrap_or_default()
+}
+
+// the .pdsc and .svd files of a downloaded pack, returns their paths below root
+pub fn unpack(root: &Path, dir: &Path, file: &Path) -> io::Result<Vec<String>> {
+    let mut zip = zip::ZipArchive::new(File::open(file)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut out = Vec::new();
+
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // entries that would end up outside the store are skipped
+        let name = match entry.enclosed_name() {
+            Some(n) if entry.is_file() => n.to_path_buf(),
+            _ => continue,
+        };
+        let wanted = name
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("pdsc") || e.eq_ignore_ascii_case("svd"));
+        if !wanted {
+            continue;
+        }
+
+        let rel = dir.join(&name);
+        let dest = root.join(&rel);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&dest)?)?;
+        out.push(rel.to_string_lossy().replace('\\', "/"));
+    }
+
+    Ok(out)
+}
+
+// find the packs with devices of vendor in the index, download and unpack them and link them
+// to the parts, without parts every device is kept under its own name
+pub async fn crawl(root: &Path, vendor: &str, parts: &[String]) -> Vec<PackFile> {
+    let index = match crate::get_text(INDEX).await {
+        Ok(index) => parse_index(&index),
+        Err(why) => {
+            eprintln!("couldn't fetch {}: {}", INDEX, why);
+            return Vec::new();
+        }
+    };
+    let packs: Vec<Pack> = index.into_iter().filter(|p| p.is_device_pack()).collect();
+
+    let pb = ProgressBar::new(packs.len() as u64);
+    pb.set_style(crate::PB_STYLE.clone());
+    pb.set_message("Fetching pack descriptions...");
+
+    // pack -> (part, device)
+    let found: Vec<(Pack, Vec<(String, Device)>)> = stream::iter(packs)
+        .map(|pack| async move {
+            let pdsc = crate::get_text(&pack.pdsc_url()).await;
+            (pack, pdsc)
+        })
+        .buffer_unordered(8)
+        .filter_map(|(pack, pdsc)| {
+            pb.inc(1);
+            let pdsc = match pdsc {
+                Ok(pdsc) => pdsc,
+                Err(why) => {
+                    eprintln!("couldn't fetch {}: {}", pack.pdsc_url(), why);
+                    return futures::future::ready(None);
+                }
+            };
+            let mut linked = Vec::new();
+            for d in parse_pdsc(&pdsc) {
+                if d.vendor != Some(vendor) {
+                    continue;
+                }
+                if parts.is_empty() {
+                    linked.push((d.name.clone(), d));
+                    continue;
+                }
+                for p in parts.iter().filter(|p| matches(&d.name, p)) {
+                    linked.push((p.clone(), d.clone()));
+                }
+            }
+            futures::future::ready(if linked.is_empty() {
+                None
+            } else {
+                Some((pack, linked))
+            })
+        })
+        .collect()
+        .await;
+    pb.finish_and_clear();
+
+    let pb = ProgressBar::new(found.len() as u64);
+    pb.set_style(crate::PB_STYLE.clone());
+    pb.set_message("Fetching packs...");
+
+    let mut out: Vec<PackFile> = Vec::new();
+    for (pack, linked) in found {
+        pb.inc(1);
+        let file = format!("files/packs/{}.pack", pack.id());
+        match crate::save_file(
+            pack.pack_url(),
+            root.join(&file).display().to_string(),
+            MAX_SIZE,
+            &["pack"],
+        )
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(why) => {
+                eprintln!("couldn't fetch {}: {}", pack.pack_url(), why);
+                continue;
+            }
+        }
+
+        let dir: PathBuf = ["packs", vendor, &pack.id()].iter().collect();
+        let files = match unpack(root, &dir, &root.join(&file)) {
+            Ok(files) => files,
+            Err(why) => {
+                eprintln!("couldn't unpack {}: {}", file, why);
+                continue;
+            }
+        };
+        let pdsc = files.iter().find(|f| f.ends_with(".pdsc"));
+
+        for (part, device) in linked {
+            let svd = device
+                .svd
+                .as_ref()
+                .map(|s| dir.join(s).to_string_lossy().replace('\\', "/"))
+                .filter(|s| files.contains(s));
+            let entries = [(PackKind::Pdsc, pdsc.cloned()), (PackKind::Svd, svd)];
+            for (kind, path) in entries.iter() {
+                let path = match path {
+                    Some(path) => path,
+                    None => continue,
+                };
+                if out
+                    .iter()
+                    .any(|f| f.part == part && f.kind == *kind && f.path == *path)
+                {
+                    continue;
+                }
+                out.push(PackFile {
+                    vendor: vendor.to_string(),
+                    part: part.clone(),
+                    device: device.name.clone(),
+                    kind: *kind,
+                    pack: pack.id(),
+                    path: path.clone(),
+                });
+            }
+        }
+    }
+    pb.finish_and_clear();
+
+    out.sort_by(|a, b| (&a.part, a.kind, &a.path).cmp(&(&b.part, b.kind, &b.path)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_names() {
+        assert!(matches("STM32F407VGTx", "STM32F407VGT6"));
+        assert!(matches("STM32F407VGTx", "stm32f407vgt6tr"));
+        // either name may go on with more codes
+        assert!(matches("STM32F407VG", "STM32F407VGT6"));
+        assert!(matches("STM32F407VGT6", "STM32F407VG"));
+        assert!(!matches("STM32F407VGTx", "STM32F405VGT6"));
+        // too short to tell the families apart
+        assert!(!matches("STM32", "STM32F407VGT6"));
+        assert!(!matches("STM32F407VGTx", "STM"));
+    }
+
+    #[test]
+    fn vendors() {
+        assert_eq!(vendor("STMicroelectronics:13"), Some("st"));
+        assert_eq!(vendor("Texas Instruments:16"), Some("ti"));
+        assert_eq!(vendor("Freescale:78"), Some("nxp"));
+        assert_eq!(vendor("Nordic Semiconductor:54"), None);
+    }
+}
@@ -0,0 +1,348 @@
+// package normalises the package names of the vendors ("SOIC (D) | 8", "SO8", "SO-8N",
+// "TSSOP20", "LQFP 100 14x14x1.4 mm") to JEDEC style names like SOIC-8, with pitch and body
+// size where they are known, so parts can be compared by footprint
+
+use crate::orderable;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Package {
+    // canonical name, e.g. "SOIC-8" or "SOT-23-5"
+    pub name: String,
+    pub family: String,
+    pub pins: Option<u32>,
+    // lead pitch in mm
+    pub pitch: Option<f64>,
+    // body width and length in mm
+    pub body: Option<(f64, f64)>,
+    // the vendor's package designator, e.g. TI's "D" or "DGK"
+    pub code: Option<String>,
+}
+
+// names of the families as the vendors write them
+const FAMILIES: &[(&str, &str)] = &[
+    ("SO", "SOIC"),
+    ("SOIC", "SOIC"),
+    ("SOP", "SOP"),
+    ("SSOP", "SSOP"),
+    ("TSSOP", "TSSOP"),
+    ("HTSSOP", "HTSSOP"),
+    ("MSOP", "MSOP"),
+    ("MINISO", "MSOP"),
+    ("VSSOP", "MSOP"),
+    ("SOT", "SOT"),
+    ("SC", "SC"),
+    ("TO", "TO"),
+    ("DPAK", "TO-252"),
+    ("D2PAK", "TO-263"),
+    ("QFN", "QFN"),
+    ("VQFN", "QFN"),
+    ("WQFN", "QFN"),
+    ("UQFN", "QFN"),
+    ("TQFN", "QFN"),
+    ("HVQFN", "QFN"),
+    ("UFQFPN", "QFN"),
+    ("VFQFPN", "QFN"),
+    ("DFN", "DFN"),
+    ("UDFN", "DFN"),
+    ("SON", "DFN"),
+    ("VSON", "DFN"),
+    ("WSON", "DFN"),
+    ("USON", "DFN"),
+    ("QFP", "QFP"),
+    ("LQFP", "LQFP"),
+    ("TQFP", "TQFP"),
+    ("HTQFP", "TQFP"),
+    ("BGA", "BGA"),
+    ("FBGA", "BGA"),
+    ("LFBGA", "BGA"),
+    ("TFBGA", "BGA"),
+    ("UFBGA", "BGA"),
+    ("VFBGA", "BGA"),
+    ("NFBGA", "BGA"),
+    ("WLCSP", "WLCSP"),
+    ("DSBGA", "WLCSP"),
+    ("CSP", "WLCSP"),
+    ("DIP", "DIP"),
+    ("PDIP", "DIP"),
+];
+
+// families whose number is part of the name, SOT-23 or TO-220
+const NUMBERED: &[&str] = &["SOT", "SC", "TO"];
+
+// TI's package designators, the name next to them isn't always the JEDEC one (VSSOP is MSOP)
+const TI_CODES: &[(&str, &str)] = &[
+    ("D", "SOIC"),
+    ("DW", "SOIC"),
+    ("PW", "TSSOP"),
+    ("PWP", "HTSSOP"),
+    ("DGK", "MSOP"),
+    ("DGS", "MSOP"),
+    ("DBV", "SOT-23"),
+    ("DBZ", "SOT-23"),
+    ("DDF", "SOT-23"),
+    ("DCK", "SC-70"),
+    ("P", "DIP"),
+    ("N", "DIP"),
+    ("RGT", "QFN"),
+    ("RTE", "QFN"),
+    ("RUM", "QFN"),
+    ("DRB", "DFN"),
+    ("DSG", "DFN"),
+    ("YZP", "WLCSP"),
+    ("PM", "LQFP"),
+    ("PN", "LQFP"),
+    ("PZ", "LQFP"),
+];
+
+// wide body SOICs, TI's DW and the "W" of ST's SO-16W
+const WIDE_CODES: &[&str] = &["DW"];
+
+// family, pins, wide body, pitch, body width and length, shared by families of the same
+// footprint
+const DIMENSIONS: &[(&str, u32, bool, f64, f64, f64)] = &[
+    ("SOIC", 8, false, 1.27, 3.9, 4.9),
+    ("SOIC", 14, false, 1.27, 3.9, 8.65),
+    ("SOIC", 16, false, 1.27, 3.9, 9.9),
+    ("SOIC", 16, true, 1.27, 7.5, 10.3),
+    ("SOIC", 20, true, 1.27, 7.5, 12.8),
+    ("SOIC", 24, true, 1.27, 7.5, 15.4),
+    ("SOIC", 28, true, 1.27, 7.5, 17.9),
+    ("TSSOP", 8, false, 0.65, 4.4, 3.0),
+    ("TSSOP", 14, false, 0.65, 4.4, 5.0),
+    ("TSSOP", 16, false, 0.65, 4.4, 5.0),
+    ("TSSOP", 20, false, 0.65, 4.4, 6.5),
+    ("TSSOP", 24, false, 0.65, 4.4, 7.8),
+    ("TSSOP", 28, false, 0.65, 4.4, 9.7),
+    ("MSOP", 8, false, 0.65, 3.0, 3.0),
+    ("MSOP", 10, false, 0.5, 3.0, 3.0),
+    ("SOT-23", 3, false, 0.95, 1.3, 2.9),
+    ("SOT-23", 5, false, 0.95, 1.6, 2.9),
+    ("SOT-23", 6, false, 0.95, 1.6, 2.9),
+    ("SOT-23", 8, false, 0.65, 1.6, 2.9),
+    ("SC-70", 5, false, 0.65, 1.25, 2.0),
+    ("SC-70", 6, false, 0.65, 1.25, 2.0),
+    ("LQFP", 32, false, 0.8, 7.0, 7.0),
+    ("LQFP", 48, false, 0.5, 7.0, 7.0),
+    ("LQFP", 64, false, 0.5, 10.0, 10.0),
+    ("LQFP", 80, false, 0.5, 12.0, 12.0),
+    ("LQFP", 100, false, 0.5, 14.0, 14.0),
+    ("LQFP", 144, false, 0.5, 20.0, 20.0),
+    ("LQFP", 176, false, 0.5, 24.0, 24.0),
+];
+
+// the pitch of families that only come in one
+const PITCHES: &[(&str, f64)] = &[("SOIC", 1.27), ("DIP", 2.54)];
+
+// families with the same land pattern, LQFP and TQFP only differ in height
+const FOOTPRINTS: &[(&str, &str)] = &[("LQFP", "QFP"), ("TQFP", "QFP")];
+
+// dimensions closer than this (mm) are the same footprint
+const TOLERANCE: f64 = 0.1;
+
+impl Package {
+    // pins are taken from the name, the pin count of the orderable fills in when it has none
+    pub fn parse(s: &str, pins: Option<u32>) -> Option<Package> {
+        let upper = s.trim().to_uppercase();
+        let code = designator(&upper);
+
+        let first = upper.split([' ', '(', '|', ',']).find(|t| !t.is_empty())?;
+        let letters: String = first
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        let rest = first[letters.len()..].trim_start_matches('-');
+
+        let ti = code
+            .as_deref()
+            .and_then(|c| TI_CODES.iter().find(|(x, _)| *x == c))
+            .map(|(_, f)| f.to_string());
+
+        // D2PAK has a digit in the name, names outside the vocabulary can't be compared unless
+        // the TI designator tells the family
+        let mut family = match FAMILIES.iter().find(|(a, _)| *a == first || *a == letters) {
+            Some((_, f)) => f.to_string(),
+            None => ti.clone()?,
+        };
+        if NUMBERED.contains(&family.as_str()) {
+            let number: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if number.is_empty() {
+                return None;
+            }
+            family = format!("{}-{}", family, number);
+        }
+        if let Some(f) = ti {
+            family = f;
+        }
+
+        let pins = orderable::pins_from_package(s).or(pins);
+        let wide = code.as_deref().is_some_and(|c| WIDE_CODES.contains(&c))
+            || upper.contains("WIDE")
+            || (rest.ends_with('W') && rest.starts_with(|c: char| c.is_ascii_digit()));
+
+        // 20 pins and up SOICs only come wide
+        let sizes: Vec<_> = DIMENSIONS
+            .iter()
+            .filter(|d| footprint(d.0) == footprint(&family) && Some(d.1) == pins)
+            .collect();
+        let known = sizes.iter().find(|d| d.2 == wide).or_else(|| sizes.first());
+        let body = body_size(&upper).or_else(|| known.map(|d| (d.4, d.5)));
+        let pitch = known
+            .map(|d| d.3)
+            .or_else(|| PITCHES.iter().find(|(f, _)| *f == family).map(|(_, p)| *p));
+
+        Some(Package {
+            name: match pins {
+                Some(n) => format!("{}-{}", family, n),
+                None => family.clone(),
+            },
+            family,
+            pins,
+            pitch,
+            body,
+            code,
+        })
+    }
+
+    // whether one can be placed on the land pattern of the other, unknown dimensions don't count
+    pub fn compatible(&self, other: &Package) -> bool {
+        let same = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => (a - b).abs() <= TOLERANCE,
+            _ => true,
+        };
+        let body = match (self.body, other.body) {
+            (Some(a), Some(b)) => same(Some(a.0), Some(b.0)) && same(Some(a.1), Some(b.1)),
+            _ => true,
+        };
+
+        footprint(&self.family) == footprint(&other.family)
+            && (self.pins.is_none() || other.pins.is_none() || self.pins == other.pins)
+            && same(self.pitch, other.pitch)
+            && body
+    }
+}
+
+// whether a word names a package, a family like "VSSOP" or "SO8" or a TI designator like "DGK"
+pub fn known(word: &str) -> bool {
+    let upper = word.trim_matches(|c: char| !c.is_ascii_alphanumeric()).to_uppercase();
+    let letters: String = upper
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    FAMILIES.iter().any(|(a, _)| *a == upper || *a == letters)
+        || TI_CODES.iter().any(|(c, _)| *c == upper)
+}
+
+impl fmt::Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some((w, l)) = self.body {
+            write!(f, " {}x{} mm", w, l)?;
+        }
+        if let Some(p) = self.pitch {
+            write!(f, " {} mm pitch", p)?;
+        }
+        Ok(())
+    }
+}
+
+fn footprint(family: &str) -> &str {
+    FOOTPRINTS
+        .iter()
+        .find(|(f, _)| *f == family)
+        .map_or(family, |(_, g)| g)
+}
+
+// the designator in brackets, "SOIC (D) | 8", a number in brackets is a pin count
+fn designator(s: &str) -> Option<String> {
+    let inner = s.split('(').nth(1)?.split(')').next()?.trim();
+    if !inner.is_empty() && inner.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some(inner.to_string())
+    } else {
+        None
+    }
+}
+
+// "14x14x1.4 mm" or "3.9 x 4.9", the height is left out
+fn body_size(s: &str) -> Option<(f64, f64)> {
+    let s = s.replace(" X ", "X");
+    s.split_whitespace().find_map(|t| {
+        let dims: Vec<f64> = t
+            .trim_end_matches("MM")
+            .split('X')
+            .map(|x| x.parse().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        if dims.len() >= 2 {
+            Some((dims[0], dims[1]))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(s: &str) -> Option<String> {
+        Package::parse(s, None).map(|p| p.name)
+    }
+
+    #[test]
+    fn vendor_spellings() {
+        assert_eq!(name("SOIC (D) | 8").as_deref(), Some("SOIC-8"));
+        assert_eq!(name("SO8").as_deref(), Some("SOIC-8"));
+        assert_eq!(name("SO-8N").as_deref(), Some("SOIC-8"));
+        assert_eq!(name("TSSOP20").as_deref(), Some("TSSOP-20"));
+        assert_eq!(name("VSSOP (DGK) | 8").as_deref(), Some("MSOP-8"));
+        assert_eq!(name("SOT-23 (DBV) | 5").as_deref(), Some("SOT-23-5"));
+    }
+
+    #[test]
+    fn pins_of_the_orderable() {
+        let p = Package::parse("SOIC", Some(14)).unwrap();
+        assert_eq!(p.name, "SOIC-14");
+        assert_eq!(p.body, Some((3.9, 8.65)));
+    }
+
+    #[test]
+    fn dimensions() {
+        let p = Package::parse("LQFP 100 14x14x1.4 mm", None).unwrap();
+        assert_eq!(p.family, "LQFP");
+        assert_eq!(p.body, Some((14.0, 14.0)));
+        let wide = Package::parse("SOIC (DW) | 16", None).unwrap();
+        assert_eq!(wide.body, Some((7.5, 10.3)));
+    }
+
+    #[test]
+    fn unknown_families() {
+        assert_eq!(name("Tape and reel"), None);
+        assert_eq!(name("PowerFLEX 8"), None);
+        assert_eq!(name("SOT"), None);
+        // the designator names the family
+        assert_eq!(name("X2QFN (RUM) | 16").as_deref(), Some("QFN-16"));
+    }
+
+    #[test]
+    fn footprints() {
+        let soic = |s| Package::parse(s, None).unwrap();
+        assert!(soic("SO8").compatible(&soic("SOIC (D) | 8")));
+        assert!(!soic("SO8").compatible(&soic("SOIC-14")));
+        assert!(!soic("SOIC (D) | 16").compatible(&soic("SOIC (DW) | 16")));
+        assert!(soic("LQFP 64").compatible(&soic("TQFP 64")));
+        assert!(!soic("MSOP-8").compatible(&soic("SOIC-8")));
+        // the same pitch and pins, a wider body
+        assert_eq!(soic("TSSOP-8").body, Some((4.4, 3.0)));
+        assert!(!soic("TSSOP-8").compatible(&soic("MSOP-8")));
+        assert!(soic("TSSOP-8").compatible(&soic("TSSOP (PW) | 8")));
+    }
+
+    #[test]
+    fn known_words() {
+        assert!(known("VSSOP"));
+        assert!(known("SO8"));
+        assert!(known("DGK"));
+        assert!(!known("OUT"));
+    }
+}
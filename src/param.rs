@@ -0,0 +1,502 @@
+// param turns the parametric strings of the vendor tables ("1.8 to 5.5", "10MHz", "±0.5 %")
+// into typed values with units normalised to SI, so parts can be compared across vendors
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Param {
+    pub raw: String,
+    pub value: Value,
+    // where the value comes from when it isn't the vendor's parametric table, the datasheet page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Value {
+    Number { value: f64, unit: String },
+    Range { min: f64, max: f64, unit: String },
+    Tolerance { value: f64, unit: String },
+    List { values: Vec<f64>, unit: String },
+    Enum { values: Vec<String> },
+    Empty,
+}
+
+// SI prefixes, the binary ones are only used for bits and bytes
+const PREFIXES: &[(&str, f64)] = &[
+    ("p", 1e-12),
+    ("n", 1e-9),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("μ", 1e-6),
+    ("m", 1e-3),
+    ("k", 1e3),
+    ("K", 1e3),
+    ("M", 1e6),
+    ("G", 1e9),
+    ("T", 1e12),
+];
+
+const BINARY_PREFIXES: &[(&str, f64)] = &[
+    ("k", 1024.0),
+    ("K", 1024.0),
+    ("M", 1024.0 * 1024.0),
+    ("G", 1024.0 * 1024.0 * 1024.0),
+];
+
+// spellings of a base unit and the symbol it normalises to
+const UNITS: &[(&str, &str)] = &[
+    ("V", "V"),
+    ("Vpp", "Vpp"),
+    ("Vrms", "Vrms"),
+    ("A", "A"),
+    ("W", "W"),
+    ("Hz", "Hz"),
+    ("F", "F"),
+    ("H", "H"),
+    ("s", "s"),
+    ("sec", "s"),
+    ("Ω", "Ω"),
+    ("Ohm", "Ω"),
+    ("Ohms", "Ω"),
+    ("ohm", "Ω"),
+    ("ohms", "Ω"),
+    ("SPS", "SPS"),
+    ("sps", "SPS"),
+    ("Sps", "SPS"),
+    ("bps", "bps"),
+    ("°C", "°C"),
+    ("ºC", "°C"),
+    ("degC", "°C"),
+    ("C", "°C"),
+    ("%", "%"),
+    ("ppm", "ppm"),
+    ("dB", "dB"),
+    ("dBm", "dBm"),
+    ("dBc", "dBc"),
+    ("dBFS", "dBFS"),
+    ("LSB", "LSB"),
+    ("bit", "bit"),
+    ("bits", "bit"),
+    ("Bit", "bit"),
+    ("b", "bit"),
+    ("B", "B"),
+    ("Byte", "B"),
+    ("Bytes", "B"),
+    ("byte", "B"),
+    ("bytes", "B"),
+    ("√Hz", "√Hz"),
+    ("rtHz", "√Hz"),
+    ("sqrt(Hz)", "√Hz"),
+    ("mm", "mm"),
+    ("mil", "mil"),
+    ("pin", "pin"),
+    ("pins", "pin"),
+    ("ch", "ch"),
+];
+
+impl Param {
+    // parse a raw string without any unit hint
+    pub fn parse(raw: &str) -> Param {
+        Param::parse_with_unit(raw, None)
+    }
+
+    // parse a raw string, bare numbers take the unit of the column (e.g. "MHz" from "GBW (MHz)")
+    pub fn parse_with_unit(raw: &str, unit: Option<&str>) -> Param {
+        Param {
+            raw: raw.to_string(),
+            value: parse_value(raw, unit),
+            source: None,
+        }
+    }
+
+    // TI returns numbers, strings and arrays of strings depending on the column
+    pub fn from_json(v: &serde_json::Value, unit: Option<&str>) -> Param {
+        match v {
+            serde_json::Value::String(s) => Param::parse_with_unit(s, unit),
+            serde_json::Value::Array(a) => {
+                let raw = a
+                    .iter()
+                    .map(|x| match x {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                Param::parse_with_unit(&raw, unit)
+            }
+            serde_json::Value::Null => Param {
+                raw: String::new(),
+                value: Value::Empty,
+                source: None,
+            },
+            other => Param::parse_with_unit(&other.to_string(), unit),
+        }
+    }
+}
+
+impl Value {
+    // the numeric span a value covers, used for comparisons
+    pub fn bounds(&self) -> Option<(f64, f64)> {
+        match self {
+            Value::Number { value, .. } => Some((*value, *value)),
+            Value::Range { min, max, .. } => Some((*min, *max)),
+            Value::Tolerance { value, .. } => Some((-value.abs(), value.abs())),
+            Value::List { values, .. } => {
+                let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+                let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                if values.is_empty() {
+                    None
+                } else {
+                    Some((min, max))
+                }
+            }
+            Value::Enum { .. } | Value::Empty => None,
+        }
+    }
+
+    pub fn unit(&self) -> Option<&str> {
+        match self {
+            Value::Number { unit, .. }
+            | Value::Range { unit, .. }
+            | Value::Tolerance { unit, .. }
+            | Value::List { unit, .. } => Some(unit.as_str()),
+            Value::Enum { .. } | Value::Empty => None,
+        }
+    }
+}
+
+// parse a single quantity like "10MHz", "-40 °C" or "36" into its SI value and unit
+pub fn parse_quantity(s: &str, default_unit: Option<&str>) -> Option<(f64, String)> {
+    let s = s.trim().replace('−', "-");
+    let s = s.as_str();
+    let split = number_len(s)?;
+    let number: f64 = s[..split].replace(',', "").parse().ok()?;
+    let rest = s[split..].trim();
+
+    let unit = if rest.is_empty() {
+        default_unit.unwrap_or("")
+    } else {
+        rest
+    };
+
+    if unit.is_empty() {
+        return Some((number, String::new()));
+    }
+
+    let (factor, unit) = normalise_unit(unit)?;
+    Some((number * factor, unit))
+}
+
+// extract a unit from a trailing "(unit)" in a column name, e.g. "GBW (Typ) (MHz)" -> "MHz"
+pub fn unit_from_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if !name.ends_with(')') {
+        return None;
+    }
+    let start = name.rfind('(')?;
+    let unit = name[start + 1..name.len() - 1].trim();
+    normalise_unit(unit).map(|_| unit.to_string())
+}
+
+// returns the factor to apply to the number and the normalised unit symbol
+pub fn normalise_unit(unit: &str) -> Option<(f64, String)> {
+    let unit = unit.trim();
+
+    // data rates are bps whichever way they are written, with decimal prefixes
+    for suffix in &["bit/s", "b/s"] {
+        if let Some(prefix) = unit.strip_suffix(suffix) {
+            return normalise_unit(&format!("{}bps", prefix));
+        }
+    }
+
+    // compound units like "V/us" or "nV/√Hz"
+    if let Some(pos) = unit.find('/') {
+        if !UNITS.iter().any(|(u, _)| *u == unit) {
+            let (nf, nu) = normalise_unit(&unit[..pos])?;
+            let (df, du) = normalise_unit(&unit[pos + 1..])?;
+            return Some((nf / df, format!("{}/{}", nu, du)));
+        }
+    }
+
+    if let Some((_, base)) = UNITS.iter().find(|(u, _)| *u == unit) {
+        return Some((1.0, base.to_string()));
+    }
+
+    let prefixes = |base: &str| {
+        if base == "B" || base == "bit" {
+            BINARY_PREFIXES
+        } else {
+            PREFIXES
+        }
+    };
+
+    for (u, base) in UNITS {
+        if let Some(prefix) = unit.strip_suffix(u) {
+            // "C" would otherwise turn "mC" or "kC" into degrees
+            if *base == "°C" || *base == "%" || base.starts_with("dB") {
+                continue;
+            }
+            if let Some((_, f)) = prefixes(base).iter().find(|(p, _)| *p == prefix) {
+                return Some((*f, base.to_string()));
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_value(raw: &str, unit: Option<&str>) -> Value {
+    let s = raw.trim();
+
+    if s.is_empty() || s == "-" || s == "--" || s.eq_ignore_ascii_case("n/a") {
+        return Value::Empty;
+    }
+
+    // tolerances: "±0.5 %", "+/-0.5%"
+    for prefix in &["±", "+/-", "+-"] {
+        if let Some(rest) = s.strip_prefix(prefix) {
+            if let Some((value, unit)) = parse_quantity(rest, unit) {
+                return Value::Tolerance { value, unit };
+            }
+        }
+    }
+
+    // ranges: "1.8 to 5.5", "-40 to 125", "1.8V to 5.5V", "2.7 - 3.6"
+    if let Some((lo, hi)) = s.split_once(" to ").or_else(|| s.split_once(" - ")) {
+        if let Some(v) = parse_range(lo, hi, unit) {
+            return v;
+        }
+    }
+
+    if let Some((value, unit)) = parse_quantity(s, unit) {
+        return Value::Number { value, unit };
+    }
+
+    // lists: "8, 14, 16" or "SOIC, TSSOP"
+    let items: Vec<&str> = s
+        .split([',', ';'])
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    let numbers: Vec<(f64, String)> = items
+        .iter()
+        .filter_map(|x| parse_quantity(x, unit))
+        .collect();
+
+    if items.len() > 1 && numbers.len() == items.len() {
+        let unit = numbers[0].1.clone();
+        if numbers.iter().all(|(_, u)| *u == unit) {
+            return Value::List {
+                values: numbers.into_iter().map(|(v, _)| v).collect(),
+                unit,
+            };
+        }
+    }
+
+    Value::Enum {
+        values: items.iter().map(|x| x.to_string()).collect(),
+    }
+}
+
+fn parse_range(lo: &str, hi: &str, unit: Option<&str>) -> Option<Value> {
+    let hi = hi.trim().replace('−', "-");
+    // the unit is usually only written after the upper bound
+    let (max, hi_unit) = parse_quantity(&hi, unit)?;
+    let hi_unit_raw = hi[number_len(&hi)?..].trim().to_string();
+    let lo_hint = if hi_unit_raw.is_empty() {
+        unit.map(|u| u.to_string())
+    } else {
+        Some(hi_unit_raw)
+    };
+    let (min, lo_unit) = parse_quantity(lo, lo_hint.as_deref())?;
+
+    if lo_unit != hi_unit {
+        return None;
+    }
+
+    Some(Value::Range {
+        min: min.min(max),
+        max: min.max(max),
+        unit: hi_unit,
+    })
+}
+
+// length of the leading number in s, accepting a sign, thousands separators and exponents
+fn number_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    let mut digits = 0;
+
+    if i < bytes.len() && (bytes[i] == b'-' || bytes[i] == b'+') {
+        i += 1;
+    }
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'0'..=b'9' => digits += 1,
+            b'.' => {}
+            // "1,000" is a number, "8, 14" is a list
+            b',' if digits > 0 && is_thousands(&bytes[i + 1..]) => {}
+            b'e' | b'E'
+                if digits > 0
+                    && bytes
+                        .get(i + 1)
+                        .is_some_and(|b| b.is_ascii_digit() || *b == b'-') =>
+            {
+                i += 1;
+            }
+            _ => break,
+        }
+        i += 1;
+    }
+
+    if digits == 0 {
+        None
+    } else {
+        Some(i)
+    }
+}
+
+fn is_thousands(rest: &[u8]) -> bool {
+    rest.len() >= 3
+        && rest[..3].iter().all(|b| b.is_ascii_digit())
+        && !rest.get(3).is_some_and(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(raw: &str) -> (f64, String) {
+        match Param::parse(raw).value {
+            Value::Number { value, unit } => (value, unit),
+            other => panic!("{} parsed as {:?}", raw, other),
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * a.abs().max(b.abs())
+    }
+
+    #[test]
+    fn si_prefixes() {
+        let cases = [
+            ("10MHz", 10e6, "Hz"),
+            ("4.7 nF", 4.7e-9, "F"),
+            ("100 µV", 100e-6, "V"),
+            ("100 uV", 100e-6, "V"),
+            ("2.2kOhm", 2.2e3, "Ω"),
+            ("1.2 mA", 1.2e-3, "A"),
+            ("20 V/µs", 20e6, "V/s"),
+            ("8 nV/√Hz", 8e-9, "V/√Hz"),
+        ];
+        for (raw, value, unit) in cases.iter() {
+            let (v, u) = number(raw);
+            assert!(close(v, *value), "{}: {}", raw, v);
+            assert_eq!(u, *unit, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn column_unit_for_bare_numbers() {
+        let p = Param::parse_with_unit("36", Some("MHz"));
+        assert_eq!(
+            p.value,
+            Value::Number {
+                value: 36e6,
+                unit: String::from("Hz")
+            }
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        let cases = [
+            ("1.8 to 5.5", 1.8, 5.5, ""),
+            ("1.8V to 5.5V", 1.8, 5.5, "V"),
+            ("-40 to 125 °C", -40.0, 125.0, "°C"),
+            ("2.7 - 3.6 V", 2.7, 3.6, "V"),
+        ];
+        for (raw, min, max, unit) in cases.iter() {
+            assert_eq!(
+                Param::parse(raw).value,
+                Value::Range {
+                    min: *min,
+                    max: *max,
+                    unit: unit.to_string()
+                },
+                "{}",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn unicode_minus() {
+        assert_eq!(number("−40 °C"), (-40.0, String::from("°C")));
+        assert_eq!(
+            Param::parse("−40 to −10 °C").value,
+            Value::Range {
+                min: -40.0,
+                max: -10.0,
+                unit: String::from("°C")
+            }
+        );
+    }
+
+    #[test]
+    fn tolerances() {
+        assert_eq!(
+            Param::parse("±0.5 %").value,
+            Value::Tolerance {
+                value: 0.5,
+                unit: String::from("%")
+            }
+        );
+        assert_eq!(
+            Param::parse("+/-2mV").value,
+            Value::Tolerance {
+                value: 2e-3,
+                unit: String::from("V")
+            }
+        );
+    }
+
+    #[test]
+    fn unit_aliases() {
+        assert_eq!(number("10 ohms").1, "Ω");
+        assert_eq!(number("25 degC").1, "°C");
+        assert_eq!(number("1 Msps").1, "SPS");
+        assert_eq!(number("16 bits").1, "bit");
+    }
+
+    #[test]
+    fn data_rates_are_bps() {
+        for raw in ["400 kbps", "400 kb/s", "400 kbit/s"].iter() {
+            let (v, u) = number(raw);
+            assert!(close(v, 400e3), "{}: {}", raw, v);
+            assert_eq!(u, "bps", "{}", raw);
+        }
+    }
+
+    #[test]
+    fn lists_and_enums() {
+        assert_eq!(
+            Param::parse("8, 14, 16").value,
+            Value::List {
+                values: vec![8.0, 14.0, 16.0],
+                unit: String::new()
+            }
+        );
+        assert_eq!(
+            Param::parse("SOIC, TSSOP").value,
+            Value::Enum {
+                values: vec![String::from("SOIC"), String::from("TSSOP")]
+            }
+        );
+        assert_eq!(Param::parse("n/a").value, Value::Empty);
+    }
+}
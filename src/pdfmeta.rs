@@ -0,0 +1,317 @@
+// pdfmeta reads what a PDF says about itself, the Info dictionary, the XMP packet and the text
+// of the first page, for the literature number, revision, publication date and page count, and
+// the revision history from the text
+
+use crate::document;
+use crate::history::{self, Entry};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PdfMeta {
+    pub title: Option<String>,
+    // the vendor's document number with the revision, SLOS123C or DS12345
+    pub number: Option<String>,
+    pub revision: Option<String>,
+    // YYYY-MM-DD or YYYY-MM
+    pub date: Option<String>,
+    pub pages: u32,
+    // the revision history at the end, newest first
+    #[serde(default)]
+    pub history: Vec<Entry>,
+}
+
+// ST document numbers, followed by "Rev 7"
+const ST_PREFIXES: &[&str] = &["DS", "ES", "RM", "PM", "AN", "UM", "TN", "DB"];
+
+// what the Info dictionary and the XMP packet have, metadata dates are when the file was made
+#[derive(Default, Debug)]
+struct Fields {
+    title: Option<String>,
+    subject: Option<String>,
+    keywords: Option<String>,
+    date: Option<String>,
+}
+
+pub fn read(file: &Path, pages: &[String]) -> Option<PdfMeta> {
+    let doc = lopdf::Document::load(file).ok()?;
+    let mut fields = info(&doc);
+    let xmp = xmp(&doc);
+    fields.title = xmp.title.or(fields.title);
+    fields.subject = fields.subject.or(xmp.subject);
+    fields.keywords = fields.keywords.or(xmp.keywords);
+    fields.date = xmp.date.or(fields.date);
+
+    let mut meta = PdfMeta {
+        pages: doc.get_pages().len() as u32,
+        ..PdfMeta::default()
+    };
+
+    // the first page has the number and the date the vendor gives it, the metadata fills in
+    let text = pages.first().map_or("", |p| p.as_str());
+    let described = [&fields.title, &fields.subject, &fields.keywords]
+        .iter()
+        .filter_map(|f| f.as_deref())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    if let Some((number, revision)) = number(text).or_else(|| number(&described)) {
+        meta.number = Some(number);
+        meta.revision = revision;
+    }
+    meta.revision = meta.revision.or_else(|| revision(&described));
+    meta.date = publication_date(text).or(fields.date);
+    meta.title = fields.title.filter(|t| !t.trim().is_empty());
+    meta.history = history::parse(pages);
+
+    Some(meta)
+}
+
+fn info(doc: &lopdf::Document) -> Fields {
+    let dict = match doc
+        .trailer
+        .get(b"Info")
+        .and_then(|o| doc.dereference(o))
+        .and_then(|(_, o)| o.as_dict())
+    {
+        Ok(d) => d,
+        Err(_) => return Fields::default(),
+    };
+    let text = |key: &[u8]| {
+        dict.get(key)
+            .and_then(|o| doc.dereference(o))
+            .and_then(|(_, o)| lopdf::decode_text_string(o))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    Fields {
+        title: text(b"Title"),
+        subject: text(b"Subject"),
+        keywords: text(b"Keywords"),
+        date: text(b"ModDate")
+            .or_else(|| text(b"CreationDate"))
+            .and_then(|d| pdf_date(&d)),
+    }
+}
+
+// D:20210315120000+01'00'
+fn pdf_date(s: &str) -> Option<String> {
+    let digits: String = s
+        .trim_start_matches("D:")
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    match digits.len() {
+        n if n >= 8 => Some(format!(
+            "{}-{}-{}",
+            &digits[..4],
+            &digits[4..6],
+            &digits[6..8]
+        )),
+        6 | 7 => Some(format!("{}-{}", &digits[..4], &digits[4..6])),
+        _ => None,
+    }
+}
+
+fn xmp(doc: &lopdf::Document) -> Fields {
+    let data = doc
+        .catalog()
+        .and_then(|c| c.get(b"Metadata"))
+        .and_then(|o| doc.dereference(o))
+        .and_then(|(_, o)| o.as_stream())
+        .map(|s| {
+            s.decompressed_content()
+                .unwrap_or_else(|_| s.content.clone())
+        });
+    match data {
+        Ok(data) => parse_xmp(&String::from_utf8_lossy(&data)),
+        Err(_) => Fields::default(),
+    }
+}
+
+// the fields are elements or attributes of rdf:Description, the title is in an rdf:Alt
+fn parse_xmp(xml: &str) -> Fields {
+    let start = xml.find("<x:xmpmeta").or_else(|| xml.find("<rdf:RDF"));
+    let end = xml
+        .rfind("</x:xmpmeta>")
+        .map(|i| i + "</x:xmpmeta>".len())
+        .or_else(|| xml.rfind("</rdf:RDF>").map(|i| i + "</rdf:RDF>".len()));
+    let xml = match (start, end) {
+        (Some(s), Some(e)) if s < e => &xml[s..e],
+        _ => return Fields::default(),
+    };
+    let doc = match roxmltree::Document::parse(xml) {
+        Ok(doc) => doc,
+        Err(_) => return Fields::default(),
+    };
+
+    let field = |name: &str| {
+        doc.descendants().find_map(|n| {
+            if n.tag_name().name() == name {
+                let text: String = n
+                    .descendants()
+                    .filter(|d| d.is_text())
+                    .filter_map(|d| d.text())
+                    .collect::<Vec<&str>>()
+                    .join(" ");
+                Some(text.split_whitespace().collect::<Vec<&str>>().join(" "))
+            } else {
+                n.attributes()
+                    .iter()
+                    .find(|a| a.name() == name)
+                    .map(|a| a.value().trim().to_string())
+            }
+            .filter(|s| !s.is_empty())
+        })
+    };
+
+    Fields {
+        title: field("title"),
+        subject: field("description").or_else(|| field("Subject")),
+        keywords: field("Keywords").or_else(|| field("subject")),
+        // 2021-03-15T10:00:00+01:00, the time is left out
+        date: field("ModifyDate")
+            .or_else(|| field("CreateDate"))
+            .and_then(|d| document::parse_date(d.split('T').next().unwrap_or(&d))),
+    }
+}
+
+// the document number and its revision, TI's SLOS123C carries the revision in the letters
+// after the number, ST's DS12345 is followed by "Rev 7"
+pub fn number(text: &str) -> Option<(String, Option<String>)> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    for (i, w) in words.iter().enumerate() {
+        let w = w.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+        if let Some((number, revision)) = ti_number(w) {
+            return Some((number, revision));
+        }
+        let letters: String = w.chars().take_while(|c| c.is_ascii_uppercase()).collect();
+        let digits = &w[letters.len()..];
+        if ST_PREFIXES.contains(&letters.as_str())
+            && (4..=6).contains(&digits.len())
+            && digits.chars().all(|c| c.is_ascii_digit())
+        {
+            let rest = words[i + 1..].iter().take(3).cloned().collect::<Vec<_>>();
+            return Some((w.to_string(), revision(&rest.join(" "))));
+        }
+    }
+    None
+}
+
+// S and three letters for the product line, three characters with at least two digits, then
+// the revision letters, SLOS123C or SNOSD72A
+fn ti_number(w: &str) -> Option<(String, Option<String>)> {
+    let b = w.as_bytes();
+    if b.len() < 7 || b.len() > 9 || b[0] != b'S' || !w.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    if !b[..4].iter().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let serial = &w[4..7];
+    if serial.chars().filter(|c| c.is_ascii_digit()).count() < 2
+        || !serial
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        || !serial.ends_with(|c: char| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let rev = &w[7..];
+    if !rev.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    Some((
+        w.to_string(),
+        if rev.is_empty() {
+            None
+        } else {
+            Some(rev.to_string())
+        },
+    ))
+}
+
+// "Rev 7", "Rev. C" or "Revision 3" anywhere in a text
+fn revision(text: &str) -> Option<String> {
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.windows(2).find_map(|w| {
+        let lower = w[0].to_lowercase();
+        if lower == "rev" || lower == "rev." || lower == "revision" {
+            document::parse_revision(&format!("rev {}", w[1].trim_matches([',', ';', ':'])))
+        } else {
+            None
+        }
+    })
+}
+
+// TI writes "JUNE 2010 – REVISED MARCH 2021", the revised one is the date of this revision
+fn publication_date(text: &str) -> Option<String> {
+    // ASCII only so the offsets stay the same
+    let upper = text.to_ascii_uppercase();
+    match upper.find("REVISED") {
+        Some(i) => document::parse_date(&text[i + "REVISED".len()..]),
+        None => None,
+    }
+    .or_else(|| document::parse_date(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ti_numbers() {
+        assert_eq!(
+            number("OPA2197 SLOS123D – JUNE 2010"),
+            Some((String::from("SLOS123D"), Some(String::from("D"))))
+        );
+        assert_eq!(number("SNOSD72"), Some((String::from("SNOSD72"), None)));
+        // part numbers and words aren't literature numbers
+        assert_eq!(number("SN74LVC1G14 STANDARD"), None);
+    }
+
+    #[test]
+    fn st_numbers() {
+        assert_eq!(
+            number("TSV912 DS4938 - Rev 9 - April 2019"),
+            Some((String::from("DS4938"), Some(String::from("9"))))
+        );
+        assert_eq!(
+            number("ES0392 Rev. C"),
+            Some((String::from("ES0392"), Some(String::from("C"))))
+        );
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(
+            pdf_date("D:20210315120000+01'00'").as_deref(),
+            Some("2021-03-15")
+        );
+        assert_eq!(pdf_date("D:202103").as_deref(), Some("2021-03"));
+        assert_eq!(pdf_date("yesterday"), None);
+        assert_eq!(
+            publication_date("JUNE 2010 – REVISED MARCH 2021").as_deref(),
+            Some("2021-03")
+        );
+        assert_eq!(publication_date("JUNE 2010").as_deref(), Some("2010-06"));
+    }
+
+    #[test]
+    fn xmp_fields() {
+        let xml = r#"<?xpacket begin=""?><x:xmpmeta xmlns:x="adobe:ns:meta/">
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description xmlns:dc="http://purl.org/dc/elements/1.1/"
+  xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:ModifyDate="2021-03-15T10:00:00Z">
+<dc:title><rdf:Alt><rdf:li xml:lang="x-default">OPA2197  datasheet</rdf:li></rdf:Alt></dc:title>
+</rdf:Description></rdf:RDF></x:xmpmeta><?xpacket end="w"?>"#;
+        let f = parse_xmp(xml);
+        assert_eq!(f.title.as_deref(), Some("OPA2197 datasheet"));
+        assert_eq!(f.date.as_deref(), Some("2021-03-15"));
+        assert!(parse_xmp("no packet").title.is_none());
+    }
+}
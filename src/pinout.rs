@@ -0,0 +1,567 @@
+// pinout finds the pin tables of the datasheets ("Pin Configuration and Functions", "Pin
+// description") in the text of the PDF index, one table per package column, rows that didn't
+// parse cleanly are kept with the reason so someone can check them, in json/{vendor}/pins.json
+
+use crate::db::open_json;
+use crate::fulltext::{self, Index};
+use crate::history;
+use crate::package;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum PinType {
+    Input,
+    Output,
+    Bidirectional,
+    Power,
+    Ground,
+    Passive,
+    NoConnect,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Pin {
+    // "3", or "A1" on a BGA
+    pub number: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: Option<PinType>,
+    pub description: String,
+    // why the row needs a look, none if it parsed cleanly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uncertain: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Pinout {
+    pub vendor: String,
+    pub part: String,
+    // the package column of the table, "D, DGK" when several share the numbers
+    pub package: Option<String>,
+    // the PDF below the archive root and the page the table starts on
+    pub source: String,
+    pub page: u32,
+    pub pins: Vec<Pin>,
+    // numbers up to the highest one that no row has
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<u32>,
+}
+
+const HEADINGS: &[&str] = &[
+    "pin configuration and functions",
+    "pin functions",
+    "pin descriptions",
+    "pin description",
+    "pinout and pin description",
+    "pin definitions",
+];
+
+// the type column, TI writes I, O, I/O, P and G, ST S for supply
+const TYPES: &[(&str, PinType)] = &[
+    ("I", PinType::Input),
+    ("AI", PinType::Input),
+    ("O", PinType::Output),
+    ("AO", PinType::Output),
+    ("I/O", PinType::Bidirectional),
+    ("IO", PinType::Bidirectional),
+    ("B", PinType::Bidirectional),
+    ("P", PinType::Power),
+    ("PWR", PinType::Power),
+    ("S", PinType::Power),
+    ("G", PinType::Ground),
+    ("A", PinType::Passive),
+];
+
+// pin names that say what they are when the table has no type column
+const NAMES: &[(&str, PinType)] = &[
+    ("NC", PinType::NoConnect),
+    ("DNC", PinType::NoConnect),
+    ("GND", PinType::Ground),
+    ("VSS", PinType::Ground),
+    ("AGND", PinType::Ground),
+    ("DGND", PinType::Ground),
+    ("VCC", PinType::Power),
+    ("VDD", PinType::Power),
+    ("VS", PinType::Power),
+    ("V+", PinType::Power),
+    ("V-", PinType::Power),
+    ("V–", PinType::Power),
+    ("VBAT", PinType::Power),
+    ("VIN", PinType::Power),
+    ("OUT", PinType::Output),
+    ("IN", PinType::Input),
+];
+
+// page headers and footers in the middle of a table
+const NOISE: &[&str] = &[
+    "copyright",
+    "submit document feedback",
+    "product folder links",
+    "www.",
+];
+
+// a table this many pages after its heading is someone else's
+const PAGES: usize = 2;
+
+impl PinType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PinType::Input => "input",
+            PinType::Output => "output",
+            PinType::Bidirectional => "bidirectional",
+            PinType::Power => "power",
+            PinType::Ground => "ground",
+            PinType::Passive => "passive",
+            PinType::NoConnect => "no_connect",
+        }
+    }
+
+    // from the name when the table doesn't say, IN1+ is an input and VCC power
+    pub fn infer(name: &str) -> Option<PinType> {
+        let upper = name.to_uppercase();
+        if let Some((_, t)) = NAMES.iter().find(|(n, _)| *n == upper) {
+            return Some(*t);
+        }
+        let letters: String = upper
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        match letters.as_str() {
+            "OUT" | "VOUT" => Some(PinType::Output),
+            "IN" | "VIN" => Some(PinType::Input),
+            _ if upper.starts_with("+IN") || upper.starts_with("-IN") => Some(PinType::Input),
+            _ if upper.starts_with("VCC") || upper.starts_with("VDD") => Some(PinType::Power),
+            _ if upper.starts_with("GND") || upper.starts_with("VSS") => Some(PinType::Ground),
+            _ => None,
+        }
+    }
+}
+
+// a row before it is split into the packages, numbers has one cell per package column
+struct Row {
+    name: String,
+    numbers: Vec<Vec<String>>,
+    kind: Option<PinType>,
+    description: String,
+    uncertain: Option<String>,
+}
+
+// the header of the table, the package columns and whether the number comes before the name
+struct Header {
+    packages: Vec<String>,
+    number_first: bool,
+}
+
+// the pin numbers of a cell, "4, 11" for several and "—" for a package without the pin
+fn pin_numbers(cell: &str) -> Option<Vec<String>> {
+    if ["—", "–", "-", "N/A"].contains(&cell) {
+        return Some(Vec::new());
+    }
+    let mut out = Vec::new();
+    for n in cell.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let letters = n.chars().take_while(|c| c.is_ascii_uppercase()).count();
+        let digits = &n[letters..];
+        let ok = letters <= 2
+            && (1..=4).contains(&digits.len())
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && (letters == 0 || digits.len() <= 2);
+        if !ok {
+            return None;
+        }
+        out.push(n.to_string());
+    }
+    if out.is_empty() {
+        None
+    } else {
+        Some(out)
+    }
+}
+
+// pin names are short, have an upper case letter and no spaces
+fn plausible_name(name: &str) -> bool {
+    name.chars().count() <= 20
+        && name.chars().any(|c| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "+-–_/()#.!".contains(c))
+}
+
+// "Output" is a word and not a name, names are upper case or short
+fn wordlike(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.chars().count() > 3
+        && chars.next().is_some_and(|c| c.is_uppercase())
+        && chars.all(|c| c.is_lowercase())
+}
+
+fn header(lines: &[&str], at: usize) -> Option<Header> {
+    let words: Vec<String> = lines[at]
+        .split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect();
+    let name = words.iter().position(|w| w.trim_end_matches(':') == "name")?;
+    if !words.iter().any(|w| w.starts_with("description") || w == "function")
+        && !words.iter().any(|w| w == "type" || w == "i/o")
+    {
+        return None;
+    }
+
+    // the package columns are in this line or the one or two above it, "D, DGK RGT" is two
+    let mut packages: Vec<String> = Vec::new();
+    let mut first_package: Option<usize> = None;
+    let mut joined = false;
+    for (i, line) in lines[at.saturating_sub(2)..=at].iter().enumerate() {
+        for (j, w) in line.split_whitespace().enumerate() {
+            let word = w.trim_end_matches(',');
+            // the columns are upper case, "n°" isn't the N package
+            let upper = word
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-');
+            if !upper || !package::known(word) || word == "NAME" {
+                joined = false;
+                continue;
+            }
+            if i == at.min(2) && first_package.is_none() {
+                first_package = Some(j);
+            }
+            match packages.last_mut() {
+                Some(last) if joined => {
+                    last.push_str(", ");
+                    last.push_str(word);
+                }
+                _ => packages.push(word.to_string()),
+            }
+            joined = w.ends_with(',');
+        }
+        joined = false;
+    }
+
+    let number = words
+        .iter()
+        .position(|w| ["no", "no.", "n°", "number", "#", "pin#", "nr."].contains(&w.as_str()));
+    let number_first = match (number, first_package) {
+        (Some(n), _) => n < name,
+        (None, Some(p)) => p < name,
+        _ => false,
+    };
+    Some(Header {
+        packages,
+        number_first,
+    })
+}
+
+// one table row, the name first (TI) or the number first (ST)
+fn row(line: &str, h: &Header) -> Option<Row> {
+    let columns = h.packages.len().max(1);
+    // "4, 11" is one cell
+    let mut tokens: Vec<String> = Vec::new();
+    for t in line.split_whitespace() {
+        match tokens.last_mut() {
+            Some(last) if last.ends_with(',') && pin_numbers(t).is_some() => last.push_str(t),
+            _ => tokens.push(t.to_string()),
+        }
+    }
+
+    let mut i = 0;
+    let mut name = None;
+    if !h.number_first {
+        name = Some(tokens.first()?.clone());
+        i = 1;
+    }
+    let mut numbers = Vec::new();
+    while numbers.len() < columns {
+        match tokens
+            .get(i)
+            .and_then(|t| pin_numbers(t.trim_end_matches(',')))
+        {
+            Some(n) => numbers.push(n),
+            None => break,
+        }
+        i += 1;
+    }
+    if numbers.is_empty() || numbers.iter().all(|n| n.is_empty()) {
+        return None;
+    }
+    if h.number_first {
+        name = Some(tokens.get(i)?.clone());
+        i += 1;
+    }
+    let name = name?;
+    if !plausible_name(&name) {
+        return None;
+    }
+
+    let table_kind = tokens
+        .get(i)
+        .and_then(|t| TYPES.iter().find(|(x, _)| x == t).map(|(_, k)| *k));
+    if table_kind.is_some() || tokens.get(i).is_some_and(|t| t == "—" || t == "-") {
+        i += 1;
+    }
+    let description = tokens[i.min(tokens.len())..].join(" ");
+
+    let uncertain = if numbers.len() < columns {
+        Some(format!(
+            "pin numbers for {} of {} packages",
+            numbers.len(),
+            columns
+        ))
+    } else if wordlike(&name) {
+        Some(String::from("the name looks like a word"))
+    } else if description.is_empty() {
+        Some(String::from("no description"))
+    } else {
+        None
+    };
+
+    Some(Row {
+        kind: table_kind.or_else(|| PinType::infer(&name)),
+        name,
+        numbers,
+        description,
+        uncertain,
+    })
+}
+
+// the rows of the table whose header is at lines[at], up to the next heading
+fn rows(lines: &[&str], at: usize, h: &Header) -> Vec<Row> {
+    let mut out: Vec<Row> = Vec::new();
+    for line in &lines[at + 1..] {
+        let lower = line.to_lowercase();
+        if NOISE.iter().any(|n| lower.contains(n)) {
+            continue;
+        }
+        // the header again on the next page
+        if header(&[line], 0).is_some() {
+            continue;
+        }
+        let caption = lower.starts_with("table ") || lower.starts_with("figure ");
+        let end = history::heading(line) || caption || line.starts_with("(1)");
+        match row(line, h) {
+            // "3 Absolute maximum ratings" looks like a row of an ST table
+            Some(r) if !(end && wordlike(&r.name)) => out.push(r),
+            _ if end => {
+                if out.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            // a description that goes on
+            _ => {
+                if let Some(last) = out.last_mut() {
+                    if !last.description.is_empty() {
+                        last.description.push(' ');
+                    }
+                    last.description.push_str(line);
+                    if last.uncertain.as_deref() == Some("no description") {
+                        last.uncertain = None;
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+// the first pin table after one of the headings, split by package
+pub fn parse(vendor: &str, part: &str, source: &str, pages: &[String]) -> Vec<Pinout> {
+    for (n, page) in pages.iter().enumerate() {
+        let lower = page.to_lowercase();
+        if !HEADINGS.iter().any(|h| lower.contains(h)) {
+            continue;
+        }
+        let text = pages[n..]
+            .iter()
+            .take(PAGES + 1)
+            .map(|p| p.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        for at in 0..lines.len() {
+            let h = match header(&lines, at) {
+                Some(h) => h,
+                None => continue,
+            };
+            let rows = rows(&lines, at, &h);
+            if rows.len() < 2 {
+                continue;
+            }
+            return split(vendor, part, source, n as u32 + 1, &h, rows);
+        }
+    }
+    Vec::new()
+}
+
+fn split(
+    vendor: &str,
+    part: &str,
+    source: &str,
+    page: u32,
+    h: &Header,
+    rows: Vec<Row>,
+) -> Vec<Pinout> {
+    let packages: Vec<Option<String>> = if h.packages.is_empty() {
+        vec![None]
+    } else {
+        h.packages.iter().cloned().map(Some).collect()
+    };
+
+    let mut out = Vec::new();
+    for (column, package) in packages.into_iter().enumerate() {
+        let mut pins: Vec<Pin> = Vec::new();
+        for r in &rows {
+            for number in r.numbers.get(column).into_iter().flatten() {
+                pins.push(Pin {
+                    number: number.clone(),
+                    name: r.name.clone(),
+                    kind: r.kind,
+                    description: r.description.clone(),
+                    uncertain: r.uncertain.clone(),
+                });
+            }
+        }
+
+        // a number on two rows means the columns got mixed up
+        let mut count: BTreeMap<String, usize> = BTreeMap::new();
+        for p in &pins {
+            *count.entry(p.number.clone()).or_default() += 1;
+        }
+        for p in pins.iter_mut().filter(|p| count[&p.number] > 1) {
+            p.uncertain = Some(String::from("the number is on more than one row"));
+        }
+
+        let numbers: BTreeSet<u32> = pins.iter().filter_map(|p| p.number.parse().ok()).collect();
+        let missing = match numbers.iter().next_back() {
+            Some(max) => (1..=*max).filter(|n| !numbers.contains(n)).collect(),
+            None => Vec::new(),
+        };
+
+        if !pins.is_empty() {
+            out.push(Pinout {
+                vendor: vendor.to_string(),
+                part: part.to_string(),
+                package,
+                source: source.to_string(),
+                page,
+                pins,
+                missing,
+            });
+        }
+    }
+    out
+}
+
+pub fn load(root: &Path, vendor: &str) -> Vec<Pinout> {
+    open_json(&root.join(format!("json/{}/pins.json", vendor))).unwrap_or_default()
+}
+
+// extract the pin tables of the datasheets indexed in this run again and drop those of the
+// removed ones, a vendor without pins.json gets every indexed datasheet
+pub fn update(root: &Path, index: &Index, changed: &[String], removed: &[String]) {
+    for vendor in ["ti", "st"].iter() {
+        let file = root.join(format!("json/{}/pins.json", vendor));
+        let redo = match fulltext::datasheets(index, vendor, !file.exists(), changed, removed) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        let mut pins: Vec<Pinout> = load(root, vendor)
+            .into_iter()
+            .filter(|p| !changed.contains(&p.source) && !removed.contains(&p.source))
+            .collect();
+        for key in redo {
+            let (_, part) = fulltext::datasheet_part(key).unwrap();
+            pins.extend(parse(vendor, &part, key, &index.pages(key)));
+        }
+        pins.sort_by(|a, b| (&a.part, &a.package).cmp(&(&b.part, &b.package)));
+        // only the PDFs of a vendor may have been downloaded so far
+        fs::create_dir_all(file.parent().unwrap()).expect("couldn't create the vendor directory");
+        crate::dump_json(file.to_str().unwrap(), pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TI: &str = "5 Pin Configuration and Functions\nTable 5-1. Pin Functions\nPIN\n\
+        NAME D, DGK RGT TYPE DESCRIPTION\nIN1– 2 1 I Inverting input, channel 1\n\
+        IN1+ 3 2 I Noninverting input, channel 1\nNC — 4, 9 — No internal connection\n\
+        OUT1 1 10 O Output, channel 1\nV+ 8 5 P Positive (highest) power\nsupply\n\
+        (1) I = input, O = output, P = power.\n6 Specifications";
+
+    const ST: &str = "2 Pin description\nTable 1. Pin description\nPin n° Pin name Description\n\
+        1 OUT1 Output channel 1\n2 IN1- Inverting input channel 1\n\
+        4 VCC- Negative supply voltage\n3 Absolute maximum ratings";
+
+    fn tables(vendor: &str, text: &str) -> Vec<Pinout> {
+        parse(vendor, "X", "x.pdf", &[String::new(), text.to_string()])
+    }
+
+    #[test]
+    fn a_table_per_package_column() {
+        let t = tables("ti", TI);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].package.as_deref(), Some("D, DGK"));
+        assert_eq!(t[0].page, 2);
+        let numbers: Vec<&str> = t[0].pins.iter().map(|p| p.number.as_str()).collect();
+        assert_eq!(numbers, vec!["2", "3", "1", "8"]);
+        let nc: Vec<&str> = t[1]
+            .pins
+            .iter()
+            .filter(|p| p.name == "NC")
+            .map(|p| p.number.as_str())
+            .collect();
+        assert_eq!(nc, vec!["4", "9"]);
+    }
+
+    #[test]
+    fn types_and_wrapped_descriptions() {
+        let t = tables("ti", TI);
+        let v = t[0].pins.iter().find(|p| p.name == "V+").unwrap();
+        assert_eq!(v.kind, Some(PinType::Power));
+        assert_eq!(v.description, "Positive (highest) power supply");
+        assert_eq!(t[0].pins[0].kind, Some(PinType::Input));
+        assert!(t[0].pins.iter().all(|p| p.uncertain.is_none()));
+    }
+
+    #[test]
+    fn number_first_with_a_gap() {
+        let t = tables("st", ST);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t[0].package, None);
+        let names: Vec<&str> = t[0].pins.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["OUT1", "IN1-", "VCC-"]);
+        assert_eq!(t[0].missing, vec![3]);
+        assert_eq!(t[0].pins[0].kind, Some(PinType::Output));
+    }
+
+    #[test]
+    fn inferred_types() {
+        assert_eq!(PinType::infer("IN1+"), Some(PinType::Input));
+        assert_eq!(PinType::infer("+IN"), Some(PinType::Input));
+        assert_eq!(PinType::infer("VOUT"), Some(PinType::Output));
+        assert_eq!(PinType::infer("VDDA"), Some(PinType::Power));
+        assert_eq!(PinType::infer("GND"), Some(PinType::Ground));
+        assert_eq!(PinType::infer("EN"), None);
+    }
+
+    #[test]
+    fn number_cells() {
+        assert_eq!(
+            pin_numbers("4, 11"),
+            Some(vec![String::from("4"), String::from("11")])
+        );
+        assert_eq!(pin_numbers("A1"), Some(vec![String::from("A1")]));
+        assert_eq!(pin_numbers("—"), Some(Vec::new()));
+        assert_eq!(pin_numbers("Output"), None);
+    }
+}
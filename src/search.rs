@@ -0,0 +1,470 @@
+// search implements the filter expressions of `llh search`, e.g. `gbw>10MHz package~SOIC`
+
+use crate::category;
+use crate::db::{Database, Part};
+use crate::package::Package;
+use crate::param::{self, Param, Value};
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Like,
+}
+
+#[derive(Clone, Debug)]
+pub struct Filter {
+    pub field: String,
+    pub op: Op,
+    pub value: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+pub struct ParseError(pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid filter: {}", self.0)
+    }
+}
+
+// operators in the order they have to be tried, two character ones first
+const OPS: &[(&str, Op)] = &[
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    ("!=", Op::Ne),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+    ("=", Op::Eq),
+    ("~", Op::Like),
+];
+
+impl Filter {
+    pub fn parse(expr: &str) -> Result<Filter, ParseError> {
+        let pos = expr
+            .find(|c| "<>=!~".contains(c))
+            .ok_or_else(|| ParseError(expr.to_string()))?;
+        let field = expr[..pos].trim().to_lowercase();
+        let rest = &expr[pos..];
+
+        let (op_str, op) = OPS
+            .iter()
+            .find(|(s, _)| rest.starts_with(s))
+            .ok_or_else(|| ParseError(expr.to_string()))?;
+
+        if field.is_empty() {
+            return Err(ParseError(expr.to_string()));
+        }
+
+        let value = rest[op_str.len()..].trim().trim_matches('"').to_string();
+
+        Ok(Filter {
+            field,
+            op: *op,
+            value,
+        })
+    }
+
+    pub fn matches(&self, part: &Part) -> bool {
+        // a category filter matches the vendor's name as well as the unified one and the other
+        // categories that list the part, != none of them
+        if self.field == "category" {
+            let names = [part.category.as_deref(), part.unified_category.as_deref()];
+            let also = part
+                .also
+                .iter()
+                .filter_map(|p| p.last().map(|c| c.as_str()));
+            let mut names = names.iter().flatten().copied().chain(also);
+            return match self.op {
+                Op::Ne => names.all(|c| self.matches_text(c)),
+                _ => names.any(|c| self.matches_text(c)),
+            };
+        }
+        if self.field == "path" || self.field == "category_path" {
+            let mut paths = std::iter::once(&part.category_path)
+                .chain(&part.also)
+                .filter(|p| !p.is_empty())
+                .map(|p| category::join(p));
+            return match self.op {
+                Op::Ne => paths.all(|p| self.matches_text(&p)),
+                _ => paths.any(|p| self.matches_text(&p)),
+            };
+        }
+
+        // package filters hold if any package of the part matches, != if none does, parts
+        // without known packages fall back to their parameters
+        if PACKAGE_FIELDS.contains(&self.field.as_str()) {
+            let fields: Vec<Field> = part
+                .packages()
+                .iter()
+                .flat_map(|p| package_fields(p, &self.field))
+                .collect();
+            if !fields.is_empty() {
+                return match self.op {
+                    Op::Ne => fields.iter().all(|f| self.matches_field(f)),
+                    _ => fields.iter().any(|f| self.matches_field(f)),
+                };
+            }
+        }
+
+        match field(part, &self.field) {
+            None => false,
+            Some(f) => self.matches_field(&f),
+        }
+    }
+
+    fn matches_field(&self, f: &Field) -> bool {
+        match f {
+            Field::Text(s) => self.matches_text(s),
+            Field::Param(v, raw, unit) => self.matches_value(v, raw, unit.as_deref()),
+        }
+    }
+
+    fn matches_text(&self, s: &str) -> bool {
+        let eq = s.eq_ignore_ascii_case(&self.value);
+        match self.op {
+            Op::Eq => eq,
+            Op::Ne => !eq,
+            Op::Like => s.to_lowercase().contains(&self.value.to_lowercase()),
+            Op::Gt => s > self.value.as_str(),
+            Op::Ge => s >= self.value.as_str(),
+            Op::Lt => s < self.value.as_str(),
+            Op::Le => s <= self.value.as_str(),
+        }
+    }
+
+    // bare numbers in the filter are taken in the unit of the column, "gbw>10" means 10 MHz
+    fn matches_value(&self, v: &Value, raw: &str, column_unit: Option<&str>) -> bool {
+        if self.op == Op::Like {
+            return raw.to_lowercase().contains(&self.value.to_lowercase());
+        }
+
+        // ±5 % is 5 % off at most, accuracy<=0.1% doesn't hold for it
+        let bounds = match v {
+            Value::Tolerance { value, .. } => Some((value.abs(), value.abs())),
+            _ => v.bounds(),
+        };
+        let wanted = param::parse_quantity(&self.value, column_unit);
+
+        let (min, max, n) = match (bounds, wanted) {
+            // the unit of the filter has to agree with the parameter, unless either has none
+            (Some((min, max)), Some((n, unit)))
+                if unit.is_empty() || v.unit().is_none_or(|u| u.is_empty() || u == unit) =>
+            {
+                (min, max, n)
+            }
+            _ => {
+                let eq = match v {
+                    Value::Enum { values } => {
+                        values.iter().any(|x| x.eq_ignore_ascii_case(&self.value))
+                    }
+                    _ => raw.eq_ignore_ascii_case(&self.value),
+                };
+                return match self.op {
+                    Op::Eq => eq,
+                    Op::Ne => !eq,
+                    _ => false,
+                };
+            }
+        };
+
+        // ranges match if any value inside them satisfies the comparison
+        match self.op {
+            Op::Gt => max > n,
+            Op::Ge => max >= n,
+            Op::Lt => min < n,
+            Op::Le => min <= n,
+            Op::Eq => min <= n && n <= max,
+            Op::Ne => !(min <= n && n <= max),
+            Op::Like => unreachable!(),
+        }
+    }
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "table" => Some(Format::Table),
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+enum Field {
+    Text(String),
+    Param(Value, String, Option<String>),
+}
+
+// fields taken from the normalised packages, pkg=SOIC matches the family, pkg=SOIC-8 the name
+const PACKAGE_FIELDS: &[&str] = &["pkg", "pins", "pitch"];
+
+fn package_fields(p: &Package, name: &str) -> Vec<Field> {
+    match name {
+        "pkg" => vec![Field::Text(p.name.clone()), Field::Text(p.family.clone())],
+        "pins" => p
+            .pins
+            .map(|n| {
+                let raw = n.to_string();
+                Field::Param(Param::parse(&raw).value, raw, None)
+            })
+            .into_iter()
+            .collect(),
+        "pitch" => p
+            .pitch
+            .map(|x| {
+                let raw = format!("{} mm", x);
+                Field::Param(Param::parse(&raw).value, raw, Some(String::from("mm")))
+            })
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn field(part: &Part, name: &str) -> Option<Field> {
+    match name {
+        "mpn" | "part" => Some(Field::Text(part.mpn.clone())),
+        "vendor" => Some(Field::Text(part.vendor.clone())),
+        "category" => part.category.clone().map(Field::Text),
+        "unified" | "unified_category" => part.unified_category.clone().map(Field::Text),
+        "path" | "category_path" if !part.category_path.is_empty() => {
+            Some(Field::Text(category::join(&part.category_path)))
+        }
+        "pkg" if !part.packages().is_empty() => Some(Field::Text(
+            part.packages()
+                .iter()
+                .map(|p| p.name.clone())
+                .collect::<Vec<String>>()
+                .join(", "),
+        )),
+        _ => part
+            .param(name)
+            .map(|(n, p)| Field::Param(p.value.clone(), p.raw.clone(), param::unit_from_name(n))),
+    }
+}
+
+// the text shown for a column of a part
+pub fn column(part: &Part, name: &str) -> String {
+    match field(part, name) {
+        None => String::new(),
+        Some(Field::Text(s)) => s,
+        Some(Field::Param(_, raw, _)) => raw,
+    }
+}
+
+// split a query string into filter expressions, double quotes keep spaces together
+pub fn split_query(q: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+
+    for c in q.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                cur.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !cur.is_empty() {
+                    out.push(std::mem::take(&mut cur));
+                }
+            }
+            c => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        out.push(cur);
+    }
+
+    out
+}
+
+pub fn search<'a>(db: &'a Database, filters: &[Filter]) -> Vec<&'a Part> {
+    db.parts
+        .iter()
+        .filter(|p| filters.iter().all(|f| f.matches(p)))
+        .collect()
+}
+
+// sort by a column, a leading '-' sorts descending, parts without the column go last
+pub fn sort(parts: &mut [&Part], key: &str) {
+    let (key, desc) = match key.strip_prefix('-') {
+        Some(k) => (k, true),
+        None => (key, false),
+    };
+
+    parts.sort_by(|a, b| {
+        let (fa, fb) = (field(a, key), field(b, key));
+        let ord = match (&fa, &fb) {
+            (None, None) => return Ordering::Equal,
+            (None, _) => return Ordering::Greater,
+            (_, None) => return Ordering::Less,
+            (Some(Field::Param(va, ra, _)), Some(Field::Param(vb, rb, _))) => {
+                match (va.bounds(), vb.bounds()) {
+                    (Some(x), Some(y)) => x.0.partial_cmp(&y.0).unwrap_or(Ordering::Equal),
+                    _ => ra.cmp(rb),
+                }
+            }
+            _ => column(a, key).cmp(&column(b, key)),
+        };
+        if desc {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+}
+
+pub fn render(parts: &[&Part], columns: &[String], format: Format) -> String {
+    let rows: Vec<Vec<String>> = parts
+        .iter()
+        .map(|p| columns.iter().map(|c| column(p, c)).collect())
+        .collect();
+
+    render_rows(columns, rows, format)
+}
+
+// render rows of text cells, JSON output is a list of objects keyed by column
+pub fn render_rows(columns: &[String], rows: Vec<Vec<String>>, format: Format) -> String {
+    match format {
+        Format::Table => render_table(columns, &rows),
+        Format::Csv => render_csv(columns, &rows),
+        Format::Json => {
+            let v: Vec<serde_json::Map<String, serde_json::Value>> = rows
+                .into_iter()
+                .map(|r| {
+                    columns
+                        .iter()
+                        .cloned()
+                        .zip(r.into_iter().map(serde_json::Value::String))
+                        .collect()
+                })
+                .collect();
+            serde_json::to_string_pretty(&v).unwrap()
+        }
+    }
+}
+
+fn render_table(columns: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{:<width$}", c, width = w))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = vec![line(columns)];
+    out.extend(rows.iter().map(|r| line(r)));
+    out.join("\n")
+}
+
+fn csv_line(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|c| {
+            if c.contains([',', '"', '\n']) {
+                format!("\"{}\"", c.replace('"', "\"\""))
+            } else {
+                c.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn render_csv(columns: &[String], rows: &[Vec<String>]) -> String {
+    let mut out = vec![csv_line(columns)];
+    out.extend(rows.iter().map(|r| csv_line(r)));
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(filter: &str, raw: &str) -> bool {
+        let p = Param::parse(raw);
+        Filter::parse(filter)
+            .unwrap()
+            .matches_value(&p.value, &p.raw, None)
+    }
+
+    #[test]
+    fn operators() {
+        let f = Filter::parse("GBW >= 10MHz").unwrap();
+        assert_eq!(
+            (f.field.as_str(), f.op, f.value.as_str()),
+            ("gbw", Op::Ge, "10MHz")
+        );
+        let f = Filter::parse("package~\"SOIC 8\"").unwrap();
+        assert_eq!((f.op, f.value.as_str()), (Op::Like, "SOIC 8"));
+        assert_eq!(Filter::parse("vs!=5V").unwrap().op, Op::Ne);
+        assert!(Filter::parse("gbw").is_err());
+        assert!(Filter::parse(">10MHz").is_err());
+    }
+
+    #[test]
+    fn quoted_queries() {
+        assert_eq!(
+            split_query("gbw>10MHz  \"category=Op amps\" pkg=SOIC-8"),
+            vec!["gbw>10MHz", "\"category=Op amps\"", "pkg=SOIC-8"]
+        );
+    }
+
+    #[test]
+    fn units_and_ranges() {
+        assert!(matches("gbw>10MHz", "20 MHz"));
+        assert!(!matches("gbw>10MHz", "8 MHz"));
+        assert!(matches("gbw>10MHz", "0.02 GHz"));
+        // a different unit doesn't compare
+        assert!(!matches("gbw>10MHz", "20 V"));
+        // a range matches if a value inside it does
+        assert!(matches("vs>=36V", "4.5 to 36 V"));
+        assert!(matches("vs=5V", "4.5 to 36 V"));
+        assert!(!matches("vs<3V", "4.5 to 36 V"));
+    }
+
+    #[test]
+    fn bare_numbers_take_the_column_unit() {
+        let f = Filter::parse("gbw>10").unwrap();
+        let p = Param::parse_with_unit("20", Some("MHz"));
+        assert!(f.matches_value(&p.value, &p.raw, Some("MHz")));
+        let p = Param::parse_with_unit("5", Some("MHz"));
+        assert!(!f.matches_value(&p.value, &p.raw, Some("MHz")));
+    }
+
+    #[test]
+    fn tolerances_compare_by_magnitude() {
+        assert!(!matches("accuracy<=0.1%", "±5 %"));
+        assert!(matches("accuracy<=0.1%", "±0.05 %"));
+        assert!(matches("accuracy>1%", "±5 %"));
+        assert!(!matches("accuracy>10%", "±5 %"));
+        assert!(matches("accuracy=5%", "±5 %"));
+    }
+}
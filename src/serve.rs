@@ -0,0 +1,746 @@
+// serve exposes the local archive over HTTP, as HTML pages for browsing and JSON under /api/
+
+use crate::category::{self, Category};
+use crate::db::{Database, Part};
+use crate::document::{self, Document, DocumentKind};
+use crate::fulltext::Index;
+use crate::search::{self, Filter};
+use crate::taxonomy;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+struct State {
+    root: PathBuf,
+    db: Database,
+    index: Index,
+    documents: Vec<Document>,
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;max-width:70em}\
+table{border-collapse:collapse}td,th{border-bottom:1px solid #ddd;padding:.2em .6em;text-align:left}\
+input[type=text]{width:30em}.snippet{color:#555}";
+
+const UNCATEGORISED: &str = "Uncategorised";
+
+// parts per response or page when nothing else is asked for, an empty query matches them all
+const LIMIT: usize = 100;
+
+pub async fn run(addr: SocketAddr, root: &Path) -> Result<(), hyper::Error> {
+    println!("Loading database...");
+    let state = Arc::new(State {
+        root: root.to_path_buf(),
+        db: Database::load(root),
+        index: Index::open(root),
+        documents: ["ti", "st"]
+            .iter()
+            .flat_map(|v| document::load(root, v))
+            .collect(),
+    });
+    println!(
+        "Serving {} parts and {} indexed documents on http://{}",
+        state.db.parts.len(),
+        state.index.docs().len(),
+        addr
+    );
+
+    let make_svc = make_service_fn(move |_| {
+        let state = Arc::clone(&state);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let state = Arc::clone(&state);
+                async move { Ok::<_, Infallible>(handle(&state, req).await) }
+            }))
+        }
+    });
+
+    Server::bind(&addr).serve(make_svc).await
+}
+
+async fn handle(state: &State, req: Request<Body>) -> Response<Body> {
+    if req.method() != Method::GET {
+        return status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    let path = percent_decode(req.uri().path());
+    let query = query(req.uri().query());
+    let q = query.get("q").map(|s| s.as_str()).unwrap_or("");
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match segments.as_slice() {
+        [""] => html("llh", &page_index(state)),
+        ["category"] => match query.get("vendor") {
+            Some(vendor) => {
+                let path = category::parse(query.get("path").map(|s| s.as_str()).unwrap_or(""));
+                match state.db.categories.get(vendor).and_then(|t| t.find(&path)) {
+                    Some(c) => html(&c.name, &page_tree(state, vendor, c)),
+                    None => status(StatusCode::NOT_FOUND),
+                }
+            }
+            None => {
+                let name = query.get("name").map(|s| s.as_str()).unwrap_or("");
+                html(name, &page_category(state, name))
+            }
+        },
+        ["part", vendor, mpn] => match find(state, vendor, mpn) {
+            Some(p) => html(&p.mpn, &page_part(state, p)),
+            None => status(StatusCode::NOT_FOUND),
+        },
+        ["search"] => html("Search", &page_search(state, q)),
+        ["grep"] => html("Full-text search", &page_grep(state, q)),
+        ["pdf", ..] | ["files", ..] | ["models", ..] | ["packs", ..] => file(state, &path).await,
+        ["api", "categories"] => json(&categories(state)),
+        ["api", "tree"] => json(&state.db.categories),
+        ["api", "parts"] => match (filters(q), window(&query)) {
+            (Ok(f), Ok((offset, limit))) => {
+                let parts = search::search(&state.db, &f);
+                json(&parts.iter().skip(offset).take(limit).collect::<Vec<_>>())
+            }
+            (Err(why), _) | (_, Err(why)) => text(StatusCode::BAD_REQUEST, &why),
+        },
+        ["api", "part", vendor, mpn] => match find(state, vendor, mpn) {
+            Some(p) => json(p),
+            None => status(StatusCode::NOT_FOUND),
+        },
+        ["api", "docs"] => {
+            let kind = query.get("kind").map(|k| k.parse::<DocumentKind>());
+            match kind {
+                Some(Err(why)) => text(StatusCode::BAD_REQUEST, &why),
+                _ => {
+                    let kind = kind.and_then(|k| k.ok());
+                    let part = query.get("part");
+                    let docs: Vec<&Document> = state
+                        .documents
+                        .iter()
+                        .filter(|d| kind.is_none_or(|k| d.kind == k))
+                        .filter(|d| {
+                            part.is_none_or(|p| d.parts.iter().any(|x| x.eq_ignore_ascii_case(p)))
+                        })
+                        .collect();
+                    json(&docs)
+                }
+            }
+        }
+        ["api", "models", vendor, mpn] => match find(state, vendor, mpn) {
+            Some(p) => json(&p.models),
+            None => status(StatusCode::NOT_FOUND),
+        },
+        ["api", "packs", vendor, mpn] => match find(state, vendor, mpn) {
+            Some(p) => json(&p.packs),
+            None => status(StatusCode::NOT_FOUND),
+        },
+        ["api", "cad", vendor, mpn] => match find(state, vendor, mpn) {
+            Some(p) => json(&p.cad),
+            None => status(StatusCode::NOT_FOUND),
+        },
+        ["api", "grep"] => json(&state.index.search(
+            q,
+            query.get("prefix").map(|s| s.as_str()),
+            100,
+        )),
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+fn find<'a>(state: &'a State, vendor: &str, mpn: &str) -> Option<&'a Part> {
+    state
+        .db
+        .parts
+        .iter()
+        .find(|p| p.vendor == *vendor && p.mpn.eq_ignore_ascii_case(mpn))
+}
+
+fn filters(q: &str) -> Result<Vec<Filter>, String> {
+    search::split_query(q)
+        .iter()
+        .map(|f| Filter::parse(f).map_err(|e| e.to_string()))
+        .collect()
+}
+
+// offset and limit of a listing from the query string
+fn window(query: &HashMap<String, String>) -> Result<(usize, usize), String> {
+    let number = |key: &str, default: usize| match query.get(key) {
+        Some(v) => v.parse().map_err(|_| format!("{} has to be a number", key)),
+        None => Ok(default),
+    };
+    Ok((number("offset", 0)?, number("limit", LIMIT)?))
+}
+
+fn categories(state: &State) -> BTreeMap<String, usize> {
+    let mut m = BTreeMap::new();
+    for p in &state.db.parts {
+        let c = p.category.clone().unwrap_or_else(|| UNCATEGORISED.into());
+        *m.entry(c).or_insert(0) += 1;
+    }
+    m
+}
+
+// part counts per unified category, in the order of the taxonomy
+fn unified(state: &State) -> Vec<(&'static str, usize)> {
+    taxonomy::categories()
+        .into_iter()
+        .map(|c| {
+            let n = state
+                .db
+                .parts
+                .iter()
+                .filter(|p| p.unified_category.as_deref() == Some(c))
+                .count();
+            (c, n)
+        })
+        .filter(|(_, n)| *n > 0)
+        .collect()
+}
+
+fn page_index(state: &State) -> String {
+    let mut s = String::from("<h1>llh</h1>");
+    s += &search_forms("", "");
+    s += "<h2>Categories</h2><ul>";
+    for (c, n) in unified(state) {
+        s += &format!(
+            "<li><a href=\"/category?name={}\">{}</a> ({})</li>",
+            encode(c),
+            escape(c),
+            n
+        );
+    }
+    s += "</ul><h2>Vendor categories</h2>";
+    for (vendor, tree) in &state.db.categories {
+        s += &tree_list(vendor, tree);
+    }
+
+    // parts without a place in any tree
+    if let Some(n) = categories(state).get(UNCATEGORISED) {
+        s += &format!(
+            "<p><a href=\"/category?name={}\">{}</a> ({})</p>",
+            encode(UNCATEGORISED),
+            UNCATEGORISED,
+            n
+        );
+    }
+    s
+}
+
+fn tree_list(vendor: &str, c: &Category) -> String {
+    let mut s = format!(
+        "<li><a href=\"{}\">{}</a> ({})",
+        tree_link(vendor, &c.path),
+        escape(&c.name),
+        c.total()
+    );
+    if !c.children.is_empty() {
+        s += "<ul>";
+        for child in &c.children {
+            s += &tree_list(vendor, child);
+        }
+        s += "</ul>";
+    }
+    s += "</li>";
+
+    // the root has no list around it
+    if c.path.is_empty() {
+        format!("<ul>{}</ul>", s)
+    } else {
+        s
+    }
+}
+
+fn tree_link(vendor: &str, path: &[String]) -> String {
+    format!(
+        "/category?vendor={}&path={}",
+        encode(vendor),
+        encode(&category::join(path))
+    )
+}
+
+// llh / vendor / top category / ... with every level linked
+fn breadcrumbs(vendor: &str, path: &[String]) -> String {
+    let mut s = format!(
+        "<a href=\"/\">llh</a> / <a href=\"{}\">{}</a>",
+        tree_link(vendor, &[]),
+        escape(vendor)
+    );
+    for i in 0..path.len() {
+        s += &format!(
+            " / <a href=\"{}\">{}</a>",
+            tree_link(vendor, &path[..=i]),
+            escape(&path[i])
+        );
+    }
+    s
+}
+
+fn page_tree(state: &State, vendor: &str, c: &Category) -> String {
+    let mut s = format!(
+        "<p>{}</p><h1>{}</h1>",
+        breadcrumbs(vendor, &c.path),
+        escape(&c.name)
+    );
+
+    if !c.children.is_empty() {
+        s += "<ul>";
+        for child in &c.children {
+            s += &format!(
+                "<li><a href=\"{}\">{}</a> ({})</li>",
+                tree_link(vendor, &child.path),
+                escape(&child.name),
+                child.total()
+            );
+        }
+        s += "</ul>";
+    }
+
+    let parts: Vec<&Part> = state
+        .db
+        .parts
+        .iter()
+        .filter(|p| {
+            p.vendor == vendor
+                && std::iter::once(&p.category_path)
+                    .chain(&p.also)
+                    .any(|x| x.starts_with(&c.path))
+        })
+        .collect();
+    s + &part_table(&parts)
+}
+
+fn page_category(state: &State, name: &str) -> String {
+    let parts: Vec<&Part> = state
+        .db
+        .parts
+        .iter()
+        .filter(|p| {
+            p.category.as_deref().unwrap_or(UNCATEGORISED) == name
+                || p.unified_category.as_deref() == Some(name)
+        })
+        .collect();
+
+    format!(
+        "<p><a href=\"/\">llh</a></p><h1>{}</h1>{}",
+        escape(name),
+        part_table(&parts)
+    )
+}
+
+fn page_part(state: &State, p: &Part) -> String {
+    let mut s = if p.category_path.is_empty() {
+        format!(
+            "<p><a href=\"/\">llh</a> / <a href=\"/category?name={}\">{}</a></p>",
+            encode(p.category.as_deref().unwrap_or(UNCATEGORISED)),
+            escape(p.category.as_deref().unwrap_or(UNCATEGORISED))
+        )
+    } else {
+        format!("<p>{}</p>", breadcrumbs(&p.vendor, &p.category_path))
+    };
+    for path in &p.also {
+        s += &format!("<p>Also in {}</p>", breadcrumbs(&p.vendor, path));
+    }
+    s += &format!("<h1>{}</h1>", escape(&p.mpn));
+
+    if let Some(u) = &p.unified_category {
+        s += &format!(
+            "<p>Category: <a href=\"/category?name={}\">{}</a></p>",
+            encode(u),
+            escape(u)
+        );
+    }
+
+    s += "<h2>Documents</h2><ul>";
+    if let Some(ds) = p.datasheet(&state.root) {
+        // the number and revision the PDF gives itself, once indexed
+        let about = match &p.datasheet_meta {
+            Some(m) => [
+                m.number.clone(),
+                m.revision.as_ref().map(|r| format!("rev {}", r)),
+                m.date.clone(),
+                Some(format!("{} pages", m.pages)),
+            ]
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(", "),
+            None => String::new(),
+        };
+        s += &format!(
+            "<li><a href=\"/{}\">Datasheet</a> {}</li>",
+            encode_path(&ds),
+            escape(&about)
+        );
+    }
+    if let Some(url) = &p.url {
+        s += &format!("<li><a href=\"{}\">Product page</a></li>", escape(url));
+    }
+    for d in state
+        .documents
+        .iter()
+        .filter(|d| d.vendor == p.vendor && d.parts.iter().any(|x| x.eq_ignore_ascii_case(&p.mpn)))
+    {
+        // the local copy if there is one
+        let href = match d.local_path().filter(|l| state.root.join(l).exists()) {
+            Some(l) => format!("/{}", encode_path(&l)),
+            None => d.absolute_url(),
+        };
+        s += &format!(
+            "<li><a href=\"{}\">{}</a> ({})</li>",
+            escape(&href),
+            escape(&d.title),
+            d.kind
+        );
+    }
+    s += "</ul>";
+
+    // from the end of the indexed datasheet, newest first
+    if let Some(m) = p.datasheet_meta.as_ref().filter(|m| !m.history.is_empty()) {
+        s += "<h2>Revision history</h2><table><tr><th>Revision</th><th>Date</th><th>Changes</th></tr>";
+        for e in &m.history {
+            s += &format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(e.revision.as_deref().unwrap_or("")),
+                escape(e.date.as_deref().unwrap_or("")),
+                e.changes
+                    .iter()
+                    .map(|c| escape(c))
+                    .collect::<Vec<String>>()
+                    .join("<br>")
+            );
+        }
+        s += "</table>";
+    }
+
+    if !p.models.is_empty() {
+        s += "<h2>Models</h2><ul>";
+        for m in &p.models {
+            s += &format!(
+                "<li><a href=\"/{}\">{}</a> ({})</li>",
+                encode_path(&m.path),
+                escape(m.path.rsplit('/').next().unwrap_or(&m.path)),
+                m.kind.name()
+            );
+        }
+        s += "</ul>";
+    }
+
+    if !p.cad.is_empty() {
+        s += "<h2>CAD</h2><table><tr><th>Package</th><th>Source</th><th>Contents</th></tr>";
+        for c in &p.cad {
+            let href = match &c.path {
+                Some(path) => format!("/{}", encode_path(path)),
+                None => c.absolute_url(),
+            };
+            s += &format!(
+                "<tr><td>{}</td><td><a href=\"{}\">{}</a>{}</td><td>{}</td></tr>",
+                escape(c.package.as_deref().unwrap_or("all")),
+                escape(&href),
+                escape(&c.portal),
+                if c.login { " (login)" } else { "" },
+                c.kinds
+                    .iter()
+                    .map(|k| k.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        s += "</table>";
+    }
+
+    if !p.packs.is_empty() {
+        s += "<h2>Device packs</h2><ul>";
+        for f in &p.packs {
+            s += &format!(
+                "<li><a href=\"/{}\">{}</a> ({}, {})</li>",
+                encode_path(&f.path),
+                escape(f.path.rsplit('/').next().unwrap_or(&f.path)),
+                f.kind.name(),
+                escape(&f.device)
+            );
+        }
+        s += "</ul>";
+    }
+
+    if !p.orderables.is_empty() {
+        s += "<h2>Orderables</h2><table><tr><th>Orderable</th><th>Package</th><th>Pins</th>\
+              <th>Footprint</th><th>Packing</th><th>Status</th></tr>";
+        for o in &p.orderables {
+            s += &format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&o.opn),
+                escape(o.package.as_deref().unwrap_or("")),
+                o.pins.map(|n| n.to_string()).unwrap_or_default(),
+                escape(
+                    &o.canonical
+                        .as_ref()
+                        .map(|c| c.to_string())
+                        .unwrap_or_default()
+                ),
+                escape(o.packing.as_deref().unwrap_or("")),
+                escape(o.status.as_deref().unwrap_or(""))
+            );
+        }
+        s += "</table>";
+    }
+
+    s += "<h2>Parameters</h2><table>";
+    for (name, param) in &p.params {
+        s += &format!(
+            "<tr><th>{}</th><td>{}</td><td>{}</td></tr>",
+            escape(name),
+            escape(&param.raw),
+            escape(param.source.as_deref().unwrap_or(""))
+        );
+    }
+    s += "</table>";
+
+    // the families disagreed on these, the value above is the one the merge policy kept
+    if !p.conflicts.is_empty() {
+        s += "<h2>Conflicting values</h2><table><tr><th>Parameter</th><th>Family</th>\
+              <th>Value</th></tr>";
+        for c in &p.conflicts {
+            for (source, v) in &c.values {
+                let v = match v {
+                    serde_json::Value::String(x) => x.clone(),
+                    x => x.to_string(),
+                };
+                s += &format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&c.param),
+                    escape(source),
+                    escape(&v)
+                );
+            }
+        }
+        s += "</table>";
+    }
+    s
+}
+
+fn page_search(state: &State, q: &str) -> String {
+    let mut s = format!("<p><a href=\"/\">llh</a></p>{}", search_forms(q, ""));
+    match filters(q) {
+        Err(why) => s += &format!("<p>{}</p>", escape(&why)),
+        Ok(f) => {
+            let parts = search::search(&state.db, &f);
+            if parts.len() > LIMIT {
+                s += &format!("<p>{} parts, the first {}</p>", parts.len(), LIMIT);
+            } else {
+                s += &format!("<p>{} parts</p>", parts.len());
+            }
+            s += &part_table(&parts[..parts.len().min(LIMIT)]);
+        }
+    }
+    s
+}
+
+fn page_grep(state: &State, q: &str) -> String {
+    let mut s = format!("<p><a href=\"/\">llh</a></p>{}<ul>", search_forms("", q));
+    for hit in state.index.search(q, None, 100) {
+        s += &format!(
+            "<li><a href=\"/{}#page={}\">{}</a> page {}<br><span class=\"snippet\">{}</span></li>",
+            encode_path(&hit.path),
+            hit.page,
+            escape(&hit.path),
+            hit.page,
+            escape(&hit.snippet)
+        );
+    }
+    s + "</ul>"
+}
+
+fn search_forms(filters: &str, words: &str) -> String {
+    format!(
+        "<form action=\"/search\"><input type=\"text\" name=\"q\" value=\"{}\" \
+         placeholder=\"gbw&gt;10MHz package~SOIC\"> <input type=\"submit\" value=\"Search parts\"></form>\
+         <form action=\"/grep\"><input type=\"text\" name=\"q\" value=\"{}\" \
+         placeholder=\"words in the datasheets\"> <input type=\"submit\" value=\"Search documents\"></form>",
+        escape(filters),
+        escape(words)
+    )
+}
+
+fn part_table(parts: &[&Part]) -> String {
+    let mut s = String::from("<table><tr><th>Vendor</th><th>Part</th><th>Category</th></tr>");
+    for p in parts {
+        s += &format!(
+            "<tr><td>{}</td><td><a href=\"/part/{}/{}\">{}</a></td><td>{}</td></tr>",
+            escape(&p.vendor),
+            encode_segment(&p.vendor),
+            encode_segment(&p.mpn),
+            escape(&p.mpn),
+            escape(p.category.as_deref().unwrap_or(""))
+        );
+    }
+    s + "</table>"
+}
+
+// serve a file below the archive, refusing anything that would leave it
+// the file below the root a request names, none if it would leave the root
+fn below_root(path: &str) -> Option<&Path> {
+    let rel = Path::new(path.trim_start_matches('/'));
+    if rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        Some(rel)
+    } else {
+        None
+    }
+}
+
+async fn file(state: &State, path: &str) -> Response<Body> {
+    let rel = match below_root(path) {
+        Some(rel) => rel,
+        None => return status(StatusCode::FORBIDDEN),
+    };
+
+    let content_type = match rel.extension().and_then(|e| e.to_str()) {
+        Some("pdf") => "application/pdf",
+        Some("json") => "application/json",
+        Some("svd") | Some("pdsc") => "application/xml",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    };
+
+    match tokio::fs::read(state.root.join(rel)).await {
+        Err(_) => status(StatusCode::NOT_FOUND),
+        Ok(body) => Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap(),
+    }
+}
+
+fn html(title: &str, body: &str) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Body::from(format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title>\
+             <style>{}</style></head><body>{}</body></html>",
+            escape(title),
+            STYLE,
+            body
+        )))
+        .unwrap()
+}
+
+fn json<T: Serialize + ?Sized>(v: &T) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_string(v).unwrap()))
+        .unwrap()
+}
+
+fn text(code: StatusCode, s: &str) -> Response<Body> {
+    Response::builder()
+        .status(code)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(s.to_string()))
+        .unwrap()
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    text(code, code.canonical_reason().unwrap_or(""))
+}
+
+fn query(q: Option<&str>) -> HashMap<String, String> {
+    let url = format!("http://localhost/?{}", q.unwrap_or(""));
+    Url::parse(&url)
+        .map(|u| u.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn encode(s: &str) -> String {
+    Url::parse_with_params("http://localhost/", &[("x", s)])
+        .map(|u| u.query().unwrap_or("x=")[2..].to_string())
+        .unwrap_or_default()
+}
+
+// form encoding writes spaces as '+', which path segments don't decode
+fn encode_segment(s: &str) -> String {
+    encode(s).replace('+', "%20")
+}
+
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(encode_segment)
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoding() {
+        assert_eq!(percent_decode("/part/ti/OPA2197"), "/part/ti/OPA2197");
+        assert_eq!(percent_decode("a%20b%2Fc"), "a b/c");
+        assert_eq!(percent_decode("%C2%B5V"), "µV");
+        // not an escape
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(encode("a b&c=d"), "a+b%26c%3Dd");
+        assert_eq!(
+            encode_path("pdf/ti/lit/SBOA 123#1.pdf"),
+            "pdf/ti/lit/SBOA%20123%231.pdf"
+        );
+        // a path survives the way there and back
+        let path = "files/st/ecad/STM32F4/x?y%.zip";
+        assert_eq!(percent_decode(&encode_path(path)), path);
+    }
+
+    #[test]
+    fn files_stay_below_the_root() {
+        assert_eq!(
+            below_root("/pdf/ti/gpn/OPA2197.pdf"),
+            Some(Path::new("pdf/ti/gpn/OPA2197.pdf"))
+        );
+        assert_eq!(below_root("/pdf/../../etc/passwd"), None);
+        assert_eq!(below_root("/pdf/./x.pdf"), Some(Path::new("pdf/x.pdf")));
+        assert_eq!(below_root("//etc/passwd"), Some(Path::new("etc/passwd")));
+        // escaped dots are decoded before the check
+        assert_eq!(
+            below_root(&percent_decode("/pdf/%2e%2e/%2E%2E/etc/passwd")),
+            None
+        );
+        assert_eq!(below_root(&percent_decode("/files/..%2f..%2fetc")), None);
+    }
+}
@@ -0,0 +1,430 @@
+// taxonomy maps the vendor categories ("Op amps", "Operational Amplifiers") onto one unified
+// set, so search and alternates can compare parts across vendors, and the vendor parameter
+// names ("GBW (Typ) (MHz)", "GBP typ (MHz)") onto one name per quantity
+
+use crate::db::{open_json, slug};
+use std::collections::HashMap;
+use std::path::Path;
+
+// vendor category names that the keyword rules below get wrong or miss
+const VENDOR_MAP: &[(&str, &str, &str)] = &[
+    ("ti", "Op amps", "Operational amplifiers"),
+    (
+        "ti",
+        "Precision op amps (Vos<1mV)",
+        "Operational amplifiers",
+    ),
+    (
+        "ti",
+        "Isolated DC/DC converters & modules",
+        "DC/DC converters",
+    ),
+    ("ti", "Isolated gate drivers", "Gate drivers"),
+    ("ti", "Digital isolators", "Isolators"),
+    (
+        "st",
+        "Operational Amplifiers (Op Amps)",
+        "Operational amplifiers",
+    ),
+    ("st", "STM32 32-bit Arm Cortex MCUs", "Microcontrollers"),
+    ("st", "STM8 8-bit MCUs", "Microcontrollers"),
+    ("st", "Galvanically Isolated Gate Drivers", "Gate drivers"),
+];
+
+// unified categories and the keywords that identify them, the more specific ones come first
+const RULES: &[(&str, &[&str])] = &[
+    ("Instrumentation amplifiers", &["instrumentation amp"]),
+    // "Current sensors" are Hall-effect sensors
+    (
+        "Current sense amplifiers",
+        &["current sense amp", "current-sense amp"],
+    ),
+    (
+        "Audio amplifiers",
+        &["audio amp", "audio power amp", "class-d", "class d"],
+    ),
+    ("Comparators", &["comparator"]),
+    ("Operational amplifiers", &["op amp", "operational amp"]),
+    ("Gate drivers", &["gate driver"]),
+    ("Motor drivers", &["motor driver", "motor control"]),
+    ("LED drivers", &["led driver"]),
+    (
+        "Analog-to-digital converters",
+        &["analog-to-digital", "adc", "a/d converter"],
+    ),
+    (
+        "Digital-to-analog converters",
+        &["digital-to-analog", "dac", "d/a converter"],
+    ),
+    (
+        "Voltage references",
+        &["voltage reference", "shunt reference", "series reference"],
+    ),
+    (
+        "Linear regulators",
+        &["ldo", "linear regulator", "linear voltage regulator"],
+    ),
+    (
+        "DC/DC converters",
+        &[
+            "dc/dc",
+            "dc-dc",
+            "buck",
+            "boost",
+            "switching regulator",
+            "step-down",
+            "step-up",
+        ],
+    ),
+    ("Battery management", &["battery", "charger", "fuel gauge"]),
+    (
+        "Supervisors and reset ICs",
+        &["supervisor", "reset ic", "voltage detector", "watchdog"],
+    ),
+    (
+        "Power switches",
+        &[
+            "load switch",
+            "power switch",
+            "ideal diode",
+            "efuse",
+            "hot swap",
+        ],
+    ),
+    ("Isolators", &["isolator", "digital isolat"]),
+    (
+        "CAN and LIN transceivers",
+        &["can transceiver", "can fd", "lin transceiver", "can & lin"],
+    ),
+    (
+        "RS-485 and RS-232 transceivers",
+        &["rs-485", "rs-422", "rs-232", "rs485", "rs232"],
+    ),
+    ("USB ICs", &["usb"]),
+    ("Ethernet ICs", &["ethernet"]),
+    (
+        "I2C and level translators",
+        &["i2c", "level shift", "level translat", "voltage translat"],
+    ),
+    (
+        "Switches and multiplexers",
+        &["multiplexer", "analog switch", "mux"],
+    ),
+    (
+        "Clocks and timing",
+        &[
+            "clock",
+            "oscillator",
+            "timer",
+            "real-time clock",
+            "rtc",
+            "pll",
+        ],
+    ),
+    ("Microcontrollers", &["microcontroller", "mcu"]),
+    ("Processors", &["processor", "mpu", "dsp"]),
+    (
+        "Memory",
+        &["eeprom", "flash memor", "fram", "sram", "memory"],
+    ),
+    ("MOSFETs", &["mosfet"]),
+    (
+        "ESD and surge protection",
+        &["esd", "tvs", "transient voltage", "surge"],
+    ),
+    ("Temperature sensors", &["temperature sensor"]),
+    (
+        "Sensors",
+        &[
+            "sensor",
+            "mems",
+            "accelerometer",
+            "gyroscope",
+            "magnetometer",
+            "humidity",
+            "pressure",
+        ],
+    ),
+    (
+        "Wireless",
+        &[
+            "wireless",
+            "bluetooth",
+            "sub-1 ghz",
+            "zigbee",
+            "wi-fi",
+            "nfc",
+            "rfid",
+            "lora",
+            "rf transceiver",
+        ],
+    ),
+    ("Display and DLP", &["dlp", "display"]),
+    ("Logic", &["logic", "flip-flop", "buffer", "inverter"]),
+    ("Amplifiers", &["amplifier"]),
+    (
+        "Power management",
+        &["power management", "pmic", "regulator"],
+    ),
+];
+
+// unified parameter names and the slugs that start the vendor names, without the min/typ/max
+// qualifier, the more specific ones come first
+const PARAMS: &[(&str, &[&str])] = &[
+    (
+        "offset_drift",
+        &[
+            "offset_drift",
+            "input_offset_voltage_drift",
+            "input_offset_drift",
+            "vos_drift",
+            "vio_drift",
+            "dvos_dt",
+            "dvio_dt",
+        ],
+    ),
+    (
+        "offset_voltage",
+        &["vos", "vio", "input_offset_voltage", "offset_voltage"],
+    ),
+    (
+        "gbw",
+        &["gbw", "gbp", "gain_bandwidth", "gain_bandwidth_product"],
+    ),
+    ("slew_rate", &["slew_rate", "sr"]),
+    ("cmrr", &["cmrr", "common_mode_rejection_ratio"]),
+    (
+        "psrr",
+        &[
+            "psrr",
+            "svr",
+            "power_supply_rejection_ratio",
+            "supply_voltage_rejection_ratio",
+        ],
+    ),
+    (
+        "supply_current",
+        &["iq", "quiescent_current", "supply_current", "icc"],
+    ),
+    (
+        "supply_voltage",
+        &["total_supply_voltage", "supply_voltage", "vcc", "vs"],
+    ),
+    ("input_bias_current", &["input_bias_current", "ib", "iib"]),
+    (
+        "channels",
+        &["number_of_channels", "nb_of_channels", "channels"],
+    ),
+    ("rail_to_rail", &["rail_to_rail"]),
+];
+
+#[derive(Default)]
+pub struct Taxonomy {
+    // vendor -> lowercase vendor category -> unified category, from json/taxonomy.json
+    overrides: HashMap<String, HashMap<String, String>>,
+}
+
+impl Taxonomy {
+    pub fn load(root: &Path) -> Taxonomy {
+        let raw: HashMap<String, HashMap<String, String>> =
+            open_json(&root.join("json/taxonomy.json")).unwrap_or_default();
+
+        Taxonomy {
+            overrides: raw
+                .into_iter()
+                .map(|(v, m)| {
+                    (
+                        v,
+                        m.into_iter().map(|(k, u)| (k.to_lowercase(), u)).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    // the unified category for a vendor category, overrides first, then the mapping table,
+    // then the keyword rules
+    pub fn classify(&self, vendor: &str, category: &str) -> Option<String> {
+        let lower = category.trim().to_lowercase();
+
+        if let Some(u) = self.overrides.get(vendor).and_then(|m| m.get(&lower)) {
+            return Some(u.clone());
+        }
+
+        if let Some((_, _, u)) = VENDOR_MAP
+            .iter()
+            .find(|(v, c, _)| *v == vendor && c.eq_ignore_ascii_case(category.trim()))
+        {
+            return Some(u.to_string());
+        }
+
+        // pad with spaces so short keywords like "adc" only match whole words
+        let padded = format!(" {} ", lower.replace(['(', ')', ',', '&', '-', '/'], " "));
+        RULES
+            .iter()
+            .find(|(_, keywords)| {
+                keywords.iter().any(|k| {
+                    if k.len() <= 4 {
+                        padded.contains(&format!(" {} ", k))
+                            || padded.contains(&format!(" {}s ", k))
+                    } else {
+                        lower.contains(k)
+                    }
+                })
+            })
+            .map(|(u, _)| u.to_string())
+    }
+}
+
+// every unified category, in the order of the rules
+pub fn categories() -> Vec<&'static str> {
+    RULES.iter().map(|(u, _)| *u).collect()
+}
+
+// the name parameters are compared by across vendors, "GBW (Typ) (MHz)" of TI and "GBP typ
+// (MHz)" of ST are both gbw_typ, names without a rule keep their slug
+pub fn parameter(name: &str) -> String {
+    let slug = slug(name);
+    let mut qualifier = None;
+    let words: Vec<&str> = slug
+        .split('_')
+        .filter(|w| {
+            let q = match *w {
+                "min" | "typ" | "max" => *w,
+                "nom" => "typ",
+                _ => return true,
+            };
+            qualifier = qualifier.or(Some(q));
+            false
+        })
+        .collect();
+    let rest = words.join("_");
+
+    let name = PARAMS
+        .iter()
+        .find(|(_, starts)| {
+            starts
+                .iter()
+                .any(|s| rest == *s || rest.starts_with(&format!("{}_", s)))
+        })
+        .map(|(u, _)| u.to_string())
+        .unwrap_or(rest);
+    match qualifier {
+        Some(q) => format!("{}_{}", name, q),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(vendor: &str, category: &str) -> Option<String> {
+        Taxonomy::default().classify(vendor, category)
+    }
+
+    #[test]
+    fn vendor_names_agree() {
+        let ti = classify("ti", "Op amps");
+        assert_eq!(ti.as_deref(), Some("Operational amplifiers"));
+        assert_eq!(classify("st", "Operational Amplifiers (Op Amps)"), ti);
+        assert_eq!(
+            classify("st", "STM32 32-bit Arm Cortex MCUs").as_deref(),
+            Some("Microcontrollers")
+        );
+    }
+
+    #[test]
+    fn specific_rules_first() {
+        assert_eq!(
+            classify("ti", "Instrumentation amplifiers").as_deref(),
+            Some("Instrumentation amplifiers")
+        );
+        assert_eq!(
+            classify("ti", "Audio power amplifiers").as_deref(),
+            Some("Audio amplifiers")
+        );
+        assert_eq!(
+            classify("ti", "RF amplifiers").as_deref(),
+            Some("Amplifiers")
+        );
+    }
+
+    #[test]
+    fn current_sensors_are_no_amplifiers() {
+        assert_eq!(
+            classify("ti", "Current sense amplifiers").as_deref(),
+            Some("Current sense amplifiers")
+        );
+        assert_eq!(
+            classify("st", "Current-Sense Amplifiers").as_deref(),
+            Some("Current sense amplifiers")
+        );
+        assert_eq!(
+            classify("ti", "Current sensors").as_deref(),
+            Some("Sensors")
+        );
+    }
+
+    #[test]
+    fn short_keywords_are_words() {
+        assert_eq!(
+            classify("ti", "Precision ADCs").as_deref(),
+            Some("Analog-to-digital converters")
+        );
+        // "adc" in "Broadcast" is no ADC
+        assert_eq!(classify("ti", "Broadcast"), None);
+    }
+
+    #[test]
+    fn overrides_win() {
+        let mut t = Taxonomy::default();
+        t.overrides.insert(
+            String::from("st"),
+            vec![(String::from("op amps"), String::from("Comparators"))]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(t.classify("st", "Op Amps").as_deref(), Some("Comparators"));
+        assert_eq!(
+            t.classify("ti", "Op amps").as_deref(),
+            Some("Operational amplifiers")
+        );
+    }
+
+    #[test]
+    fn parameter_names_agree() {
+        assert_eq!(parameter("GBW (Typ) (MHz)"), "gbw_typ");
+        assert_eq!(parameter("GBP typ (MHz)"), "gbw_typ");
+        assert_eq!(
+            parameter("Vos (offset voltage at 25°C) (Max) (mV)"),
+            "offset_voltage_max"
+        );
+        assert_eq!(
+            parameter("Input Offset Voltage (25°C) max (mV)"),
+            "offset_voltage_max"
+        );
+        assert_eq!(parameter("Offset drift (Typ) (µV/°C)"), "offset_drift_typ");
+        assert_eq!(
+            parameter("Input Offset Voltage Drift typ (µV/°C)"),
+            "offset_drift_typ"
+        );
+        assert_eq!(parameter("Iq per channel (Typ) (mA)"), "supply_current_typ");
+        assert_eq!(
+            parameter("Supply Current / Op (typ) (µA)"),
+            "supply_current_typ"
+        );
+        assert_eq!(
+            parameter("Total supply voltage (+5V=5, +/-5V=10) (Max) (V)"),
+            "supply_voltage_max"
+        );
+        assert_eq!(parameter("Supply Voltage (V) max"), "supply_voltage_max");
+        assert_eq!(
+            parameter("Supply Voltage Rejection Ratio min (dB)"),
+            "psrr_min"
+        );
+        // whole words only
+        assert_eq!(parameter("Sram (kB)"), "sram");
+        assert_eq!(parameter("Program memory size (kB)"), "program_memory_size");
+    }
+}
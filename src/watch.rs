@@ -0,0 +1,613 @@
+// watch reports what changed between crawls for a list of parts, the list is a text file with
+// one MPN per line, # starts a comment, the state of the parts at the last digest is kept in
+// json/watch/snapshot.json
+
+use crate::db::{self, Database, Part};
+use crate::document::{self, Document, DocumentKind};
+use crate::errata::{ChangeKind, History};
+use crate::fulltext::{self, DocEntry};
+use crate::history::{self, Entry};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// the list the crawlers make a digest for after every crawl, if there is one
+pub const LIST: &str = "watchlist.txt";
+pub const SNAPSHOT: &str = "json/watch/snapshot.json";
+pub const DIGESTS: &str = "digests";
+// the digest also goes to these if they exist, a mail per digest and the payload of a webhook
+pub const SPOOL: &str = "spool";
+pub const WEBHOOK: &str = "webhook";
+// who the mails in the spool are from and to, {"from": "...", "to": "..."}
+pub const MAIL: &str = "json/watch/mail.json";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Mail {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ErrataChange {
+    // as it is in the list
+    pub mpn: String,
+    pub vendor: String,
+    pub part: String,
+    pub title: String,
+    pub change: ChangeKind,
+    pub revision: Option<String>,
+    pub date: Option<String>,
+    pub url: String,
+    // the crawl that found it
+    pub seen: String,
+}
+
+pub fn read_list(path: &Path) -> io::Result<Vec<String>> {
+    let text = fs::read_to_string(path)?;
+    let mut out: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let mpn = line.split('#').next().unwrap_or("").trim();
+        if !mpn.is_empty() && !out.iter().any(|m| m.eq_ignore_ascii_case(mpn)) {
+            out.push(mpn.to_string());
+        }
+    }
+    Ok(out)
+}
+
+// the errata the parts gained after since, by default since the crawl before the last one of
+// each vendor, the MPNs that aren't in the database are returned as well
+pub fn errata(
+    db: &Database,
+    root: &Path,
+    mpns: &[String],
+    since: Option<&str>,
+) -> (Vec<ErrataChange>, Vec<String>) {
+    let mut histories: HashMap<String, History> = HashMap::new();
+    let mut out = Vec::new();
+    let mut unknown = Vec::new();
+
+    for mpn in mpns {
+        let part = match db.resolve(mpn) {
+            Some((p, _)) => p,
+            None => {
+                unknown.push(mpn.clone());
+                continue;
+            }
+        };
+        let history = histories
+            .entry(part.vendor.clone())
+            .or_insert_with(|| History::load(root, &part.vendor));
+        let since = match since.or_else(|| history.previous_crawl().map(|c| c.as_str())) {
+            Some(s) => s,
+            None => continue,
+        };
+
+        for c in history.changes(&part.mpn, since) {
+            out.push(ErrataChange {
+                mpn: mpn.clone(),
+                vendor: part.vendor.clone(),
+                part: part.mpn.clone(),
+                title: c.erratum.title.clone(),
+                change: c.change,
+                revision: c.revision.revision.clone(),
+                date: c.revision.date.clone(),
+                url: c.revision.url.clone(),
+                seen: c.revision.seen.clone(),
+            });
+        }
+    }
+
+    (out, unknown)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DocumentState {
+    pub title: String,
+    pub kind: DocumentKind,
+    pub url: String,
+    pub revision: Option<String>,
+    pub date: Option<String>,
+    // the revision history of the indexed PDF, not kept in the snapshot
+    #[serde(skip)]
+    pub history: Vec<Entry>,
+}
+
+// what a watched part looked like at the last digest
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Snapshot {
+    pub status: Option<String>,
+    // raw values by name
+    pub params: BTreeMap<String, String>,
+    // by Document::key
+    pub documents: BTreeMap<String, DocumentState>,
+    // as the indexed PDF has it, the product pages don't list the datasheet revisions
+    #[serde(default)]
+    pub datasheet: Option<DocumentState>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct State {
+    pub taken: Option<String>,
+    // by vendor/mpn
+    pub parts: BTreeMap<String, Snapshot>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Lifecycle {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    Document {
+        document: DocumentState,
+        // the revision or date it replaces, none for a new document
+        previous: Option<String>,
+        // what the revision history says changed since
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        changes: Vec<String>,
+    },
+    Param {
+        name: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PartDigest {
+    pub mpn: String,
+    pub vendor: String,
+    pub part: String,
+    pub events: Vec<Event>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Digest {
+    pub created: String,
+    // the last digest, none on the first
+    pub since: Option<String>,
+    pub watched: usize,
+    // only the parts with changes
+    pub parts: Vec<PartDigest>,
+    // watched for the first time, the baseline for the next digest
+    pub added: Vec<String>,
+    pub unknown: Vec<String>,
+}
+
+impl State {
+    pub fn load(root: &Path) -> State {
+        db::open_json(&root.join(SNAPSHOT)).unwrap_or_default()
+    }
+}
+
+impl DocumentState {
+    fn label(&self) -> String {
+        match (&self.revision, &self.date) {
+            (Some(r), Some(d)) => format!("rev {} ({})", r, d),
+            (Some(r), None) => format!("rev {}", r),
+            (None, Some(d)) => d.clone(),
+            (None, None) => String::from("undated"),
+        }
+    }
+}
+
+// manifest is that of the PDF index, for the revision histories
+pub fn snapshot(part: &Part, docs: &[Document], manifest: &BTreeMap<String, DocEntry>) -> Snapshot {
+    Snapshot {
+        status: part.status().map(String::from),
+        params: part
+            .params
+            .iter()
+            .filter(|(name, _)| !db::STATUS_FIELDS.contains(&db::slug(name).as_str()))
+            // those read from the datasheet change with its revision, which is reported instead
+            .filter(|(_, p)| p.source.is_none())
+            .map(|(name, p)| (name.clone(), p.raw.clone()))
+            .collect(),
+        documents: docs
+            .iter()
+            .filter(|d| d.parts.iter().any(|p| p.eq_ignore_ascii_case(&part.mpn)))
+            .map(|d| {
+                (
+                    d.key(),
+                    DocumentState {
+                        title: d.title.clone(),
+                        kind: d.kind,
+                        url: d.url.clone(),
+                        revision: d.revision.clone(),
+                        date: d.date.clone(),
+                        history: d
+                            .local_path()
+                            .and_then(|p| manifest.get(&p))
+                            .and_then(|e| e.meta.as_ref())
+                            .map(|m| m.history.clone())
+                            .unwrap_or_default(),
+                    },
+                )
+            })
+            .collect(),
+        datasheet: part.datasheet_meta.as_ref().map(|m| DocumentState {
+            title: m
+                .number
+                .clone()
+                .unwrap_or_else(|| format!("{} datasheet", part.mpn)),
+            kind: DocumentKind::Datasheet,
+            url: part.datasheet_path().unwrap_or_default(),
+            revision: m.revision.clone(),
+            date: m.date.clone(),
+            history: m.history.clone(),
+        }),
+    }
+}
+
+// lifecycle first, then new and revised documents, then parameters, documents that are no
+// longer linked aren't news
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Vec<Event> {
+    let mut out = Vec::new();
+    if old.status != new.status {
+        out.push(Event::Lifecycle {
+            from: old.status.clone(),
+            to: new.status.clone(),
+        });
+    }
+
+    let datasheet = new.datasheet.as_ref().map(|d| (d, old.datasheet.as_ref()));
+    let documents = new
+        .documents
+        .iter()
+        .map(|(key, d)| (d, old.documents.get(key)));
+    for (d, before) in datasheet.into_iter().chain(documents) {
+        match before {
+            None => out.push(Event::Document {
+                document: d.clone(),
+                previous: None,
+                changes: Vec::new(),
+            }),
+            Some(o) if o.revision != d.revision || o.date != d.date || o.url != d.url => {
+                let changes = history::since(&d.history, o.revision.as_deref(), o.date.as_deref())
+                    .iter()
+                    .flat_map(|e| {
+                        let rev = e.revision.clone().unwrap_or_else(|| String::from("?"));
+                        e.changes.iter().map(move |c| format!("rev {}: {}", rev, c))
+                    })
+                    .collect();
+                out.push(Event::Document {
+                    document: d.clone(),
+                    previous: Some(o.label()),
+                    changes,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    let names: BTreeSet<&String> = old.params.keys().chain(new.params.keys()).collect();
+    for name in names {
+        let (from, to) = (old.params.get(name), new.params.get(name));
+        if from != to {
+            out.push(Event::Param {
+                name: name.clone(),
+                from: from.cloned(),
+                to: to.cloned(),
+            });
+        }
+    }
+
+    out
+}
+
+// compare the watched parts with the last snapshot, returns the digest and the new state to be
+// saved once the digest is out
+pub fn digest(db: &Database, root: &Path, mpns: &[String], now: &str) -> (Digest, State) {
+    let old = State::load(root);
+    let mut state = State {
+        taken: Some(now.to_string()),
+        parts: BTreeMap::new(),
+    };
+    let mut docs: HashMap<String, Vec<Document>> = HashMap::new();
+    let manifest = fulltext::manifest(root);
+    let mut digest = Digest {
+        created: now.to_string(),
+        since: old.taken.clone(),
+        watched: mpns.len(),
+        parts: Vec::new(),
+        added: Vec::new(),
+        unknown: Vec::new(),
+    };
+
+    for mpn in mpns {
+        let part = match db.resolve(mpn) {
+            Some((p, _)) => p,
+            None => {
+                digest.unknown.push(mpn.clone());
+                continue;
+            }
+        };
+        let docs = docs
+            .entry(part.vendor.clone())
+            .or_insert_with(|| document::load(root, &part.vendor));
+        let key = format!("{}/{}", part.vendor, part.mpn);
+        let snap = snapshot(part, docs, &manifest);
+
+        match old.parts.get(&key) {
+            Some(o) => {
+                let events = diff(o, &snap);
+                if !events.is_empty() {
+                    digest.parts.push(PartDigest {
+                        mpn: mpn.clone(),
+                        vendor: part.vendor.clone(),
+                        part: part.mpn.clone(),
+                        events,
+                    });
+                }
+            }
+            None => digest.added.push(mpn.clone()),
+        }
+        state.parts.insert(key, snap);
+    }
+
+    (digest, state)
+}
+
+fn or_none(v: &Option<String>) -> &str {
+    v.as_deref().unwrap_or("none")
+}
+
+impl Digest {
+    pub fn subject(&self) -> String {
+        format!(
+            "llh watch: {} of {} parts changed",
+            self.parts.len(),
+            self.watched
+        )
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "# Watchlist digest {}\n", self.created).unwrap();
+        match &self.since {
+            Some(since) => writeln!(
+                s,
+                "{} of {} watched parts changed since {}.",
+                self.parts.len(),
+                self.watched,
+                since
+            ),
+            None => writeln!(s, "First digest, nothing to compare to yet."),
+        }
+        .unwrap();
+
+        for p in &self.parts {
+            writeln!(s, "\n## {} ({} {})\n", p.mpn, p.vendor, p.part).unwrap();
+            for e in &p.events {
+                let line = match e {
+                    Event::Lifecycle { from, to } => {
+                        format!("lifecycle: {} -> {}", or_none(from), or_none(to))
+                    }
+                    Event::Document {
+                        document: d,
+                        previous: None,
+                        ..
+                    } => format!("new {}: [{}]({}) {}", d.kind, d.title, d.url, d.label()),
+                    Event::Document {
+                        document: d,
+                        previous: Some(prev),
+                        ..
+                    } => format!(
+                        "revised {}: [{}]({}) {}, was {}",
+                        d.kind,
+                        d.title,
+                        d.url,
+                        d.label(),
+                        prev
+                    ),
+                    Event::Param { name, from, to } => {
+                        format!("{}: {} -> {}", name, or_none(from), or_none(to))
+                    }
+                };
+                writeln!(s, "- {}", line).unwrap();
+                if let Event::Document { changes, .. } = e {
+                    for c in changes {
+                        writeln!(s, "  - {}", c).unwrap();
+                    }
+                }
+            }
+        }
+
+        if !self.added.is_empty() {
+            writeln!(s, "\nNewly watched: {}", self.added.join(", ")).unwrap();
+        }
+        if !self.unknown.is_empty() {
+            writeln!(s, "\nNot in the database: {}", self.unknown.join(", ")).unwrap();
+        }
+        s
+    }
+
+    // a message for the local mail spool, picked up by whatever delivers the mail
+    pub fn mail(&self, mail: &Mail) -> String {
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/markdown; charset=utf-8\r\n\r\n{}",
+            mail.from,
+            mail.to,
+            self.subject(),
+            Utc::now().to_rfc2822(),
+            self.markdown().replace('\n', "\r\n")
+        )
+    }
+
+    // the body a chat webhook takes, a sender POSTs the files as they come
+    pub fn webhook(&self) -> serde_json::Value {
+        serde_json::json!({ "text": self.markdown(), "digest": self })
+    }
+}
+
+// file name safe form of the digest time
+pub fn stamp(now: &str) -> String {
+    now.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(15)
+        .collect()
+}
+
+// write the digest to dir as Markdown and JSON, returns the path of the Markdown
+pub fn write(dir: &Path, digest: &Digest) -> io::Result<String> {
+    fs::create_dir_all(dir)?;
+    let name = stamp(&digest.created);
+    let md = dir.join(format!("{}.md", name));
+    fs::write(&md, digest.markdown())?;
+    fs::write(
+        dir.join(format!("{}.json", name)),
+        serde_json::to_string_pretty(digest)?,
+    )?;
+    Ok(md.display().to_string())
+}
+
+pub fn spool(dir: &Path, mail: &Mail, digest: &Digest) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(format!("{}.eml", stamp(&digest.created))),
+        digest.mail(mail),
+    )
+}
+
+pub fn webhook(dir: &Path, digest: &Digest) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(format!("{}.json", stamp(&digest.created))),
+        serde_json::to_string_pretty(&digest.webhook())?,
+    )
+}
+
+pub fn save(root: &Path, state: &State) -> io::Result<()> {
+    let path = root.join(SNAPSHOT);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string(state)?)
+}
+
+// the digest into each of the directories given and then the snapshot, a failed write leaves the
+// snapshot as it was so the next digest has the changes again
+pub fn publish(
+    root: &Path,
+    digest: &Digest,
+    state: &State,
+    out: Option<&Path>,
+    mail: Option<(&Path, &Mail)>,
+    hook: Option<&Path>,
+) -> io::Result<()> {
+    if let Some(dir) = out {
+        write(dir, digest)?;
+    }
+    if let Some((dir, mail)) = mail {
+        spool(dir, mail, digest)?;
+    }
+    if let Some(dir) = hook {
+        webhook(dir, digest)?;
+    }
+    save(root, state)
+}
+
+// run by the crawlers after they built the database, a digest of watchlist.txt into digests/
+// and the spool and webhook directories that exist, the spool only with json/watch/mail.json
+pub fn after_crawl(root: &Path) {
+    let mpns = match read_list(&root.join(LIST)) {
+        Ok(l) => l,
+        Err(_) => return,
+    };
+    let db = Database::load(root);
+    let (digest, state) = digest(&db, root, &mpns, &Utc::now().to_rfc3339());
+
+    let written = write(&root.join(DIGESTS), &digest).and_then(|md| {
+        if root.join(SPOOL).is_dir() {
+            match db::open_json::<Mail>(&root.join(MAIL)) {
+                Some(mail) => spool(&root.join(SPOOL), &mail, &digest)?,
+                None => eprintln!("{} needs {} to know who to mail", SPOOL, MAIL),
+            }
+        }
+        if root.join(WEBHOOK).is_dir() {
+            webhook(&root.join(WEBHOOK), &digest)?;
+        }
+        save(root, &state)?;
+        Ok(md)
+    });
+    match written {
+        Ok(md) => println!("{}, see {}", digest.subject(), md),
+        Err(why) => eprintln!("couldn't write the watchlist digest: {}", why),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest() -> Digest {
+        Digest {
+            created: String::from("2024-05-01T10:00:00+00:00"),
+            since: None,
+            watched: 1,
+            parts: Vec::new(),
+            added: vec![String::from("OPA2197")],
+            unknown: Vec::new(),
+        }
+    }
+
+    fn state(taken: &str) -> State {
+        State {
+            taken: Some(taken.to_string()),
+            parts: BTreeMap::new(),
+        }
+    }
+
+    fn root(name: &str) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("llh-watch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn list() {
+        let root = root("list");
+        let path = root.join(LIST);
+        fs::write(&path, "OPA2197 # the op amp\n\n# TSV912\nopa2197\nLM358\n").unwrap();
+        assert_eq!(read_list(&path).unwrap(), vec!["OPA2197", "LM358"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn publish_saves_the_snapshot_last() {
+        let root = root("publish");
+        save(&root, &state("old")).unwrap();
+        let out = root.join(DIGESTS);
+        publish(&root, &digest(), &state("new"), Some(&out), None, None).unwrap();
+        assert_eq!(State::load(&root).taken.as_deref(), Some("new"));
+        assert!(out.join("20240501T100000.md").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_failed_write_keeps_the_old_snapshot() {
+        let root = root("failed");
+        save(&root, &state("old")).unwrap();
+        // a file where the webhook directory should be
+        let hook = root.join(WEBHOOK);
+        fs::write(&hook, "").unwrap();
+        let out = root.join(DIGESTS);
+        let written = publish(
+            &root,
+            &digest(),
+            &state("new"),
+            Some(&out),
+            None,
+            Some(&hook),
+        );
+        assert!(written.is_err());
+        assert_eq!(State::load(&root).taken.as_deref(), Some("old"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}